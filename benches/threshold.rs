@@ -0,0 +1,75 @@
+// benches/threshold.rs
+// Binarization throughput: the chunked `binarize_luma` against the per-pixel
+// float loop it replaced, on the payload's 256×192 frames and on 1080p.
+//
+//   cargo bench --bench threshold
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bad_apple::rectframes::binarize_luma;
+
+/// The pre-vectorization loop, kept as the baseline.
+fn binarize_scalar(buf: &[u8], invert: bool, th_mul: f32) -> (Vec<u8>, f32) {
+    let sum: u64 = buf.iter().map(|&v| v as u64).sum();
+    let th = (sum as f32) / (buf.len() as f32) * th_mul;
+
+    let mut frame = vec![0u8; buf.len()];
+    for (pi, &v) in buf.iter().enumerate() {
+        let mut on = (v as f32) < th;
+        if invert {
+            on = !on;
+        }
+        frame[pi] = if on { 1 } else { 0 };
+    }
+
+    (frame, th)
+}
+
+/// Deterministic noise (xorshift), so both sides see the same pixels.
+fn frame(w: usize, h: usize) -> Vec<u8> {
+    let mut x = 0x2545_f491_u32;
+    (0..w * h)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect()
+}
+
+/// Average time per call over ~1s.
+fn time(f: impl Fn() -> (Vec<u8>, f32)) -> Duration {
+    let warmup = Instant::now();
+    let mut iters = 0u32;
+    while warmup.elapsed() < Duration::from_millis(200) {
+        black_box(f());
+        iters += 1;
+    }
+
+    let iters = (iters * 5).max(1);
+    let start = Instant::now();
+    for _ in 0..iters {
+        black_box(f());
+    }
+    start.elapsed() / iters
+}
+
+fn main() {
+    for (w, h) in [(256, 192), (1920, 1080)] {
+        let buf = frame(w, h);
+        assert_eq!(
+            binarize_luma(&buf, false, 0.95),
+            binarize_scalar(&buf, false, 0.95),
+            "chunked and scalar binarization disagree"
+        );
+
+        let scalar = time(|| binarize_scalar(black_box(&buf), false, 0.95));
+        let chunked = time(|| binarize_luma(black_box(&buf), false, 0.95));
+        println!(
+            "{w}×{h}: scalar {scalar:?}/frame, chunked {chunked:?}/frame ({:.1}× faster)",
+            scalar.as_secs_f64() / chunked.as_secs_f64()
+        );
+    }
+}
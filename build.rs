@@ -0,0 +1,29 @@
+// build.rs
+// Generates the C header for src/ffi.rs into OUT_DIR when the "ffi" feature
+// is on (the checked-in include/bad_apple.h is regenerated with the cbindgen
+// command in the README), and sets up the Node addon link flags for "node".
+
+fn main() {
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=src/decode.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+            .expect("Failed to read cbindgen.toml");
+
+        // Only ffi.rs: parsing the whole crate exports any `pub` type it finds
+        cbindgen::Builder::new()
+            .with_src(format!("{crate_dir}/src/ffi.rs"))
+            .with_config(config)
+            .generate()
+            .expect("Failed to generate C header")
+            .write_to_file(format!("{out_dir}/bad_apple.h"));
+    }
+}
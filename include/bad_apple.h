@@ -0,0 +1,104 @@
+#ifndef BAD_APPLE_H
+#define BAD_APPLE_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of `bad_apple_rasterize`.
+ */
+typedef enum BadAppleStatus {
+  BAD_APPLE_STATUS_OK = 0,
+  BAD_APPLE_STATUS_NULL_POINTER = -1,
+  BAD_APPLE_STATUS_FRAME_OUT_OF_RANGE = -2,
+  BAD_APPLE_STATUS_BUFFER_TOO_SMALL = -3,
+} BadAppleStatus;
+
+/**
+ * Opaque decoded payload, created by `bad_apple_open`.
+ */
+typedef struct BadApplePayload BadApplePayload;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Decodes a binary ("BAPL") payload. The bytes are copied, so `data` can be
+ * freed afterwards. Returns NULL if `data` is NULL or not a valid payload.
+ *
+ * # Safety
+ *
+ * `data` must be NULL or point to `len` readable bytes.
+ */
+struct BadApplePayload *bad_apple_open(const uint8_t *data, size_t len);
+
+/**
+ * Frees a payload returned by `bad_apple_open`. NULL is ignored.
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a pointer from `bad_apple_open` not freed yet.
+ */
+void bad_apple_free(struct BadApplePayload *payload);
+
+/**
+ * Frame width in pixels (0 for NULL).
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a live pointer from `bad_apple_open`.
+ */
+uint32_t bad_apple_width(const struct BadApplePayload *payload);
+
+/**
+ * Frame height in pixels (0 for NULL).
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a live pointer from `bad_apple_open`.
+ */
+uint32_t bad_apple_height(const struct BadApplePayload *payload);
+
+/**
+ * Frames per second (0 for NULL).
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a live pointer from `bad_apple_open`.
+ */
+uint32_t bad_apple_fps(const struct BadApplePayload *payload);
+
+/**
+ * Number of frames (0 for NULL).
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a live pointer from `bad_apple_open`.
+ */
+size_t bad_apple_frame_count(const struct BadApplePayload *payload);
+
+/**
+ * Paints frame `index` into `out` as one byte per pixel, row-major, 1 = black/on
+ * and 0 = off. `out_len` must be at least width × height.
+ *
+ * # Safety
+ *
+ * `payload` must be NULL or a live pointer from `bad_apple_open`, and `out`
+ * must be NULL or point to `out_len` writable bytes.
+ */
+enum BadAppleStatus bad_apple_rasterize(const struct BadApplePayload *payload,
+                                        size_t index,
+                                        uint8_t *out,
+                                        size_t out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BAD_APPLE_H */
@@ -16,8 +16,12 @@
 
   const PATH = "http://127.0.0.1:8080/out";
   const RECT_FRAMES_URL = `${PATH}/rectFrames.json`;
+  // Highest rectFrames.json version this script understands (PAYLOAD_VERSION in rectframes.rs)
+  const SUPPORTED_PAYLOAD_VERSION = 1;
   const AUDIO_URL = `${PATH}/audio.mp3`;
 
+  // Every track in one file (`serve --bundle`); the per-language files are the fallback
+  const SUBS_BUNDLE_URL = `${PATH}/subtitles.json`;
   const SUB_JP_URL = `${PATH}/transcript_jp.json`;
   const SUB_RO_URL = `${PATH}/transcript_romaji.json`;
   const SUB_EN_URL = `${PATH}/transcript_en.json`;
@@ -91,6 +95,34 @@
     }
   }
 
+  // Raw cue arrays of the four tracks: from the subtitles.json bundle when the
+  // server wrote one, else one transcript_<lang>.json per language
+  async function loadSubtitleTracks() {
+    try {
+      const bundle = await gmFetchJson(SUBS_BUNDLE_URL);
+      if (bundle && typeof bundle === "object") {
+        return [
+          bundle.jp?.cues,
+          bundle.romaji?.cues,
+          bundle.en?.cues,
+          bundle.es?.cues,
+        ];
+      }
+    } catch (e) {
+      console.log("[BadApple] No subtitle bundle, loading each track:", e.message);
+    }
+
+    // A missing track only leaves its column empty
+    return Promise.all(
+      [SUB_JP_URL, SUB_RO_URL, SUB_EN_URL, SUB_ES_URL].map((url) =>
+        gmFetchJson(url).catch((e) => {
+          console.log("[BadApple] Skipping subtitle track", url, e.message);
+          return null;
+        })
+      )
+    );
+  }
+
   function fitAspect(containerW, containerH, contentW, contentH) {
     const containerAR = containerW / containerH;
     const contentAR = contentW / contentH;
@@ -109,7 +141,19 @@
   /* ---------------- subtitles (JSON cues) ---------------- */
 
   // Cue schema from Rust:
-  //  { s: number, e: number, t: string }
+  //  { s: number, e: number, t: string, r?: [{ i: number, b: string, r: string }] }
+  // r: furigana, base `b` starting at character `i` of `t` (bad-apple subs --ruby)
+  function normalizeRuby(r) {
+    if (!Array.isArray(r)) return [];
+    return r.filter(
+      (x) =>
+        x &&
+        Number.isInteger(x.i) &&
+        typeof x.b === "string" &&
+        typeof x.r === "string",
+    );
+  }
+
   function normalizeCues(cues) {
     if (!Array.isArray(cues)) return [];
     const out = cues
@@ -119,7 +163,7 @@
         const e = Number(c.e);
         const t = typeof c.t === "string" ? c.t : "";
         if (!Number.isFinite(s) || !Number.isFinite(e) || !t) return null;
-        return { s, e, t };
+        return { s, e, t, r: normalizeRuby(c.r) };
       })
       .filter(Boolean);
 
@@ -127,6 +171,33 @@
     return out;
   }
 
+  // Appends the cue text to `box`, each reading as <ruby> over its base
+  // (offsets are in code points, like Rust chars)
+  function appendCue(box, cue) {
+    const chars = Array.from(cue.t);
+    let i = 0;
+    for (const r of cue.r) {
+      const n = Array.from(r.b).length;
+      if (r.i < i || chars.slice(r.i, r.i + n).join("") !== r.b) continue;
+      box.append(chars.slice(i, r.i).join(""));
+      const ruby = document.createElement("ruby");
+      const rt = document.createElement("rt");
+      rt.textContent = r.r;
+      ruby.append(r.b, rt);
+      box.append(ruby);
+      i = r.i + n;
+    }
+    box.append(chars.slice(i).join(""));
+  }
+
+  function renderCues(box, cues) {
+    box.replaceChildren();
+    cues.forEach((cue, k) => {
+      if (k) box.append("\n\n");
+      appendCue(box, cue);
+    });
+  }
+
   function findActiveCue(cues, t, startIndexHint = 0) {
     let i = Math.max(0, startIndexHint);
     while (i < cues.length && cues[i].e < t) i++;
@@ -161,6 +232,12 @@
       );
     }
 
+    const version = json.version ?? 0;
+    if (version > SUPPORTED_PAYLOAD_VERSION)
+      throw new Error(
+        `rectFrames.json version ${version} is newer than this userscript supports (${SUPPORTED_PAYLOAD_VERSION}), update script.js`,
+      );
+
     const width = json.width;
     const height = json.height;
     const fps = json.fps ?? null;
@@ -334,6 +411,9 @@
       .side-text .line {
         opacity: 0.95;
       }
+      .side-text .line rt {
+        font-size: 0.55em;
+      }
     `;
     document.head.appendChild(style);
   }
@@ -592,6 +672,8 @@
   }) {
     let idxJP = 0, idxRO = 0, idxEN = 0, idxES = 0;
     let rafId = 0;
+    // Cues on screen, so the boxes are only rebuilt when they change
+    let shown = [];
 
     function render() {
       const t = audio.currentTime + timeOffsetSec;
@@ -601,16 +683,12 @@
       const c = findActiveCue(enCues, t, idxEN); idxEN = c.idx;
       const d = findActiveCue(esCues, t, idxES); idxES = d.idx;
 
-      const leftLines = [];
-      if (a.cue) leftLines.push(a.cue.t);
-      if (b.cue) leftLines.push(b.cue.t);
-
-      const rightLines = [];
-      if (c.cue) rightLines.push(c.cue.t);
-      if (d.cue) rightLines.push(d.cue.t);
-
-      leftTextBox.textContent = leftLines.join("\n\n");
-      rightTextBox.textContent = rightLines.join("\n\n");
+      const active = [a.cue, b.cue, c.cue, d.cue];
+      if (active.some((cue, k) => cue !== shown[k])) {
+        renderCues(leftTextBox, [a.cue, b.cue].filter(Boolean));
+        renderCues(rightTextBox, [c.cue, d.cue].filter(Boolean));
+        shown = active;
+      }
 
       if (!audio.ended) rafId = requestAnimationFrame(render);
     }
@@ -653,12 +731,7 @@
       const ui = createOverlaySystem(calendarEl, data.width, data.height);
 
       // fetch compact JSON cues generated by Rust
-      const [jpRaw, roRaw, enRaw, esRaw] = await Promise.all([
-        gmFetchJson(SUB_JP_URL),
-        gmFetchJson(SUB_RO_URL),
-        gmFetchJson(SUB_EN_URL),
-        gmFetchJson(SUB_ES_URL),
-      ]);
+      const [jpRaw, roRaw, enRaw, esRaw] = await loadSubtitleTracks();
 
       const jpCues = normalizeCues(jpRaw);
       const roCues = normalizeCues(roRaw);
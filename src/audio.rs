@@ -0,0 +1,78 @@
+// src/lib/audio.rs
+// Audio track playback (rodio) used as the master clock by the players.
+
+use anyhow::{Context, Result};
+use rodio::{Decoder, DeviceSinkBuilder, MixerDeviceSink, Player};
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub struct AudioTrack {
+    path: PathBuf,
+    // Must outlive the player, audio stops when the device sink is dropped
+    _sink: MixerDeviceSink,
+    player: Player,
+}
+
+fn decode(path: &Path) -> Result<Decoder<std::io::BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    Decoder::try_from(file).with_context(|| format!("Failed to decode audio: {}", path.display()))
+}
+
+impl fmt::Debug for AudioTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioTrack")
+            .field("pos", &self.player.get_pos())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AudioTrack {
+    /// Opens the default output device and starts playing `path` immediately.
+    pub fn open(path: &Path) -> Result<Self> {
+        let source = decode(path)?;
+
+        let mut sink =
+            DeviceSinkBuilder::open_default_sink().context("Failed to open audio output")?;
+        sink.log_on_drop(false);
+
+        let player = Player::connect_new(sink.mixer());
+        player.append(source);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            _sink: sink,
+            player,
+        })
+    }
+
+    /// Playback position in seconds, or `None` once the track has ended.
+    pub fn position(&self) -> Option<f64> {
+        if self.player.empty() {
+            return None;
+        }
+        Some(self.player.get_pos().as_secs_f64())
+    }
+
+    pub fn pause(&self) {
+        self.player.pause();
+    }
+
+    pub fn play(&self) {
+        self.player.play();
+    }
+
+    pub fn seek(&self, secs: f64) {
+        if self.player.empty() {
+            // Track already ended: queue it again so seeking backwards resumes audio
+            if let Ok(source) = decode(&self.path) {
+                self.player.append(source);
+            }
+        }
+        // Best effort: some formats cannot seek, video keeps its own position then
+        let _ = self.player.try_seek(Duration::from_secs_f64(secs.max(0.0)));
+    }
+}
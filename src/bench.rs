@@ -0,0 +1,183 @@
+// src/lib/bench.rs
+// Encoder comparison on a sample of frames: every threshold setting × every
+// output format, with rects/frame, output size and timings, to pick a
+// configuration before converting the whole video.
+//
+// The sample is a run of consecutive frames (so XOR bitmaps see the real
+// frame-to-frame changes), binarized and merged on one thread so the
+// per-frame times compare across settings.
+
+use anyhow::{bail, Context, Result};
+use std::{ops::Range, path::Path, time::Instant};
+
+use crate::binarize::ThresholdMode;
+use crate::format::{payload_bytes, OutputFormat, WriteOpts};
+use crate::rectframes::{
+    binarize_gray, list_frames, merge_frame_to_rects, ConvertRectframesOpts, Payload,
+    PAYLOAD_VERSION,
+};
+
+#[derive(Clone, Debug)]
+pub struct BenchOpts {
+    /// Frames in the sample
+    pub samples: usize,
+    /// First frame of the sample; None takes it from the middle
+    pub from: Option<usize>,
+    pub th_muls: Vec<f32>,
+    pub thresholds: Vec<ThresholdMode>,
+    pub invert: bool,
+    /// Encodings compared for every threshold setting
+    pub formats: Vec<WriteOpts>,
+}
+
+impl Default for BenchOpts {
+    fn default() -> Self {
+        let format = |format, xor| WriteOpts {
+            format,
+            xor,
+            pretty: false,
+        };
+        Self {
+            samples: 300,
+            from: None,
+            th_muls: vec![0.95],
+            thresholds: vec![ThresholdMode::Global, ThresholdMode::Tiles(16)],
+            invert: false,
+            formats: vec![
+                format(OutputFormat::Json, false),
+                format(OutputFormat::Flat, false),
+                format(OutputFormat::Bitmap, false),
+                format(OutputFormat::Bitmap, true),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchRow {
+    pub threshold: ThresholdMode,
+    pub th_mul: f32,
+    pub format: WriteOpts,
+    pub rects_per_frame: f64,
+    /// Encoded size of the sample
+    pub bytes: usize,
+    /// Binarize + merge time per frame
+    pub convert_ms: f64,
+    /// Time to encode the whole sample in `format`
+    pub write_ms: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    pub width: u32,
+    pub height: u32,
+    /// Sampled frame indices, out of `total_frames`
+    pub frames: Range<usize>,
+    pub total_frames: usize,
+    pub rows: Vec<BenchRow>,
+}
+
+/// Runs the sample of `in_dir` through every combination of `opts`.
+pub fn bench_encoders(in_dir: &Path, opts: &BenchOpts) -> Result<BenchReport> {
+    if opts.samples == 0 || opts.th_muls.is_empty() || opts.thresholds.is_empty() {
+        bail!("Need at least one sample frame, th_mul and threshold mode");
+    }
+    let files = list_frames(in_dir)?;
+    let n = opts.samples.min(files.len());
+    let from = opts
+        .from
+        .unwrap_or((files.len() - n) / 2)
+        .min(files.len() - n);
+    let frames = from..from + n;
+
+    let mut lumas = Vec::with_capacity(n);
+    let mut size = None;
+    for path in &files[frames.clone()] {
+        let img = image::open(path)
+            .with_context(|| format!("Failed decoding {}", path.display()))?
+            .to_luma8();
+        let dims = img.dimensions();
+        let expected = *size.get_or_insert(dims);
+        if dims != expected {
+            bail!(
+                "{}: {}×{}, expected {}×{} like the first sampled frame",
+                path.display(),
+                dims.0,
+                dims.1,
+                expected.0,
+                expected.1
+            );
+        }
+        lumas.push(img.into_raw());
+    }
+    let (w, h) = size.unwrap_or_default();
+
+    let mut rows = Vec::new();
+    for &threshold in &opts.thresholds {
+        for &th_mul in &opts.th_muls {
+            let params = ConvertRectframesOpts::builder(in_dir)
+                .size(w, h)
+                .th_mul(th_mul)
+                .threshold(threshold)
+                .invert(opts.invert)
+                .build()?
+                .frame_params();
+
+            let start = Instant::now();
+            let mut ths = Vec::with_capacity(n);
+            let rect_frames: Vec<_> = lumas
+                .iter()
+                .map(|luma| {
+                    let (frame, th) = binarize_gray(luma, &params);
+                    ths.push(th);
+                    // Same (y, x) order as a conversion writes
+                    let mut rects = merge_frame_to_rects(&frame, w as usize, h as usize, false);
+                    rects.sort_by_key(|r| (r.y, r.x));
+                    rects
+                })
+                .collect();
+            let convert_ms = start.elapsed().as_secs_f64() * 1000.0 / n as f64;
+
+            let rects: usize = rect_frames.iter().map(Vec::len).sum();
+            let avg_th = ths.iter().map(|&t| t as f64).sum::<f64>() / n as f64;
+            let payload = Payload {
+                version: PAYLOAD_VERSION,
+                width: w,
+                height: h,
+                fps: 30,
+                threshold: avg_th.round().clamp(0.0, 255.0) as u32,
+                th_mul,
+                invert: opts.invert,
+                frames_count: n,
+                rect_frames,
+                frame_thresholds: None,
+                frame_rect_counts: None,
+                preprocess: Vec::new(),
+                off_rects: false,
+                interpolated: None,
+            };
+
+            for format in &opts.formats {
+                let start = Instant::now();
+                let bytes = payload_bytes(&payload, format)?.len();
+                rows.push(BenchRow {
+                    threshold,
+                    th_mul,
+                    format: *format,
+                    rects_per_frame: rects as f64 / n as f64,
+                    bytes,
+                    convert_ms,
+                    write_ms: start.elapsed().as_secs_f64() * 1000.0,
+                });
+            }
+        }
+    }
+
+    Ok(BenchReport {
+        width: w,
+        height: h,
+        frames,
+        total_frames: files.len(),
+        rows,
+    })
+}
@@ -0,0 +1,15 @@
+use clap::Parser;
+
+use bad_apple::cli::play::{self, PlayArgs};
+
+// Same options as `bad-apple play`
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    #[command(flatten)]
+    play: PlayArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    play::run(Args::parse().play)
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use clap::Parser;
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
+use std::path::PathBuf;
+
+use bad_apple::playback::{format_time, PlaybackClock};
+use bad_apple::rectframes::Payload;
+
+const WHITE: u32 = 0x00ff_ffff;
+const BLACK: u32 = 0x0000_0000;
+const HUD_BAR: u32 = 0x00e0_3030;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Payload to preview
+    #[arg(default_value = "out/rectFrames.json")]
+    input: PathBuf,
+
+    /// Window scale: 1, 2, 4 or 8
+    #[arg(long, default_value_t = 2)]
+    scale: u8,
+
+    /// Swap black and white
+    #[arg(long)]
+    invert: bool,
+
+    /// Soundtrack to play in sync (the video follows the audio clock)
+    #[cfg(feature = "audio")]
+    #[arg(long)]
+    audio: Option<PathBuf>,
+}
+
+fn window_scale(s: u8) -> Scale {
+    match s {
+        0 | 1 => Scale::X1,
+        2 | 3 => Scale::X2,
+        4..=7 => Scale::X4,
+        _ => Scale::X8,
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let payload = Payload::from_file(&args.input)?;
+
+    if payload.rect_frames.is_empty() {
+        anyhow::bail!("Nothing to play in {}", args.input.display());
+    }
+
+    let (w, h) = (payload.width as usize, payload.height as usize);
+    let (on, off) = if args.invert {
+        (WHITE, BLACK)
+    } else {
+        (BLACK, WHITE)
+    };
+
+    let mut window = Window::new(
+        "bad-apple preview",
+        w,
+        h,
+        WindowOptions {
+            scale: window_scale(args.scale),
+            resize: true,
+            ..WindowOptions::default()
+        },
+    )?;
+    window.set_target_fps(payload.fps.max(1) as usize * 2);
+
+    println!(
+        "⌨️  Space pause · ←/→ seek 1s · Shift+←/→ seek 5s · ,/. step · -/= speed · L loop · H progress bar · Home restart · Esc quit"
+    );
+
+    let mut clock = PlaybackClock::new(payload.fps, payload.rect_frames.len());
+    #[cfg(feature = "audio")]
+    if let Some(path) = &args.audio {
+        clock = clock.with_audio(bad_apple::audio::AudioTrack::open(path)?);
+    }
+    let mut buf = vec![off; w * h];
+    let mut hud = true;
+    let mut last = None;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let seek = if shift { 5.0 } else { 1.0 };
+
+        for key in window.get_keys_pressed(KeyRepeat::Yes) {
+            match key {
+                Key::Space => clock.toggle_pause(),
+                Key::Right => clock.seek_by(seek),
+                Key::Left => clock.seek_by(-seek),
+                Key::Period => clock.step(1),
+                Key::Comma => clock.step(-1),
+                Key::Minus => clock.cycle_speed(-1),
+                Key::Equal => clock.cycle_speed(1),
+                Key::L => clock.toggle_loop(),
+                Key::H => hud = !hud,
+                Key::Home => clock.seek_to(0.0),
+                _ => {}
+            }
+        }
+
+        clock.tick();
+        if clock.is_finished() && clock.is_playing() {
+            clock.toggle_pause();
+        }
+
+        let i = clock.frame();
+        let state = (i, hud, clock.is_playing(), clock.speed(), clock.is_looping());
+        if last != Some(state) {
+            for (dst, &px) in buf.iter_mut().zip(&payload.rasterize_frame(i)) {
+                *dst = if px != 0 { on } else { off };
+            }
+            if hud {
+                // Progress bar over the bottom rows
+                let filled = w * (i + 1) / payload.rect_frames.len();
+                let bar = (h / 60).max(2).min(h);
+                for row in buf[(h - bar) * w..].chunks_mut(w) {
+                    row[..filled].fill(HUD_BAR);
+                }
+            }
+            window.set_title(&format!(
+                "bad-apple preview — {} / {} · frame {}/{} · {} rects · {}×{}{}",
+                format_time(clock.position()),
+                format_time(clock.duration()),
+                i + 1,
+                payload.rect_frames.len(),
+                payload.rect_frames[i].len(),
+                clock.speed(),
+                if clock.is_looping() { " · loop" } else { "" },
+                if clock.is_playing() { "" } else { " · paused" }
+            ));
+            last = Some(state);
+        }
+
+        window.update_with_buffer(&buf, w, h)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,400 @@
+// src/lib/binarize.rs
+// Binarization modes beyond the single `mean * th_mul` cut of
+// `rectframes::binarize_luma`, picked through `ConvertRectframesOpts`.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+/// How each pixel's threshold is derived from the frame's luma.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThresholdMode {
+    /// One cut for the whole frame: `mean * th_mul`
+    #[default]
+    Global,
+    /// Mean per N×N tile, bilinearly interpolated between tile centres, so
+    /// unevenly lit frames keep detail in both the dark and the bright parts
+    Tiles(u32),
+}
+
+impl fmt::Display for ThresholdMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdMode::Global => write!(f, "global"),
+            ThresholdMode::Tiles(n) => write!(f, "tiles:{n}"),
+        }
+    }
+}
+
+/// Parses `global` or `tiles:N`.
+impl FromStr for ThresholdMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "global" => Ok(ThresholdMode::Global),
+            Some(("tiles", n)) => match n.parse::<u32>() {
+                Ok(n) if n > 0 => Ok(ThresholdMode::Tiles(n)),
+                _ => Err(format!("tile size must be a positive integer, got {n:?}")),
+            },
+            _ => Err(format!("expected global or tiles:N, got {s:?}")),
+        }
+    }
+}
+
+/// Tiles whose luma spans fewer levels than this are flat (all silhouette or
+/// all background) and use the frame mean instead of their own.
+const MIN_TILE_CONTRAST: u8 = 24;
+
+/// Per-pixel thresholds for `ThresholdMode::Tiles`: `th_mul` times the tile
+/// means, bilinearly interpolated between tile centres (and held flat past
+/// the outer centres). A flat tile's own mean would split it on noise, so it
+/// falls back to the frame mean.
+pub fn tile_thresholds(buf: &[u8], w: usize, tile: usize, th_mul: f32) -> Vec<f32> {
+    let h = buf.len().checked_div(w).unwrap_or(0);
+    let (tx, ty) = (w.div_ceil(tile), h.div_ceil(tile));
+    let global = buf.iter().map(|&v| v as u64).sum::<u64>() as f32 / buf.len().max(1) as f32;
+
+    let mut means = vec![0f32; tx * ty];
+    for (t, mean) in means.iter_mut().enumerate() {
+        let (x0, y0) = ((t % tx) * tile, (t / tx) * tile);
+        let (x1, y1) = ((x0 + tile).min(w), (y0 + tile).min(h));
+        let (mut sum, mut lo, mut hi) = (0u64, u8::MAX, u8::MIN);
+        for y in y0..y1 {
+            for &v in &buf[y * w + x0..y * w + x1] {
+                sum += v as u64;
+                lo = lo.min(v);
+                hi = hi.max(v);
+            }
+        }
+        let local = sum as f32 / ((x1 - x0) * (y1 - y0)) as f32;
+        *mean = if hi - lo < MIN_TILE_CONTRAST {
+            global
+        } else {
+            local
+        } * th_mul;
+    }
+
+    // Grid coordinate of a pixel: tile index + fraction towards the next centre
+    let grid = |p: usize, n: usize| {
+        let g = ((p as f32 + 0.5) / tile as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+        let i = g as usize;
+        (i, (i + 1).min(n - 1), g - i as f32)
+    };
+    let cols: Vec<_> = (0..w).map(|x| grid(x, tx)).collect();
+
+    let mut ths = Vec::with_capacity(buf.len());
+    for y in 0..h {
+        let (r0, r1, fy) = grid(y, ty);
+        for &(c0, c1, fx) in &cols {
+            let top = means[r0 * tx + c0] * (1.0 - fx) + means[r0 * tx + c1] * fx;
+            let bottom = means[r1 * tx + c0] * (1.0 - fx) + means[r1 * tx + c1] * fx;
+            ths.push(top * (1.0 - fy) + bottom * fy);
+        }
+    }
+    ths
+}
+
+/// Binarizes against per-pixel thresholds (1 = on, darker unless `invert`).
+pub fn binarize_each(buf: &[u8], ths: &[f32], invert: bool) -> Vec<u8> {
+    let flip = invert as u8;
+    buf.iter()
+        .zip(ths)
+        .map(|(&v, &th)| ((v as f32) < th) as u8 ^ flip)
+        .collect()
+}
+
+/// Dual-threshold binarization, both cuts as multipliers of the frame's mean
+/// luma (like `th_mul`). Pixels past the strong cut are on; pixels between
+/// the two cuts are on only when 8-connected to a strong pixel, which drops
+/// isolated speckle on noisy sources while keeping soft edges of real shapes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hysteresis {
+    pub low: f32,
+    pub high: f32,
+}
+
+impl Hysteresis {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let ok = |m: f32| m.is_finite() && m > 0.0;
+        if !(ok(self.low) && ok(self.high) && self.low <= self.high) {
+            return Err(format!("hysteresis needs 0 < low <= high, got {}", self));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Hysteresis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.low, self.high)
+    }
+}
+
+/// Parses `LOW,HIGH`, e.g. `0.85,1.05`.
+impl FromStr for Hysteresis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (low, high) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected LOW,HIGH, got {s:?}"))?;
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<f32>()
+                .map_err(|e| format!("bad multiplier {v:?}: {e}"))
+        };
+        let h = Hysteresis {
+            low: parse(low)?,
+            high: parse(high)?,
+        };
+        h.validate()?;
+        Ok(h)
+    }
+}
+
+/// `v < cut` for a float threshold, as an integer cut (see `binarize_luma`).
+fn cut(th: f32) -> u16 {
+    th.ceil().clamp(0.0, 256.0) as u16
+}
+
+/// Hysteresis binarization of a `w`-wide luma buffer with mean luma `mean`
+/// (1 = on, darker than the cuts unless `invert`). Returns the frame and the
+/// strong threshold: `mean * low`, or `mean * high` when inverted.
+pub fn binarize_hysteresis(
+    buf: &[u8],
+    w: usize,
+    mean: f32,
+    invert: bool,
+    hyst: Hysteresis,
+) -> (Vec<u8>, f32) {
+    let (low, high) = (mean * hyst.low, mean * hyst.high);
+    let (low_cut, high_cut) = (cut(low), cut(high));
+
+    // 2 = strong, 1 = weak, 0 = off, looked up per byte value
+    let class: [u8; 256] = std::array::from_fn(|v| {
+        let v = v as u16;
+        match invert {
+            false if v < low_cut => 2,
+            false if v < high_cut => 1,
+            true if v >= high_cut => 2,
+            true if v >= low_cut => 1,
+            _ => 0,
+        }
+    });
+    let mut frame: Vec<u8> = buf.iter().map(|&v| class[v as usize]).collect();
+
+    // Weak pixels are the thin band around edges, so start from those that
+    // touch a strong pixel and flood through the rest of the band
+    let h = frame.len().checked_div(w).unwrap_or(0);
+    let neighbours = |i: usize| {
+        let (x, y) = (i % w, i / w);
+        (y.saturating_sub(1)..=(y + 1).min(h - 1)).flat_map(move |ny| {
+            (x.saturating_sub(1)..=(x + 1).min(w - 1)).map(move |nx| ny * w + nx)
+        })
+    };
+    let mut stack = Vec::new();
+    for i in 0..frame.len() {
+        if frame[i] != 1 || !neighbours(i).any(|j| frame[j] == 2) {
+            continue;
+        }
+        frame[i] = 2;
+        stack.push(i);
+        while let Some(i) = stack.pop() {
+            for j in neighbours(i) {
+                if frame[j] == 1 {
+                    frame[j] = 2;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+
+    for v in &mut frame {
+        *v = (*v == 2) as u8;
+    }
+    (frame, if invert { high } else { low })
+}
+
+/// Temporal anti-flicker: a pixel keeps its previous state unless it is
+/// `margin` or more luma levels past the threshold, or has stayed on the
+/// other side of it for `frames` consecutive frames. Removes the shimmer of
+/// pixels hovering around the cut, at the cost of a `frames`-frame lag on
+/// slow fades.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Temporal {
+    pub margin: u8,
+    pub frames: u32,
+}
+
+impl Temporal {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.frames == 0 {
+            return Err("temporal filter needs frames >= 1".into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Temporal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.margin, self.frames)
+    }
+}
+
+/// Parses `MARGIN,FRAMES`, e.g. `8,3`.
+impl FromStr for Temporal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (margin, frames) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected MARGIN,FRAMES, got {s:?}"))?;
+        let t = Temporal {
+            margin: margin
+                .trim()
+                .parse()
+                .map_err(|e| format!("bad margin {margin:?}: {e}"))?,
+            frames: frames
+                .trim()
+                .parse()
+                .map_err(|e| format!("bad frame count {frames:?}: {e}"))?,
+        };
+        t.validate()?;
+        Ok(t)
+    }
+}
+
+/// Set on top of the 0/1 state of `frame` pixels within `margin` of their
+/// threshold (`th(i)` for pixel `i`).
+pub(crate) const NEAR: u8 = 2;
+
+/// Flags the pixels of a binarized frame that are too close to the threshold
+/// to flip on their own, for `TemporalFilter`.
+pub(crate) fn mark_near(frame: &mut [u8], buf: &[u8], th: impl Fn(usize) -> f32, margin: u8) {
+    for (i, (px, &v)) in frame.iter_mut().zip(buf).enumerate() {
+        if (v as f32 - th(i)).abs() < margin as f32 {
+            *px |= NEAR;
+        }
+    }
+}
+
+/// Applies `Temporal` across frames fed in order, turning flagged frames
+/// (see `mark_near`) back into plain 0/1 ones.
+pub(crate) struct TemporalFilter {
+    frames: u32,
+    state: Vec<u8>,
+    /// Consecutive frames each pixel has disagreed with its state
+    streak: Vec<u32>,
+}
+
+impl TemporalFilter {
+    pub(crate) fn new(temporal: Temporal) -> Self {
+        Self {
+            frames: temporal.frames,
+            state: Vec::new(),
+            streak: Vec::new(),
+        }
+    }
+
+    pub(crate) fn apply(&mut self, frame: &mut [u8]) {
+        if self.state.len() != frame.len() {
+            // First frame: nothing to hold, take it as is
+            self.state = frame.iter().map(|&px| px & 1).collect();
+            self.streak = vec![0; frame.len()];
+        }
+
+        for ((px, state), streak) in frame.iter_mut().zip(&mut self.state).zip(&mut self.streak) {
+            let raw = *px & 1;
+            if raw == *state {
+                *streak = 0;
+            } else if *px & NEAR == 0 {
+                *state = raw;
+                *streak = 0;
+            } else {
+                *streak += 1;
+                if *streak >= self.frames {
+                    *state = raw;
+                    *streak = 0;
+                }
+            }
+            *px = *state;
+        }
+    }
+}
+
+/// Background subtraction for camera-captured sources: a static background is
+/// estimated as the per-pixel median of `samples` frames spread over the
+/// input, and a pixel is on when it differs from it by more than `threshold`
+/// luma levels, so only the moving silhouette is encoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Background {
+    pub threshold: u8,
+    pub samples: u32,
+}
+
+impl Background {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.samples == 0 {
+            return Err("background estimation needs at least 1 sample frame".into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.threshold, self.samples)
+    }
+}
+
+/// Parses `THRESHOLD[,SAMPLES]`, e.g. `30` or `30,50` (25 samples by default).
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (threshold, samples) = s.split_once(',').unwrap_or((s, "25"));
+        let bg = Background {
+            threshold: threshold
+                .trim()
+                .parse()
+                .map_err(|e| format!("bad threshold {threshold:?}: {e}"))?,
+            samples: samples
+                .trim()
+                .parse()
+                .map_err(|e| format!("bad sample count {samples:?}: {e}"))?,
+        };
+        bg.validate()?;
+        Ok(bg)
+    }
+}
+
+/// An estimated background, shared by every frame's worker.
+#[derive(Clone, Debug)]
+pub(crate) struct BackgroundModel {
+    pub luma: Arc<[u8]>,
+    pub threshold: u8,
+}
+
+impl BackgroundModel {
+    /// Per-pixel median of equally sized luma `frames`.
+    pub(crate) fn median(frames: &[Vec<u8>], threshold: u8) -> Self {
+        let len = frames.first().map_or(0, Vec::len);
+        let mut column = vec![0u8; frames.len()];
+        let luma = (0..len)
+            .map(|i| {
+                for (c, f) in column.iter_mut().zip(frames) {
+                    *c = f[i];
+                }
+                let mid = column.len() / 2;
+                *column.select_nth_unstable(mid).1
+            })
+            .collect();
+        Self { luma, threshold }
+    }
+
+    /// Absolute difference to the background, per pixel.
+    pub(crate) fn diff(&self, buf: &[u8]) -> Vec<u8> {
+        buf.iter()
+            .zip(self.luma.iter())
+            .map(|(&v, &b)| v.abs_diff(b))
+            .collect()
+    }
+}
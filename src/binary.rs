@@ -0,0 +1,128 @@
+// src/lib/binary.rs
+// Compact little-endian binary container for a rect payload.
+//
+// Layout (v1, or v2 with per-frame CRC32s):
+//   magic        4  "BAPL"
+//   version      u8 (1, 2 when flag bit1 is set)
+//   flags        u8 (bit0 = invert, bit1 = per-frame CRC32; decoders reject
+//                any other bit, which could change the frame layout)
+//   width        u16
+//   height       u16
+//   fps          u16
+//   threshold    u16
+//   th_mul       f32
+//   frames_count u32
+//   per frame:
+//     rect_count u32
+//     rect_count × { x u16, y u16, w u16, h u16, v u8 }
+//     crc32      u32, only with flag bit1: CRC32 (IEEE) of this frame's
+//                rect_count and rects
+//
+// The CRC32 changes the frame layout, hence version 2: v1-only decoders
+// ignore flag bits they don't know and would read the CRCs as rects. Payloads
+// without CRC32s stay v1, readable everywhere.
+//
+// The decoder itself lives in decode.rs so the WASM player shares it.
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+pub use crate::decode::{HEADER_LEN, MAGIC, RECT_LEN, VERSION, VERSION_CRC32};
+
+use crate::decode::{crc32, decode, FLAG_CRC32, FLAG_INVERT};
+use crate::rectframes::{Payload, PAYLOAD_VERSION};
+
+fn to_u16(v: u32, what: &str) -> Result<u16> {
+    u16::try_from(v).with_context(|| format!("{what} {v} does not fit the binary format (u16)"))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BinaryOpts {
+    /// Append a CRC32 to every frame so `decode::verify` (and decoding in
+    /// general) can tell a corrupted copy from a valid payload
+    pub crc32: bool,
+}
+
+pub fn encode_payload(payload: &Payload) -> Result<Vec<u8>> {
+    encode_payload_with(payload, &BinaryOpts::default())
+}
+
+pub fn encode_payload_with(payload: &Payload, opts: &BinaryOpts) -> Result<Vec<u8>> {
+    let rects_total: usize = payload.rect_frames.iter().map(|f| f.len()).sum();
+    let frame_len = if opts.crc32 { 8 } else { 4 };
+    let mut out = Vec::with_capacity(
+        HEADER_LEN + payload.rect_frames.len() * frame_len + rects_total * RECT_LEN,
+    );
+
+    let mut flags = 0;
+    if payload.invert {
+        flags |= FLAG_INVERT;
+    }
+    if opts.crc32 {
+        flags |= FLAG_CRC32;
+    }
+
+    out.extend_from_slice(MAGIC);
+    out.push(if opts.crc32 { VERSION_CRC32 } else { VERSION });
+    out.push(flags);
+    out.extend_from_slice(&to_u16(payload.width, "width")?.to_le_bytes());
+    out.extend_from_slice(&to_u16(payload.height, "height")?.to_le_bytes());
+    out.extend_from_slice(&to_u16(payload.fps, "fps")?.to_le_bytes());
+    out.extend_from_slice(&to_u16(payload.threshold, "threshold")?.to_le_bytes());
+    out.extend_from_slice(&payload.th_mul.to_le_bytes());
+    out.extend_from_slice(&(payload.rect_frames.len() as u32).to_le_bytes());
+
+    for rects in &payload.rect_frames {
+        let start = out.len();
+        out.extend_from_slice(&(rects.len() as u32).to_le_bytes());
+        for r in rects {
+            out.extend_from_slice(&(r.x as u16).to_le_bytes());
+            out.extend_from_slice(&(r.y as u16).to_le_bytes());
+            out.extend_from_slice(&(r.w as u16).to_le_bytes());
+            out.extend_from_slice(&(r.h as u16).to_le_bytes());
+            out.push(r.v);
+        }
+        if opts.crc32 {
+            let crc = crc32(&out[start..]);
+            out.extend_from_slice(&crc.to_le_bytes());
+        }
+    }
+
+    Ok(out)
+}
+
+pub fn decode_payload(bytes: &[u8]) -> Result<Payload> {
+    let (header, frames) = decode(bytes)?;
+    let rect_frames = frames.collect::<Result<Vec<_>, _>>()?;
+    let off_rects = rect_frames.iter().flatten().any(|r| r.v == 0);
+
+    Ok(Payload {
+        version: PAYLOAD_VERSION,
+        width: header.width,
+        height: header.height,
+        fps: header.fps,
+        threshold: header.threshold,
+        th_mul: header.th_mul,
+        invert: header.invert,
+        frames_count: header.frames_count,
+        rect_frames,
+        frame_thresholds: None,
+        frame_rect_counts: None,
+        preprocess: Vec::new(),
+        off_rects,
+        interpolated: None,
+    })
+}
+
+pub fn write_binary_file(payload: &Payload, out_file: &Path) -> Result<()> {
+    let bytes = encode_payload(payload)?;
+
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(out_file, bytes)
+        .with_context(|| format!("Failed writing binary payload: {}", out_file.display()))?;
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+// src/lib/bitmap.rs
+// Raw 1bpp bitmap payload, an alternative to rects for busy frames.
+//
+// Layout (little-endian):
+//   magic        4  "BABM"
+//   version      u8 (1)
+//   flags        u8 (bit0 = invert, bit1 = xor)
+//   width        u16
+//   height       u16
+//   fps          u16
+//   frames_count u32
+//   frames_count × frame, each row_bytes(width) * height bytes
+//
+// Pixels are MSB first with rows padded to a byte (see raster::pack_1bpp).
+// With the xor flag every frame after the first stores `frame ^ previous`,
+// which is mostly zeros for slow-moving scenes and compresses well.
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+use crate::raster::{pack_1bpp, row_bytes, unpack_1bpp};
+use crate::rectframes::Payload;
+
+pub const MAGIC: &[u8; 4] = b"BABM";
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 16;
+
+const FLAG_INVERT: u8 = 1 << 0;
+const FLAG_XOR: u8 = 1 << 1;
+
+#[derive(Clone, Debug)]
+pub struct BitmapPayload {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub invert: bool,
+    /// Packed frames as they would be displayed (never XOR'd in memory).
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl BitmapPayload {
+    pub fn from_payload(payload: &Payload) -> Self {
+        let (w, h) = (payload.width as usize, payload.height as usize);
+        let frames = (0..payload.rect_frames.len())
+            .map(|i| pack_1bpp(&payload.rasterize_frame(i), w, h))
+            .collect();
+
+        Self {
+            width: payload.width,
+            height: payload.height,
+            fps: payload.fps,
+            invert: payload.invert,
+            frames,
+        }
+    }
+
+    pub fn frame_bytes(&self) -> usize {
+        row_bytes(self.width as usize) * self.height as usize
+    }
+
+    /// Unpacks frame `i` into a 1/0 pixel buffer.
+    pub fn frame_pixels(&self, i: usize) -> Vec<u8> {
+        unpack_1bpp(&self.frames[i], self.width as usize, self.height as usize)
+    }
+}
+
+pub fn encode_bitmap(bm: &BitmapPayload, xor: bool) -> Result<Vec<u8>> {
+    let w = u16::try_from(bm.width).context("width does not fit u16")?;
+    let h = u16::try_from(bm.height).context("height does not fit u16")?;
+    let fps = u16::try_from(bm.fps).context("fps does not fit u16")?;
+
+    let mut flags = 0u8;
+    if bm.invert {
+        flags |= FLAG_INVERT;
+    }
+    if xor {
+        flags |= FLAG_XOR;
+    }
+
+    let mut out = Vec::with_capacity(HEADER_LEN + bm.frames.len() * bm.frame_bytes());
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.push(flags);
+    out.extend_from_slice(&w.to_le_bytes());
+    out.extend_from_slice(&h.to_le_bytes());
+    out.extend_from_slice(&fps.to_le_bytes());
+    out.extend_from_slice(&(bm.frames.len() as u32).to_le_bytes());
+
+    let mut prev: Option<&Vec<u8>> = None;
+    for frame in &bm.frames {
+        match (xor, prev) {
+            (true, Some(p)) => out.extend(frame.iter().zip(p).map(|(a, b)| a ^ b)),
+            _ => out.extend_from_slice(frame),
+        }
+        prev = Some(frame);
+    }
+
+    Ok(out)
+}
+
+pub fn decode_bitmap(bytes: &[u8]) -> Result<BitmapPayload> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        anyhow::bail!("Not a bitmap payload (bad magic)");
+    }
+    if bytes[4] != VERSION {
+        anyhow::bail!("Unsupported bitmap payload version {}", bytes[4]);
+    }
+
+    let flags = bytes[5];
+    let width = u16::from_le_bytes([bytes[6], bytes[7]]) as u32;
+    let height = u16::from_le_bytes([bytes[8], bytes[9]]) as u32;
+    let fps = u16::from_le_bytes([bytes[10], bytes[11]]) as u32;
+    let frames_count = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]) as usize;
+
+    let fb = row_bytes(width as usize) * height as usize;
+    let body = &bytes[HEADER_LEN..];
+    if fb == 0 || body.len() != fb * frames_count {
+        anyhow::bail!(
+            "Bitmap payload size mismatch: expected {} bytes of frames, got {}",
+            fb * frames_count,
+            body.len()
+        );
+    }
+
+    let xor = flags & FLAG_XOR != 0;
+    let mut frames: Vec<Vec<u8>> = Vec::with_capacity(frames_count);
+    for chunk in body.chunks_exact(fb) {
+        let frame = match (xor, frames.last()) {
+            (true, Some(p)) => chunk.iter().zip(p).map(|(a, b)| a ^ b).collect(),
+            _ => chunk.to_vec(),
+        };
+        frames.push(frame);
+    }
+
+    Ok(BitmapPayload {
+        width,
+        height,
+        fps,
+        invert: flags & FLAG_INVERT != 0,
+        frames,
+    })
+}
+
+pub fn write_bitmap_file(bm: &BitmapPayload, xor: bool, out_file: &Path) -> Result<()> {
+    let bytes = encode_bitmap(bm, xor)?;
+
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(out_file, bytes)
+        .with_context(|| format!("Failed writing bitmap payload: {}", out_file.display()))?;
+
+    Ok(())
+}
+
+pub fn read_bitmap_file(path: &Path) -> Result<BitmapPayload> {
+    let bytes =
+        fs::read(path).with_context(|| format!("Failed reading bitmap: {}", path.display()))?;
+    decode_bitmap(&bytes)
+}
@@ -0,0 +1,36 @@
+// src/lib/cancel.rs
+// Cooperative cancellation for long conversions: a GUI or server keeps a
+// clone of the token and calls `cancel()`, the conversion notices between
+// frames and returns `BadAppleError::Cancelled`.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::error::{BadAppleError, Result};
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(Cancelled)` once `cancel` was called.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(BadAppleError::Cancelled);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+// src/lib/cli/batch.rs
+// `bad-apple batch`: converts every project of a manifest into its own
+// `<out>/<name>/` (payload plus the JSON of each SRT in its lyrics dir):
+//
+//   [convert]              # defaults for every item, converter flag names
+//   w = 256
+//   h = 192
+//
+//   [[item]]
+//   name = "bad-apple"
+//   frames = "frames"
+//   lyrics = "lyrics"
+//
+//   [[item]]
+//   name = "oled"
+//   frames = "frames"
+//   preset = "oled128x64"
+//   convert = { th-mul = 0.9, out = "out/oled/bad_apple.bin" }
+//
+// Settings stack up as bad-apple.toml `[convert]`, the manifest's
+// `[convert]`, the item's preset, then the item's own `convert` table.
+// Paths are relative to the working directory, like on the command line;
+// an item's `out` has to stay in its `<out>/<name>/`.
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, FromArgMatches};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tracing::{error, info};
+
+use crate::cli::convert::{self, ConvertArgs};
+use crate::config::{apply_table, Config};
+use crate::logging::LogArgs;
+use crate::subs::srt_to_json_file_with;
+
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// Manifest listing the projects
+    #[arg(default_value = "batch.toml")]
+    pub manifest: PathBuf,
+
+    /// Directory holding one subdirectory per project
+    #[arg(long, default_value = "out")]
+    pub out: PathBuf,
+
+    /// Only convert these projects (repeatable)
+    #[arg(long, value_name = "NAME")]
+    pub only: Vec<String>,
+
+    /// Skip projects whose payload already exists
+    #[arg(long)]
+    pub skip_existing: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Converter settings for every item
+    #[serde(default)]
+    pub convert: toml::Table,
+
+    #[serde(default, rename = "item")]
+    pub items: Vec<BatchItem>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchItem {
+    /// Output subdirectory, also the project name in `--only` and the server
+    pub name: String,
+    /// Frames directory or archive (`--in`)
+    pub frames: String,
+    /// SRT files to convert next to the payload
+    pub lyrics: Option<PathBuf>,
+    pub preset: Option<String>,
+    /// Converter settings for this item only
+    #[serde(default)]
+    pub convert: toml::Table,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed reading {}", path.display()))?;
+        let manifest: Manifest = toml::from_str(&text)
+            .with_context(|| format!("Failed parsing {}", path.display()))?;
+        for item in &manifest.items {
+            let mut parts = Path::new(&item.name).components();
+            if !matches!((parts.next(), parts.next()), (Some(Component::Normal(_)), None)) {
+                bail!(
+                    "{}: item name {:?} isn't a plain directory name",
+                    path.display(),
+                    item.name
+                );
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// A converted project: its name and payload path, relative to the
+/// output directory.
+#[derive(Clone, Debug)]
+pub struct Project {
+    pub name: String,
+    pub payload: PathBuf,
+}
+
+/// Payload path of `item` when its settings don't set `out`.
+pub fn payload_path(out_dir: &Path, item: &BatchItem) -> PathBuf {
+    out_dir.join(&item.name).join("rectFrames.json")
+}
+
+/// Converter arguments of `item`, as if its settings were given as flags.
+fn item_args(
+    config: &Config,
+    manifest: &Manifest,
+    manifest_path: &Path,
+    item: &BatchItem,
+    out_dir: &Path,
+) -> Result<ConvertArgs> {
+    let mut cmd = ConvertArgs::augment_args(clap::Command::new("convert"));
+    cmd = apply_table(cmd, &config.convert, "bad-apple.toml [convert]", true)?;
+    let what = format!("{} [convert]", manifest_path.display());
+    cmd = apply_table(cmd, &manifest.convert, &what, true)?;
+    if let Some(name) = &item.preset {
+        cmd = apply_table(cmd, &config.preset(name)?, &format!("preset {name}"), true)?;
+    }
+    let what = format!("{} item {}", manifest_path.display(), item.name);
+    cmd = apply_table(cmd, &item.convert, &what, true)?;
+
+    let out = payload_path(out_dir, item);
+    cmd = cmd.mut_arg("in", |a| a.default_value(item.frames.clone()));
+    if !item.convert.contains_key("out") {
+        cmd = cmd.mut_arg("out", |a| a.default_value(out.display().to_string()));
+    }
+    let matches = cmd
+        .try_get_matches_from(["convert"])
+        .with_context(|| format!("Invalid settings for {}", item.name))?;
+    Ok(ConvertArgs::from_arg_matches(&matches)?)
+}
+
+/// Payload of `args` relative to `out_dir`; fails unless it's in the
+/// item's `<out>/<name>/`.
+fn project_payload(args: &ConvertArgs, out_dir: &Path, item: &BatchItem) -> Result<PathBuf> {
+    let project_dir = out_dir.join(&item.name);
+    let out = args.out();
+    let rel = if args.writes_stdout() {
+        None
+    } else {
+        std::path::absolute(out)?
+            .strip_prefix(std::path::absolute(&project_dir)?)
+            .ok()
+            .map(Path::to_path_buf)
+    };
+    match rel {
+        Some(rel)
+            if rel.components().next().is_some()
+                && rel.components().all(|c| matches!(c, Component::Normal(_))) =>
+        {
+            Ok(Path::new(&item.name).join(rel))
+        }
+        _ => bail!(
+            "{}: out {} isn't in {}",
+            item.name,
+            out.display(),
+            project_dir.display()
+        ),
+    }
+}
+
+/// Converts one item: its payload, then its lyrics.
+fn convert_item(
+    args: ConvertArgs,
+    item: &BatchItem,
+    out_dir: &Path,
+    log: &LogArgs,
+) -> Result<()> {
+    let pretty = args.is_pretty();
+    let project_dir = out_dir.join(&item.name);
+    fs::create_dir_all(&project_dir)
+        .with_context(|| format!("Failed creating {}", project_dir.display()))?;
+    convert::run(args, log)?;
+
+    let Some(lyrics) = &item.lyrics else {
+        return Ok(());
+    };
+    let mut srts: Vec<PathBuf> = fs::read_dir(lyrics)
+        .with_context(|| format!("Failed reading {}", lyrics.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "srt"))
+        .collect();
+    srts.sort();
+    for srt in srts {
+        let json = project_dir.join(srt.with_extension("json").file_name().expect("srt file"));
+        srt_to_json_file_with(&srt, &json, pretty)?;
+        info!(out = %json.display(), "📝 Wrote {}", json.display());
+    }
+    Ok(())
+}
+
+/// Converts the items of `manifest_path` (all, or those named in `only`)
+/// into `out_dir`; returns the projects converted. Failed
+/// items don't stop the others, the error lists them at the end.
+pub fn convert_manifest(
+    manifest_path: &Path,
+    out_dir: &Path,
+    only: &[String],
+    skip_existing: bool,
+    log: &LogArgs,
+) -> Result<Vec<Project>> {
+    let manifest = Manifest::load(manifest_path)?;
+    let args: Vec<_> = std::env::args_os().collect();
+    let config = Config::find(&args)?.unwrap_or_default();
+    for name in only {
+        if !manifest.items.iter().any(|item| &item.name == name) {
+            bail!("No item {name} in {}", manifest_path.display());
+        }
+    }
+
+    let mut done = Vec::new();
+    let mut failed = Vec::new();
+    for item in &manifest.items {
+        if !only.is_empty() && !only.contains(&item.name) {
+            continue;
+        }
+        let args = item_args(&config, &manifest, manifest_path, item, out_dir)?;
+        let project = Project {
+            name: item.name.clone(),
+            payload: project_payload(&args, out_dir, item)?,
+        };
+        if skip_existing && args.output_exists() {
+            info!(project = %item.name, "⏭️  {} already converted", item.name);
+            done.push(project);
+            continue;
+        }
+        info!(project = %item.name, "📦 {}", item.name);
+        match convert_item(args, item, out_dir, log) {
+            Ok(()) => done.push(project),
+            Err(e) => {
+                error!(project = %item.name, "{}: {e:#}", item.name);
+                failed.push(item.name.clone());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        bail!(
+            "{} of {} projects failed: {}",
+            failed.len(),
+            failed.len() + done.len(),
+            failed.join(", ")
+        );
+    }
+    Ok(done)
+}
+
+pub fn run(args: BatchArgs, log: &LogArgs) -> Result<()> {
+    let done = convert_manifest(
+        &args.manifest,
+        &args.out,
+        &args.only,
+        args.skip_existing,
+        log,
+    )?;
+    info!(
+        projects = done.len(),
+        "✅ {} projects in {}",
+        done.len(),
+        args.out.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,556 @@
+// src/lib/cli/convert.rs
+// `bad-apple convert` / `convert_rectframes`: frames directory (or archive,
+// URL, raw stdin) to payload, with a progress bar and a result summary.
+
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    borrow::Cow,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+use crate::binarize::{Background, Hysteresis, Temporal, ThresholdMode};
+use crate::cli::watch;
+use crate::format::{
+    encoded_len, payload_bytes, write_payload, write_sizes, OutputFormat, WriteOpts,
+};
+use crate::interpolate::Interpolate;
+use crate::logging::{LogArgs, LogFormat};
+use crate::metrics::evaluate_quality;
+use crate::preprocess::Equalize;
+use crate::raster::parse_size;
+use crate::rectframes::{
+    convert_luma_frames, convert_rectframes, convert_rectframes_sizes, convert_rectframes_to_file,
+    ConvertRectframesOpts, ConvertRectframesOptsBuilder,
+};
+use crate::rectframes::Payload;
+use crate::source::RawFrames;
+use crate::stats::{payload_stats, Distribution};
+
+#[derive(Args, Clone, Debug)]
+pub struct ConvertArgs {
+    #[arg(long, default_value_t = 192)]
+    w: u32,
+
+    #[arg(long, default_value_t = 144)]
+    h: u32,
+
+    #[arg(long, default_value_t = 30)]
+    fps: u32,
+
+    #[arg(long, default_value_t = 0)]
+    invert: u8,
+
+    /// Frames directory or .zip/.tar(.gz); with feature "http" also an http(s) URL of an
+    /// archive or a frame sequence (`https://host/frames/frame_%05d.png`)
+    #[arg(long, default_value = "frames")]
+    r#in: String,
+
+    /// Where downloaded inputs are kept between runs
+    #[cfg(feature = "http")]
+    #[arg(long, default_value = "cache")]
+    cache_dir: PathBuf,
+
+    /// Download retries on network errors and 5xx responses
+    #[cfg(feature = "http")]
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Read raw 8-bit grayscale frames (--w × --h bytes each) from stdin instead of --in,
+    /// e.g. `ffmpeg -i video.mp4 -vf scale=192:144 -pix_fmt gray -f rawvideo - | convert_rectframes --stdin-raw`
+    #[arg(long, conflicts_with_all = ["in", "metrics"])]
+    stdin_raw: bool,
+
+    /// Output file, or - for stdout (logs then go to stderr)
+    #[arg(long, default_value = "out/rectFrames.json")]
+    out: String,
+
+    /// Also write payloads downscaled to these sizes from the same decode pass, e.g.
+    /// 128x96,64x48: every size goes to <out>_WxH.<ext>, listed in <out>.manifest.json
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_size,
+        conflicts_with = "stdin_raw"
+    )]
+    sizes: Vec<(u32, u32)>,
+
+    #[arg(long, default_value_t = 0.95)]
+    th_mul: f32,
+
+    /// Threshold source: global (one cut per frame) or tiles:N (per-N×N-tile means, interpolated)
+    #[arg(long, default_value_t = ThresholdMode::Global)]
+    threshold: ThresholdMode,
+
+    /// Histogram equalization before thresholding: global, clahe or clahe:TILES[,CLIP]
+    #[arg(long, value_name = "MODE")]
+    equalize: Option<Equalize>,
+
+    /// Gaussian blur sigma (pixels) before thresholding, against compression noise and dithering
+    #[arg(long, value_name = "SIGMA")]
+    blur: Option<f32>,
+
+    /// Gamma correction before thresholding (> 1 brightens midtones)
+    #[arg(long, default_value_t = 1.0)]
+    gamma: f32,
+
+    /// Brightness offset before thresholding, -1..1
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    brightness: f32,
+
+    /// Contrast around mid-gray before thresholding
+    #[arg(long, default_value_t = 1.0)]
+    contrast: f32,
+
+    /// Dual-threshold binarization LOW,HIGH (mean-luma multipliers, replaces --th-mul):
+    /// in-between pixels stay on only next to strong ones, e.g. 0.85,1.05
+    #[arg(long, value_name = "LOW,HIGH")]
+    hysteresis: Option<Hysteresis>,
+
+    /// Background subtraction THRESHOLD[,SAMPLES]: encode pixels differing from the median of
+    /// SAMPLES frames (default 25) by more than THRESHOLD luma levels, e.g. 30
+    #[arg(long, value_name = "THRESHOLD[,SAMPLES]", conflicts_with = "stdin_raw")]
+    background: Option<Background>,
+
+    /// Region-of-interest PNG of the frame size: white areas are encoded, black ones forced off
+    /// (watermarks, timestamps, letterbox bars)
+    #[arg(long, value_name = "PATH")]
+    mask: Option<PathBuf>,
+
+    /// Anti-flicker MARGIN,FRAMES: a pixel only flips when MARGIN luma levels past the threshold
+    /// or after FRAMES frames across it, e.g. 8,3
+    #[arg(long, value_name = "MARGIN,FRAMES")]
+    temporal: Option<Temporal>,
+
+    /// Binarize colour frames on the GPU (falls back to the CPU without an adapter)
+    #[cfg(feature = "gpu")]
+    #[arg(long)]
+    gpu: bool,
+
+    /// Decoder + encoder worker threads (0 = one per core, 1 = single-threaded)
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+
+    /// Store each frame's threshold and rect count in the payload (frame_thresholds)
+    #[arg(long)]
+    frame_stats: bool,
+
+    /// Double the frame rate with in-between frames: blend (binarized crossfade) or morph
+    /// (edges move halfway)
+    #[arg(long, value_name = "MODE")]
+    interpolate: Option<Interpolate>,
+
+    /// Also emit the off regions as `v: 0` rects, for renderers that paint both colours
+    #[arg(long)]
+    off_rects: bool,
+
+    /// After encoding, compare every rasterized frame against the binarized source
+    #[arg(long)]
+    metrics: bool,
+
+    /// Output format: json (rects), bitmap (packed 1bpp frames) or flat (x,y,w,h int array + frame offsets)
+    #[arg(long, default_value = "json")]
+    format: OutputFormat,
+
+    /// Bitmap only: XOR each frame against the previous one
+    #[arg(long)]
+    xor: bool,
+
+    /// Json only: indented output for debugging (compact by default)
+    #[arg(long)]
+    pretty: bool,
+
+    /// Settings file (defaults to ./bad-apple.toml when present); flags given here override it
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Settings bundle applied over the settings file: oled128x64, oled128x32, led64x32,
+    /// web256, web480 or a [presets.NAME] table of bad-apple.toml
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Decode and encode every frame, then print thresholds, rect counts and the output size
+    /// without writing anything
+    #[arg(long, conflicts_with = "sizes")]
+    dry_run: bool,
+
+    /// Keep running and convert again whenever a frame in --in (or an SRT in --lyrics) changes
+    #[arg(long, conflicts_with_all = ["stdin_raw", "dry_run"])]
+    watch: bool,
+
+    /// With --watch: SRT directory whose files are converted to JSON next to the payload
+    #[arg(long, value_name = "DIR", requires = "watch")]
+    lyrics: Option<PathBuf>,
+}
+
+/// Progress bar on stderr (hidden when it isn't a terminal), with rate and
+/// ETA when the frame count is known, a spinner for streamed input.
+fn progress_bar(streaming: bool, visible: bool) -> ProgressBar {
+    if !visible {
+        return ProgressBar::hidden();
+    }
+    let template = if streaming {
+        "{spinner} {pos} frames · {per_sec} · {msg}"
+    } else {
+        "{bar:40} {pos}/{len} · {per_sec} · ETA {eta} · {msg}"
+    };
+    let bar = ProgressBar::new(0);
+    bar.set_style(ProgressStyle::with_template(template).expect("valid template"));
+    bar
+}
+
+/// Size of a file just written, 0 if it can't be read back.
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |m| m.len())
+}
+
+fn log_distribution(label: &str, stat: &str, d: &Distribution) {
+    info!(
+        stat,
+        min = d.min,
+        avg = d.avg,
+        max = d.max,
+        p50 = d.p50,
+        p90 = d.p90,
+        p99 = d.p99,
+        "{label}: min {:.0} / avg {:.1} / max {:.0} (p50 {:.0}, p90 {:.0}, p99 {:.0})",
+        d.min,
+        d.avg,
+        d.max,
+        d.p50,
+        d.p90,
+        d.p99
+    );
+}
+
+/// `--dry-run` report: what the conversion would produce, nothing written.
+fn dry_run(
+    mut payload: Payload,
+    write_opts: &WriteOpts,
+    frame_stats: bool,
+    out_file: &Path,
+) -> anyhow::Result<Payload> {
+    let s = payload_stats(&payload)?;
+    info!("🧪 Dry run, nothing written");
+    info!(
+        width = s.width,
+        height = s.height,
+        fps = s.fps,
+        "📐 {}×{} @ {}fps",
+        s.width,
+        s.height,
+        s.fps
+    );
+    info!(
+        frames_count = s.frames_count,
+        duration_secs = s.duration_secs,
+        "🎞️  Frames: {} ({:.2}s)",
+        s.frames_count,
+        s.duration_secs
+    );
+    if let Some(th) = &s.thresholds {
+        log_distribution("🎚️  Threshold/frame", "threshold", th);
+    }
+    info!(total_rects = s.total_rects, "🧱 Total rects: {}", s.total_rects);
+    log_distribution("🧮 Rects/frame", "rects_per_frame", &s.rects_per_frame);
+    info!(
+        coverage = s.coverage_ratio,
+        "⬛ Coverage: {:.2}%",
+        s.coverage_ratio * 100.0
+    );
+
+    // Per-frame stats were only turned on for the report
+    if !frame_stats {
+        payload.frame_thresholds = None;
+        payload.frame_rect_counts = None;
+    }
+    let len = encoded_len(&payload, write_opts)?;
+    info!(
+        format = %write_opts.format,
+        bytes = len,
+        out = %out_file.display(),
+        "💾 {} output: {:.1} KiB ({:.0} B/frame), would be written to {}",
+        write_opts.format,
+        len as f64 / 1024.0,
+        len as f64 / s.frames_count.max(1) as f64,
+        out_file.display()
+    );
+
+    Ok(payload)
+}
+
+impl ConvertArgs {
+    /// `--out -`: the payload goes to stdout, so logs have to go to stderr.
+    pub fn writes_stdout(&self) -> bool {
+        self.out == "-"
+    }
+
+    pub(crate) fn out(&self) -> &Path {
+        Path::new(&self.out)
+    }
+
+    pub(crate) fn is_pretty(&self) -> bool {
+        self.pretty
+    }
+
+    pub(crate) fn input(&self) -> &str {
+        &self.r#in
+    }
+
+    pub(crate) fn lyrics(&self) -> Option<&Path> {
+        self.lyrics.as_deref()
+    }
+
+    pub(crate) fn out_file(&self) -> &Path {
+        Path::new(&self.out)
+    }
+
+    /// Whether the payload file is already there (never for stdout).
+    pub(crate) fn output_exists(&self) -> bool {
+        !self.writes_stdout() && Path::new(&self.out).exists()
+    }
+
+    pub(crate) fn write_opts(&self) -> WriteOpts {
+        WriteOpts {
+            format: self.format,
+            xor: self.xor,
+            pretty: self.pretty,
+        }
+    }
+
+    /// `--watch` can redo just the changed frames (`reconvert_frames`): one
+    /// payload, no frame depending on the others.
+    pub(crate) fn incremental(&self) -> bool {
+        self.watch
+            && self.sizes.is_empty()
+            && self.temporal.is_none()
+            && self.interpolate.is_none()
+            && self.background.is_none()
+    }
+
+    /// `payload` as written: without the per-frame stats when they were only
+    /// turned on for `reconvert_frames`.
+    pub(crate) fn written<'a>(&self, payload: &'a Payload) -> Cow<'a, Payload> {
+        if self.frame_stats || !self.incremental() {
+            return Cow::Borrowed(payload);
+        }
+        Cow::Owned(Payload {
+            frame_thresholds: None,
+            frame_rect_counts: None,
+            ..payload.clone()
+        })
+    }
+
+    /// The conversion settings for `in_dir`, without progress reporting.
+    pub(crate) fn opts<'a>(&self, in_dir: &'a Path) -> ConvertRectframesOptsBuilder<'a> {
+        let opts = ConvertRectframesOpts::builder(in_dir)
+            .size(self.w, self.h)
+            .fps(self.fps)
+            .invert(self.invert == 1)
+            .th_mul(self.th_mul)
+            .threads(self.threads)
+            // Per-frame stats are kept for the report, or for `reconvert_frames`
+            .frame_stats(self.frame_stats || self.dry_run || self.incremental())
+            .off_rects(self.off_rects)
+            .pretty(self.pretty)
+            .sizes(
+                self.sizes
+                    .iter()
+                    .copied()
+                    .filter(|&size| size != (self.w, self.h))
+                    .collect(),
+            )
+            .threshold(self.threshold)
+            .gamma(self.gamma)
+            .brightness(self.brightness)
+            .contrast(self.contrast);
+        let opts = match self.hysteresis {
+            Some(h) => opts.hysteresis(h),
+            None => opts,
+        };
+        let opts = match self.blur {
+            Some(sigma) => opts.blur(sigma),
+            None => opts,
+        };
+        let opts = match self.equalize {
+            Some(eq) => opts.equalize(eq),
+            None => opts,
+        };
+        let opts = match self.background {
+            Some(bg) => opts.background(bg),
+            None => opts,
+        };
+        let opts = match &self.mask {
+            Some(mask) => opts.mask(mask),
+            None => opts,
+        };
+        let opts = match self.interpolate {
+            Some(mode) => opts.interpolate(mode),
+            None => opts,
+        };
+        let opts = match self.temporal {
+            Some(t) => opts.temporal(t),
+            None => opts,
+        };
+        #[cfg(feature = "gpu")]
+        let opts = opts.gpu(self.gpu);
+        opts
+    }
+}
+
+/// Runs the conversion (or `--watch`); the caller has installed the
+/// subscriber from `log` (on stderr when `writes_stdout`).
+pub fn run(args: ConvertArgs, log: &LogArgs) -> anyhow::Result<()> {
+    if args.watch {
+        return watch::watch(args, log);
+    }
+    convert_once(args, log)?;
+    Ok(())
+}
+
+/// One conversion, returning the payload (with per-frame stats when
+/// `incremental`, whatever was written).
+pub(crate) fn convert_once(args: ConvertArgs, log: &LogArgs) -> anyhow::Result<Payload> {
+    let to_stdout = args.writes_stdout();
+
+    #[cfg(feature = "http")]
+    let in_dir = if crate::remote::is_url(&args.r#in) {
+        crate::remote::RemoteSource::new(&args.r#in, &args.cache_dir)
+            .retries(args.retries)
+            .fetch()?
+    } else {
+        PathBuf::from(&args.r#in)
+    };
+    #[cfg(not(feature = "http"))]
+    let in_dir = PathBuf::from(&args.r#in);
+    let out_file = PathBuf::from(&args.out);
+
+    let opts = args.opts(&in_dir);
+    let bar = progress_bar(
+        args.stdin_raw,
+        !log.quiet && log.log_format == LogFormat::Text,
+    );
+    let opts = opts
+        .on_progress({
+            let bar = bar.clone();
+            move |p| {
+                if let Some(n) = p.total {
+                    bar.set_length(n as u64);
+                }
+                bar.set_position(p.done as u64);
+                bar.set_message(format!("th {:.0} · {} rects", p.threshold, p.rects));
+                // Clear before the library logs the result
+                if Some(p.done) == p.total {
+                    bar.finish_and_clear();
+                }
+            }
+        })
+        .build()?;
+
+    if to_stdout && !args.sizes.is_empty() {
+        anyhow::bail!("--sizes writes one file per size, it can't go to stdout (--out -)");
+    }
+
+    let payload = match args.format {
+        _ if args.dry_run => {
+            let payload = if args.stdin_raw {
+                let payload = convert_luma_frames(
+                    RawFrames::new(io::stdin().lock(), args.w, args.h),
+                    &opts,
+                )?;
+                bar.finish_and_clear();
+                payload
+            } else {
+                convert_rectframes(opts.clone())?
+            };
+            let write_opts = args.write_opts();
+            dry_run(payload, &write_opts, args.frame_stats, &out_file)?
+        }
+        format if !args.sizes.is_empty() => {
+            let payloads = convert_rectframes_sizes(opts.clone())?;
+            let write_opts = args.write_opts();
+            let manifest = write_sizes(&payloads, &out_file, &write_opts)?;
+            info!(
+                format = %format,
+                sizes = payloads.len(),
+                manifest = %manifest.display(),
+                "✅ {} payloads at {} sizes, manifest: {}",
+                format,
+                payloads.len(),
+                manifest.display()
+            );
+            payloads.into_iter().next().expect("base payload")
+        }
+        OutputFormat::Json if !args.stdin_raw && !to_stdout && !args.incremental() => {
+            let payload = convert_rectframes_to_file(opts.clone(), &out_file)?;
+            let bytes = file_len(&out_file);
+            info!(bytes, "💾 {:.1} KiB", bytes as f64 / 1024.0);
+            payload
+        }
+        format => {
+            let payload = if args.stdin_raw {
+                let payload = convert_luma_frames(
+                    RawFrames::new(io::stdin().lock(), args.w, args.h),
+                    &opts,
+                )?;
+                bar.finish_and_clear();
+                payload
+            } else {
+                convert_rectframes(opts.clone())?
+            };
+            let write_opts = args.write_opts();
+            let written = args.written(&payload);
+            let (bytes, dest) = if to_stdout {
+                let bytes = payload_bytes(&written, &write_opts)?;
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(&bytes)?;
+                stdout.flush()?;
+                (bytes.len() as u64, String::from("stdout"))
+            } else {
+                write_payload(&written, &out_file, &write_opts)?;
+                (file_len(&out_file), out_file.display().to_string())
+            };
+            info!(
+                format = %format,
+                out = %dest,
+                bytes,
+                frames_count = payload.frames_count,
+                threshold = payload.threshold,
+                "✅ {} payload written: {} ({:.1} KiB)",
+                format,
+                dest,
+                bytes as f64 / 1024.0
+            );
+            payload
+        }
+    };
+
+    if args.metrics {
+        let report = evaluate_quality(&payload, &opts)?;
+        info!(
+            avg_accuracy = report.avg_accuracy,
+            "📊 avg accuracy: {:.4}%",
+            report.avg_accuracy * 100.0
+        );
+        info!(avg_iou = report.avg_iou, "📊 avg IoU: {:.4}", report.avg_iou);
+        info!(
+            worst_accuracy_frame = report.worst_accuracy.frame,
+            worst_accuracy = report.worst_accuracy.accuracy,
+            "📉 worst accuracy: frame {} ({:.4}%)",
+            report.worst_accuracy.frame,
+            report.worst_accuracy.accuracy * 100.0
+        );
+        info!(
+            worst_iou_frame = report.worst_iou.frame,
+            worst_iou = report.worst_iou.iou,
+            "📉 worst IoU: frame {} ({:.4})",
+            report.worst_iou.frame,
+            report.worst_iou.iou
+        );
+    }
+
+    Ok(payload)
+}
@@ -0,0 +1,12 @@
+// src/lib/cli/mod.rs
+// Subcommands shared by the `bad-apple` binary and the standalone tools:
+// each module has its clap `Args` struct and a `run` function, so
+// `bad-apple convert` and `convert_rectframes` take the same options.
+
+pub mod batch;
+pub mod convert;
+pub mod play;
+#[cfg(feature = "server")]
+pub mod serve;
+pub mod tune;
+mod watch;
@@ -0,0 +1,225 @@
+// src/lib/cli/play.rs
+// `bad-apple play` / `play`: terminal player with a status bar, seeking,
+// speed and loop controls, optionally synced to a soundtrack.
+
+use anyhow::Result;
+use clap::Args;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::playback::{format_time, PlaybackClock};
+use crate::rectframes::Payload;
+use crate::term::Renderer;
+
+#[derive(Args, Debug)]
+pub struct PlayArgs {
+    /// Payload to play
+    #[arg(default_value = "out/rectFrames.json")]
+    input: PathBuf,
+
+    /// Renderer: blocks (1×2 px per cell), braille (2×4 px per cell) or sixel (real pixels)
+    #[arg(long, default_value = "blocks")]
+    renderer: Renderer,
+
+    /// Light up the silhouette instead of the background
+    #[arg(long)]
+    invert: bool,
+
+    /// Max columns (defaults to the terminal width)
+    #[arg(long)]
+    cols: Option<u16>,
+
+    /// Max rows (defaults to the terminal height)
+    #[arg(long)]
+    rows: Option<u16>,
+
+    /// Hide the status bar (toggle with `h`)
+    #[arg(long)]
+    no_hud: bool,
+
+    /// Soundtrack to play in sync (the video follows the audio clock)
+    #[cfg(feature = "audio")]
+    #[arg(long)]
+    audio: Option<PathBuf>,
+}
+
+const SEEK_SECS: f64 = 5.0;
+
+/// Puts the terminal in raw/alternate-screen mode and restores it on drop.
+pub(crate) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(crate) fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// One-line status bar: state, time, frame, rect count, speed and loop flag.
+fn hud_line(clock: &PlaybackClock, payload: &Payload, i: usize) -> String {
+    format!(
+        " {} {} / {} · frame {}/{} · {} rects · {}×{} · ␣ pause ←→ seek ,. step -+ speed l loop h hud q quit",
+        if clock.is_playing() { "▶" } else { "⏸" },
+        format_time(clock.position()),
+        format_time(clock.duration()),
+        i + 1,
+        payload.rect_frames.len(),
+        payload.rect_frames[i].len(),
+        clock.speed(),
+        if clock.is_looping() { " · loop" } else { "" },
+    )
+}
+
+pub(crate) fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Character cell size in pixels, when the terminal reports its pixel dimensions.
+pub(crate) fn cell_px() -> Option<(usize, usize)> {
+    let ws = terminal::window_size().ok()?;
+    if ws.columns == 0 || ws.rows == 0 || ws.width == 0 || ws.height == 0 {
+        return None;
+    }
+    Some((
+        (ws.width / ws.columns) as usize,
+        (ws.height / ws.rows) as usize,
+    ))
+}
+
+/// Plays `args.input` until it ends or `q` is pressed.
+pub fn run(args: PlayArgs) -> Result<()> {
+    let payload = Payload::from_file(&args.input)?;
+
+    if payload.fps == 0 || payload.rect_frames.is_empty() {
+        anyhow::bail!("Nothing to play in {}", args.input.display());
+    }
+
+    let fps = payload.fps as f64;
+    let (fw, fh) = (payload.width as usize, payload.height as usize);
+
+    let _guard = TerminalGuard::enter()?;
+    let mut out = io::stdout();
+    let mut hud = !args.no_hud;
+    let mut last: Option<(usize, (u16, u16), bool)> = None;
+    let mut last_hud = String::new();
+
+    let mut clock = PlaybackClock::new(payload.fps, payload.rect_frames.len());
+    #[cfg(feature = "audio")]
+    if let Some(path) = &args.audio {
+        clock = clock.with_audio(crate::audio::AudioTrack::open(path)?);
+    }
+
+    loop {
+        // Frame index follows the clock, so slow terminals drop frames instead of drifting
+        clock.tick();
+        if clock.is_finished() {
+            break;
+        }
+        let i = clock.frame();
+
+        let (tc, tr) = terminal::size()?;
+        let renderer = args.renderer.with_cell_px(cell_px());
+
+        // The HUD takes the last row; sixel keeps off it anyway so the terminal never scrolls
+        let reserved = if hud || !renderer.is_text() { 1 } else { 0 };
+        let avail_rows = tr.saturating_sub(reserved).max(1);
+        let max_cols = args.cols.unwrap_or(tc).min(tc) as usize;
+        let max_rows = args.rows.unwrap_or(avail_rows).min(avail_rows) as usize;
+
+        let mut dirty = false;
+        if last != Some((i, (tc, tr), hud)) {
+            if last.map(|(_, size, hud)| (size, hud)) != Some(((tc, tr), hud)) {
+                queue!(out, terminal::Clear(ClearType::All))?;
+                last_hud.clear();
+            }
+
+            let (cols, rows) = renderer.fit_grid(fw, fh, max_cols, max_rows);
+            let text =
+                renderer.render(&payload.rasterize_frame(i), fw, fh, cols, rows, args.invert);
+
+            let left = (tc as usize).saturating_sub(cols) / 2;
+            let top = (avail_rows as usize).saturating_sub(rows) / 2;
+            if renderer.is_text() {
+                for (row, line) in text.split("\r\n").enumerate() {
+                    queue!(
+                        out,
+                        cursor::MoveTo(left as u16, (top + row) as u16),
+                        Print(line)
+                    )?;
+                }
+            } else {
+                queue!(out, cursor::MoveTo(left as u16, top as u16), Print(text))?;
+            }
+            last = Some((i, (tc, tr), hud));
+            dirty = true;
+        }
+
+        if hud {
+            let line = hud_line(&clock, &payload, i);
+            if line != last_hud {
+                let line: String = line.chars().take(tc as usize).collect();
+                queue!(
+                    out,
+                    cursor::MoveTo(0, tr.saturating_sub(1)),
+                    terminal::Clear(ClearType::CurrentLine),
+                    Print(&line)
+                )?;
+                last_hud = line;
+                dirty = true;
+            }
+        }
+        if dirty {
+            out.flush()?;
+        }
+
+        // Sleep until the next frame is due (or a key arrives)
+        let wait = if clock.is_playing() {
+            let next = (i + 1) as f64 / fps;
+            ((next - clock.position()) / clock.speed()).clamp(0.0, 1.0 / fps)
+        } else {
+            0.1
+        };
+        if event::poll(Duration::from_secs_f64(wait))?
+            && let Event::Key(key) = event::read()?
+            && key.kind != KeyEventKind::Release
+        {
+            if is_quit(&key) {
+                break;
+            }
+            match key.code {
+                KeyCode::Char(' ') => clock.toggle_pause(),
+                KeyCode::Right => clock.seek_by(SEEK_SECS),
+                KeyCode::Left => clock.seek_by(-SEEK_SECS),
+                KeyCode::Char('.') => clock.step(1),
+                KeyCode::Char(',') => clock.step(-1),
+                KeyCode::Char('+' | '=' | ']') => clock.cycle_speed(1),
+                KeyCode::Char('-' | '[') => clock.cycle_speed(-1),
+                KeyCode::Char('l') => clock.toggle_loop(),
+                KeyCode::Char('h') => hud = !hud,
+                KeyCode::Home | KeyCode::Char('0') => clock.seek_to(0.0),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
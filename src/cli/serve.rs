@@ -0,0 +1,401 @@
+// src/lib/cli/serve.rs
+// `bad-apple serve` / `server`: static server for the userscript and the OBS
+// page, generating the payload and subtitle JSON first when they're missing.
+
+use actix_cors::Cors;
+use actix_files::Files;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use anyhow::{Context, Result};
+use crate::cli::batch::convert_manifest;
+use crate::export::html::obs_page;
+use crate::logging::LogArgs;
+use crate::rectframes::{convert_rectframes_to_file_async, ConvertRectframesOpts};
+use crate::subs::{
+    lang_from_path, srt_to_json_file_with, write_subs_bundle, SubsOpts, BUNDLE_FILE,
+};
+use clap::Args;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::{info, warn};
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Directory to serve (e.g. "out")
+    #[arg(long, default_value = "out")]
+    dir: String,
+
+    /// Directory containing PNG frames
+    #[arg(long, default_value = "frames")]
+    frames_dir: String,
+
+    /// Directory containing SRT lyrics
+    #[arg(long, default_value = "lyrics")]
+    lyrics_dir: String,
+
+    /// Payload file name in --dir, generated from --frames-dir when missing
+    #[arg(long, default_value = "rectFrames.json")]
+    payload_name: String,
+
+    /// Subtitle to convert, as an SRT name in --lyrics-dir and optionally the JSON name in
+    /// --dir (default: the SRT name with .json); repeat for each track. Missing SRTs only
+    /// skip their track
+    #[arg(
+        long = "subtitle",
+        value_name = "SRT[=JSON]",
+        value_parser = parse_subtitle,
+        default_values = [
+            "transcript_jp.srt",
+            "transcript_romaji.srt",
+            "transcript_en.srt",
+            "transcript_es.srt",
+        ]
+    )]
+    subtitles: Vec<(String, String)>,
+
+    /// Subtitle languages instead of the --subtitle tracks: transcript_LANG.srt in
+    /// --lyrics-dir for each (comma-separated, e.g. jp,en), or `auto` for every
+    /// transcript_*.srt there
+    #[arg(long, value_name = "LANGS", value_delimiter = ',', conflicts_with = "subtitles")]
+    langs: Vec<String>,
+
+    /// Also write every subtitle track into one subtitles.json in --dir, keyed by the
+    /// language of its JSON name (transcript_jp.json -> jp)
+    #[arg(long)]
+    bundle: bool,
+
+    /// Converter width/height/fps
+    #[arg(long, default_value_t = 256)]
+    w: u32,
+
+    #[arg(long, default_value_t = 192)]
+    h: u32,
+
+    #[arg(long, default_value_t = 30)]
+    fps: u32,
+
+    /// Invert output (0/1)
+    #[arg(long, default_value_t = 0)]
+    invert: u8,
+
+    /// Threshold multiplier
+    #[arg(long, default_value_t = 0.95)]
+    th_mul: f32,
+
+    /// Bind host
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Bind port
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// URL mount path
+    #[arg(long, default_value = "/out")]
+    mount: String,
+
+    /// Converter settings bundle (size, fps, invert, th-mul), e.g. web256; see `convert --preset`
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Settings file with user presets (defaults to ./bad-apple.toml when present)
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Serve the projects of a batch manifest (see `bad-apple batch`) from --dir instead of
+    /// a single payload, converting the ones missing first; each project's OBS page is at
+    /// /obs/NAME
+    #[arg(long, value_name = "MANIFEST")]
+    pub batch: Option<PathBuf>,
+
+    /// Write generated JSON (rectFrames, subtitles) indented, for debugging
+    #[arg(long)]
+    pretty: bool,
+
+    #[command(flatten)]
+    pub log: LogArgs,
+}
+
+/// `a.srt=b.json` -> (a.srt, b.json); `a.srt` -> (a.srt, a.json).
+fn parse_subtitle(s: &str) -> Result<(String, String), String> {
+    let (srt, json) = match s.split_once('=') {
+        Some((srt, json)) => (srt, json.to_string()),
+        None => {
+            let stem = Path::new(s)
+                .file_stem()
+                .ok_or_else(|| format!("not a file name: {s:?}"))?;
+            (s, format!("{}.json", stem.to_string_lossy()))
+        }
+    };
+    if srt.is_empty() || json.is_empty() {
+        return Err(format!("expected SRT[=JSON], got {s:?}"));
+    }
+    Ok((srt.to_string(), json))
+}
+
+fn mtime(p: &Path) -> Result<SystemTime> {
+    Ok(fs::metadata(p)?.modified()?)
+}
+
+fn needs_regen(src: &Path, dst: &Path) -> bool {
+    if !dst.exists() {
+        return true;
+    }
+    match (mtime(src), mtime(dst)) {
+        (Ok(s), Ok(d)) => s > d,
+        _ => true,
+    }
+}
+
+/// The `--subtitle` tracks, or `--langs` ones.
+fn subtitle_tracks(
+    langs: &[String],
+    subtitles: &[(String, String)],
+    lyrics_dir: &Path,
+) -> Vec<(String, String)> {
+    let track = |lang: &str| (format!("transcript_{lang}.srt"), format!("transcript_{lang}.json"));
+    match langs {
+        [] => subtitles.to_vec(),
+        [auto] if auto == "auto" => {
+            let entries = match fs::read_dir(lyrics_dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Can't read {} ({e}), no subtitles", lyrics_dir.display());
+                    return Vec::new();
+                }
+            };
+            let mut tracks: Vec<_> = entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name().into_string().ok()?;
+                    let lang = name.strip_prefix("transcript_")?.strip_suffix(".srt")?;
+                    (!lang.is_empty()).then(|| track(lang))
+                })
+                .collect();
+            tracks.sort();
+            if tracks.is_empty() {
+                warn!("No transcript_*.srt in {}", lyrics_dir.display());
+            }
+            tracks
+        }
+        langs => langs.iter().map(|lang| track(lang.trim())).collect(),
+    }
+}
+
+/// Converts each SRT newer than its JSON; returns the tracks that have a
+/// JSON. A missing SRT only skips its track (keeping a JSON already there).
+fn ensure_subtitle_jsons(
+    out_dir: &Path,
+    lyrics_dir: &Path,
+    subtitles: &[(String, String)],
+    pretty: bool,
+) -> Result<Vec<(String, String)>> {
+    let mut available = Vec::new();
+    for (srt_name, json_name) in subtitles {
+        let srt_path = lyrics_dir.join(srt_name);
+        let json_path = out_dir.join(json_name);
+
+        if !srt_path.exists() {
+            if json_path.exists() {
+                info!(
+                    out = %json_path.display(),
+                    "📝 OK {} (no {})",
+                    json_path.display(),
+                    srt_path.display()
+                );
+                available.push((srt_name.clone(), json_name.clone()));
+            } else {
+                warn!(
+                    srt = %srt_path.display(),
+                    "Missing SRT: {}, skipping that track",
+                    srt_path.display()
+                );
+            }
+            continue;
+        }
+
+        if needs_regen(&srt_path, &json_path) {
+            info!(out = %json_path.display(), "📝 Generating {}", json_path.display());
+            srt_to_json_file_with(&srt_path, &json_path, pretty).with_context(|| {
+                format!(
+                    "Failed converting {} -> {}",
+                    srt_path.display(),
+                    json_path.display()
+                )
+            })?;
+        } else {
+            info!(out = %json_path.display(), "📝 OK {}", json_path.display());
+        }
+        available.push((srt_name.clone(), json_name.clone()));
+    }
+    Ok(available)
+}
+
+/// Writes `BUNDLE_FILE` from the subtitle JSONs when any is newer than it.
+fn ensure_subtitle_bundle(out_dir: &Path, subtitles: &[(String, String)], pretty: bool) -> Result<()> {
+    let bundle_path = out_dir.join(BUNDLE_FILE);
+    let tracks: Vec<(String, PathBuf)> = subtitles
+        .iter()
+        .map(|(_, json)| (lang_from_path(Path::new(json)), out_dir.join(json)))
+        .collect();
+
+    let stale = tracks.iter().any(|(_, json)| needs_regen(json, &bundle_path));
+    if stale || !bundle_path.exists() {
+        info!(out = %bundle_path.display(), "📝 Generating {}", bundle_path.display());
+        let opts = SubsOpts {
+            pretty,
+            ..SubsOpts::default()
+        };
+        write_subs_bundle(&tracks, &bundle_path, &opts)
+            .with_context(|| format!("Failed writing {}", bundle_path.display()))?;
+    } else {
+        info!(out = %bundle_path.display(), "📝 OK {}", bundle_path.display());
+    }
+    Ok(())
+}
+
+async fn ensure_rectframes(out_dir: &Path, frames_dir: &Path, args: &ServeArgs) -> Result<()> {
+    let rect_path = out_dir.join(&args.payload_name);
+    if rect_path.exists() {
+        info!(out = %rect_path.display(), "🎞️ OK {}", rect_path.display());
+        return Ok(());
+    }
+
+    warn!(
+        out = %rect_path.display(),
+        "Missing {} — generating via library…",
+        rect_path.display()
+    );
+
+    let opts = ConvertRectframesOpts::builder(frames_dir)
+        .size(args.w, args.h)
+        .fps(args.fps)
+        .invert(args.invert == 1)
+        .th_mul(args.th_mul)
+        .pretty(args.pretty)
+        .build()?;
+
+    convert_rectframes_to_file_async(opts, &rect_path)
+        .await
+        .context("rectFrames generation failed")?;
+
+    Ok(())
+}
+
+/// Transparent, controls-free player for OBS browser sources.
+async fn obs(page: web::Data<String>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(page.get_ref().clone())
+}
+
+/// Payload URL of each project of a `--batch`, for their OBS pages.
+struct PayloadUrls(HashMap<String, String>);
+
+/// OBS player for one project of a `--batch`.
+async fn obs_project(project: web::Path<String>, urls: web::Data<PayloadUrls>) -> HttpResponse {
+    match urls.0.get(project.as_str()) {
+        Some(url) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(obs_page(url)),
+        None => HttpResponse::NotFound().body(format!("No project {project}")),
+    }
+}
+
+/// Builds the payload and subtitles if needed, then serves `--dir` until
+/// the process is stopped.
+pub fn run(args: ServeArgs) -> io::Result<()> {
+    actix_web::rt::System::new().block_on(serve(args))
+}
+
+async fn serve(args: ServeArgs) -> io::Result<()> {
+    let out_dir = PathBuf::from(&args.dir);
+    let frames_dir = PathBuf::from(&args.frames_dir);
+    let lyrics_dir = PathBuf::from(&args.lyrics_dir);
+
+    fs::create_dir_all(&out_dir).ok();
+
+    // ✅ Build pipeline before serving
+    let projects = if let Some(manifest) = &args.batch {
+        convert_manifest(manifest, &out_dir, &[], true, &args.log)
+            .context("batch conversion failed")
+            .unwrap()
+    } else {
+        ensure_rectframes(&out_dir, &frames_dir, &args)
+            .await
+            .context("ensure_rectframes failed")
+            .unwrap();
+
+        let tracks = subtitle_tracks(&args.langs, &args.subtitles, &lyrics_dir);
+        let tracks = ensure_subtitle_jsons(&out_dir, &lyrics_dir, &tracks, args.pretty)
+            .context("ensure_subtitle_jsons failed")
+            .unwrap();
+        if args.bundle {
+            ensure_subtitle_bundle(&out_dir, &tracks, args.pretty)
+                .context("ensure_subtitle_bundle failed")
+                .unwrap();
+        }
+        Vec::new()
+    };
+
+    let bind_addr = format!("{}:{}", args.host, args.port);
+
+    info!(
+        "📡 Serving '{}' at http://{}{}",
+        args.dir, bind_addr, args.mount
+    );
+    if projects.is_empty() {
+        info!(
+            "🎥 OBS browser source: http://{}/obs (optional ?w=1920&h=1080&fg=fff&bg=00ff00)",
+            bind_addr
+        );
+    }
+    let mount = args.mount.trim_end_matches('/');
+    for project in &projects {
+        info!(
+            "🎥 {}: http://{}{}/{}/ · OBS http://{}/obs/{}",
+            project.name,
+            bind_addr,
+            mount,
+            project.name,
+            bind_addr,
+            project.name
+        );
+    }
+
+    let obs_html = web::Data::new(obs_page(&format!("{mount}/{}", args.payload_name)));
+    let payload_urls = web::Data::new(PayloadUrls(
+        projects
+            .iter()
+            .map(|project| {
+                let path: Vec<_> = project.payload.iter().map(|c| c.to_string_lossy()).collect();
+                (project.name.clone(), format!("{mount}/{}", path.join("/")))
+            })
+            .collect(),
+    ));
+
+    HttpServer::new(move || {
+        App::new()
+            .wrap(
+                Cors::default()
+                    .allow_any_origin()
+                    .allow_any_method()
+                    .allow_any_header(),
+            )
+            .app_data(obs_html.clone())
+            .app_data(payload_urls.clone())
+            .route("/obs", web::get().to(obs))
+            .route("/obs/{project}", web::get().to(obs_project))
+            .service(
+                Files::new(&args.mount, &args.dir)
+                    .prefer_utf8(true)
+                    .use_last_modified(true),
+            )
+    })
+    .bind(bind_addr)?
+    .run()
+    .await
+}
@@ -0,0 +1,247 @@
+// src/lib/cli/tune.rs
+// `bad-apple tune`: binarizes one frame in the terminal while th_mul, the
+// threshold mode and invert change with the keys, showing the threshold and
+// rect count live. On exit it prints the matching converter flags and
+// bad-apple.toml lines.
+//
+// Frames are binarized at their own size, with the converter's code path
+// (`binarize_gray`, `merge_frame_to_rects`), so the rect count is the one the
+// payload will have.
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::binarize::ThresholdMode;
+use crate::cli::play::{cell_px, is_quit, TerminalGuard};
+use crate::rectframes::{binarize_gray, list_frames, merge_frame_to_rects, ConvertRectframesOpts};
+use crate::term::Renderer;
+
+/// Modes `t` cycles through.
+const MODES: &[ThresholdMode] = &[
+    ThresholdMode::Global,
+    ThresholdMode::Tiles(8),
+    ThresholdMode::Tiles(16),
+    ThresholdMode::Tiles(32),
+    ThresholdMode::Tiles(64),
+];
+
+#[derive(Args, Debug)]
+pub struct TuneArgs {
+    /// Frames directory
+    #[arg(default_value = "frames")]
+    pub dir: PathBuf,
+
+    /// Frame index to start on
+    #[arg(long, default_value_t = 0)]
+    pub frame: usize,
+
+    /// Starting threshold multiplier
+    #[arg(long, default_value_t = 0.95)]
+    pub th_mul: f32,
+
+    /// Starting threshold mode: global or tiles:N
+    #[arg(long, default_value_t = ThresholdMode::Global)]
+    pub threshold: ThresholdMode,
+
+    /// Start inverted (0/1)
+    #[arg(long, default_value_t = 0)]
+    pub invert: u8,
+
+    /// Renderer: blocks, braille or sixel
+    #[arg(long, default_value = "blocks")]
+    pub renderer: Renderer,
+}
+
+/// Settings being tuned.
+#[derive(Clone, Copy, Debug)]
+struct Tuning {
+    th_mul: f32,
+    threshold: ThresholdMode,
+    invert: bool,
+}
+
+impl Tuning {
+    fn flags(&self) -> String {
+        format!(
+            "--th-mul {:.2} --threshold {} --invert {}",
+            self.th_mul, self.threshold, self.invert as u8
+        )
+    }
+
+    fn toml(&self) -> String {
+        format!(
+            "[convert]\nth-mul = {:.2}\nthreshold = \"{}\"\ninvert = {}",
+            self.th_mul, self.threshold, self.invert as u8
+        )
+    }
+}
+
+/// One decoded frame.
+struct Sample {
+    path: PathBuf,
+    luma: Vec<u8>,
+    w: u32,
+    h: u32,
+}
+
+impl Sample {
+    fn load(path: &Path) -> Result<Self> {
+        let img = image::open(path)
+            .with_context(|| format!("Failed decoding {}", path.display()))?
+            .to_luma8();
+        let (w, h) = img.dimensions();
+        Ok(Self {
+            path: path.to_path_buf(),
+            luma: img.into_raw(),
+            w,
+            h,
+        })
+    }
+
+    /// Binarized frame, threshold and rect count with `tuning`.
+    fn binarize(&self, dir: &Path, tuning: &Tuning) -> Result<(Vec<u8>, f32, usize)> {
+        let opts = ConvertRectframesOpts::builder(dir)
+            .size(self.w, self.h)
+            .th_mul(tuning.th_mul)
+            .threshold(tuning.threshold)
+            .invert(tuning.invert)
+            .build()?;
+        let (frame, th) = binarize_gray(&self.luma, &opts.frame_params());
+        let rects = merge_frame_to_rects(&frame, self.w as usize, self.h as usize, false).len();
+        Ok((frame, th, rects))
+    }
+}
+
+/// Runs the tuner until `q`, then prints the chosen settings.
+pub fn run(args: TuneArgs) -> Result<()> {
+    let frames = list_frames(&args.dir)?;
+    if args.frame >= frames.len() {
+        bail!("Frame {} out of range (0..{})", args.frame, frames.len());
+    }
+    let mut tuning = Tuning {
+        th_mul: args.th_mul,
+        threshold: args.threshold,
+        invert: args.invert == 1,
+    };
+
+    {
+        let _guard = TerminalGuard::enter()?;
+        tune(&args, &frames, &mut tuning)?;
+    }
+
+    println!("{}", tuning.flags());
+    println!();
+    println!("{}", tuning.toml());
+    Ok(())
+}
+
+fn tune(args: &TuneArgs, frames: &[PathBuf], tuning: &mut Tuning) -> Result<()> {
+    let mut out = io::stdout();
+    let mut index = args.frame;
+    let mut sample = Sample::load(&frames[index])?;
+
+    loop {
+        let (tc, tr) = terminal::size()?;
+        let renderer = args.renderer.with_cell_px(cell_px());
+        let avail_rows = tr.saturating_sub(2).max(1);
+        let (frame, th, rects) = sample.binarize(&args.dir, tuning)?;
+        let (fw, fh) = (sample.w as usize, sample.h as usize);
+        let (cols, rows) = renderer.fit_grid(fw, fh, tc as usize, avail_rows as usize);
+        let text = renderer.render(&frame, fw, fh, cols, rows, false);
+
+        queue!(out, terminal::Clear(ClearType::All))?;
+        let left = (tc as usize).saturating_sub(cols) / 2;
+        if renderer.is_text() {
+            for (row, line) in text.split("\r\n").enumerate() {
+                queue!(out, cursor::MoveTo(left as u16, row as u16), Print(line))?;
+            }
+        } else {
+            queue!(out, cursor::MoveTo(left as u16, 0), Print(text))?;
+        }
+        let status = format!(
+            " {} ({}/{}) · th_mul {:.2} · {} · cut {:.0} · invert {} · {} rects",
+            sample.path.file_name().unwrap_or_default().to_string_lossy(),
+            index,
+            frames.len() - 1,
+            tuning.th_mul,
+            tuning.threshold,
+            th,
+            if tuning.invert { "on" } else { "off" },
+            rects,
+        );
+        let keys = " ↑↓ th_mul ±0.01 · PgUp/PgDn ±0.05 · ←→ frame (Shift ±30) · t mode · i invert · q done";
+        for (row, line) in [(tr.saturating_sub(2), status), (tr.saturating_sub(1), keys.into())] {
+            let line: String = line.chars().take(tc as usize).collect();
+            queue!(out, cursor::MoveTo(0, row), Print(line))?;
+        }
+        out.flush()?;
+
+        // Redraw only on input (or a resize)
+        let key = loop {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => break Some(key),
+                Event::Resize(..) => break None,
+                _ => {}
+            }
+        };
+        let Some(key) = key else {
+            continue;
+        };
+        if is_quit(&key) || key.code == KeyCode::Enter {
+            return Ok(());
+        }
+        let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+            30
+        } else {
+            1
+        };
+        let last = frames.len() - 1;
+        let next = match key.code {
+            KeyCode::Up => {
+                tuning.th_mul = (tuning.th_mul + 0.01).min(3.0);
+                None
+            }
+            KeyCode::Down => {
+                tuning.th_mul = (tuning.th_mul - 0.01).max(0.01);
+                None
+            }
+            KeyCode::PageUp => {
+                tuning.th_mul = (tuning.th_mul + 0.05).min(3.0);
+                None
+            }
+            KeyCode::PageDown => {
+                tuning.th_mul = (tuning.th_mul - 0.05).max(0.01);
+                None
+            }
+            KeyCode::Char('t') => {
+                let i = MODES.iter().position(|&m| m == tuning.threshold);
+                tuning.threshold = MODES[i.map_or(0, |i| (i + 1) % MODES.len())];
+                None
+            }
+            KeyCode::Char('i') => {
+                tuning.invert = !tuning.invert;
+                None
+            }
+            KeyCode::Right => Some((index + step).min(last)),
+            KeyCode::Left => Some(index.saturating_sub(step)),
+            KeyCode::Home => Some(0),
+            KeyCode::End => Some(last),
+            _ => None,
+        };
+        if let Some(next) = next.filter(|&n| n != index) {
+            index = next;
+            sample = Sample::load(&frames[index])?;
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// src/lib/cli/watch.rs
+// `convert --watch`: converts once, then again whenever the frames change,
+// for tuning settings with a player open on the output. SRTs in `--lyrics`
+// are converted one by one as they change, without touching the payload.
+//
+// Events are collected until the directories stay quiet for `SETTLE`, so an
+// ffmpeg run rewriting thousands of frames triggers a single conversion. When
+// only existing frames changed, just those are binarized again and spliced
+// into the last payload (`reconvert_frames`); added or removed frames, and
+// settings where frames depend on each other, convert everything again.
+
+use anyhow::{bail, Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+use tracing::{error, info, warn};
+
+use crate::cli::convert::{convert_once, ConvertArgs};
+use crate::format::write_payload;
+use crate::logging::LogArgs;
+use crate::rectframes::{list_frames, part_path, reconvert_frames, Payload};
+use crate::subs::srt_to_json_file_with;
+
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// The last conversion, for `reconvert_frames` to update.
+struct Converted {
+    payload: Payload,
+    /// Frame file name -> index in the payload
+    frames: HashMap<OsString, usize>,
+}
+
+pub(crate) fn watch(args: ConvertArgs, log: &LogArgs) -> Result<()> {
+    if !Path::new(args.input()).is_dir() {
+        bail!("--watch needs a frames directory as --in, not {}", args.input());
+    }
+    if args.writes_stdout() {
+        bail!("--watch writes the payload again on every change, it can't go to stdout (--out -)");
+    }
+    // notify reports absolute paths, so watch and compare absolute ones
+    let in_dir = fs::canonicalize(args.input())
+        .with_context(|| format!("Failed resolving {}", args.input()))?;
+    let lyrics = match args.lyrics() {
+        Some(dir) => Some(
+            fs::canonicalize(dir).with_context(|| format!("Failed resolving {}", dir.display()))?,
+        ),
+        None => None,
+    };
+    let out_file = args.out_file().to_path_buf();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed starting the file watcher")?;
+    watcher
+        .watch(&in_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed watching {}", in_dir.display()))?;
+    if let Some(dir) = &lyrics {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed watching {}", dir.display()))?;
+    }
+
+    let mut last = rerun(&args, log);
+    // Our own output can sit in a watched directory; it exists by now
+    let own = [absolute(&out_file), absolute(&part_path(&out_file))];
+    if let Some(dir) = &lyrics {
+        for srt in srts(dir)? {
+            convert_srt(&srt, &out_file, args.is_pretty());
+        }
+    }
+
+    let watching = || info!("👀 Watching {} for changes (Ctrl+C to stop)", in_dir.display());
+    watching();
+    loop {
+        // Block for the first event, then gather the rest of the burst
+        let mut changed = BTreeSet::new();
+        let mut next = rx.recv().context("File watcher stopped")?;
+        loop {
+            match next {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    changed.extend(event.paths)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("File watcher: {e}"),
+            }
+            match rx.recv_timeout(SETTLE) {
+                Ok(event) => next = event,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => bail!("File watcher stopped"),
+            }
+        }
+        for path in &own {
+            changed.remove(path);
+        }
+
+        let (srts, frames): (Vec<PathBuf>, Vec<PathBuf>) = changed
+            .into_iter()
+            .partition(|p| p.extension().is_some_and(|x| x == "srt"));
+        let frames: Vec<PathBuf> = frames
+            .into_iter()
+            .filter(|p| p.parent() == Some(&in_dir) && p.extension().is_some_and(|x| x == "png"))
+            .collect();
+        if frames.is_empty() && srts.is_empty() {
+            continue;
+        }
+        if !frames.is_empty() {
+            info!(changed = frames.len(), "🔁 {} frame file(s) changed", frames.len());
+            last = last
+                .take()
+                .and_then(|c| update(c, &args, &frames))
+                .or_else(|| rerun(&args, log));
+        }
+        for srt in srts.iter().filter(|p| p.is_file()) {
+            convert_srt(srt, &out_file, args.is_pretty());
+        }
+        watching();
+    }
+}
+
+/// One full conversion; errors are logged, the watch goes on. Returns the
+/// payload when later changes can be applied to it.
+fn rerun(args: &ConvertArgs, log: &LogArgs) -> Option<Converted> {
+    let payload = match convert_once(args.clone(), log) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("{e:#}");
+            return None;
+        }
+    };
+    if !args.incremental() {
+        return None;
+    }
+    let files = list_frames(Path::new(args.input())).ok()?;
+    let frames = files
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, fp)| Some((fp.file_name()?.to_owned(), i)))
+        .collect();
+    Some(Converted { payload, frames })
+}
+
+/// Re-binarizes the `changed` frames into `last` and writes it, or `None`
+/// when a full conversion is needed (a frame was added or removed, or the
+/// update failed).
+fn update(mut last: Converted, args: &ConvertArgs, changed: &[PathBuf]) -> Option<Converted> {
+    let mut indices = Vec::with_capacity(changed.len());
+    for fp in changed {
+        let i = fp.file_name().and_then(|name| last.frames.get(name));
+        match i {
+            Some(&i) if fp.is_file() => indices.push(i),
+            _ => return None,
+        }
+    }
+
+    if let Err(e) = splice(&mut last.payload, args, &indices) {
+        warn!("{e:#}, converting everything again");
+        return None;
+    }
+    let out_file = args.out_file();
+    info!(
+        frames = indices.len(),
+        out = %out_file.display(),
+        threshold = last.payload.threshold,
+        "✅ {} frame(s) updated in {}",
+        indices.len(),
+        out_file.display()
+    );
+    Some(last)
+}
+
+fn splice(payload: &mut Payload, args: &ConvertArgs, indices: &[usize]) -> Result<()> {
+    let opts = args.opts(Path::new(args.input())).build()?;
+    reconvert_frames(payload, &opts, indices)?;
+    write_payload(&args.written(payload), args.out_file(), &args.write_opts())?;
+    Ok(())
+}
+
+/// `path` with its directory resolved, like the paths notify reports (the
+/// file itself may not exist).
+fn absolute(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(dir), path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// `lyrics/x.srt` -> `x.json` next to the payload.
+fn convert_srt(srt: &Path, out_file: &Path, pretty: bool) {
+    let name = srt.with_extension("json");
+    let json = out_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(name.file_name().expect("srt file"));
+    match srt_to_json_file_with(srt, &json, pretty) {
+        Ok(()) => info!(out = %json.display(), "📝 Wrote {}", json.display()),
+        Err(e) => error!("{}: {e:#}", srt.display()),
+    }
+}
+
+fn srts(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut srts: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed reading {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "srt"))
+        .collect();
+    srts.sort();
+    Ok(srts)
+}
@@ -0,0 +1,221 @@
+// src/lib/config.rs
+// Project settings file (`bad-apple.toml`) for the converter, so a project
+// keeps its size, fps, threshold mode, format and paths in one place:
+//
+//   [convert]
+//   w = 256
+//   h = 192
+//   threshold = "tiles:16"
+//   format = "bitmap"
+//   out = "out/bad_apple.bin"
+//   sizes = ["128x96", "64x48"]
+//
+//   [presets.sd-card]
+//   w = 320
+//   h = 240
+//   format = "bitmap"
+//
+// Keys are the converter's flag names (`th-mul` or `th_mul`) with the same
+// values. They become the flags' defaults before parsing, so anything given
+// on the command line wins, and `--help` shows the values from the file.
+//
+// `--preset NAME` (converter and server) applies a named bundle of the same
+// keys on top of `[convert]`: one of `PRESETS` or a `[presets.NAME]` table,
+// which replaces a built-in one of the same name. The server only takes the
+// keys it has flags for (size, fps, invert, th-mul).
+
+use anyhow::{bail, Context, Result};
+use clap::{Command, CommandFactory, FromArgMatches, Parser};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Read from the working directory when no `--config` is given.
+pub const CONFIG_FILE: &str = "bad-apple.toml";
+
+/// Built-in presets, as `[presets.NAME]` tables.
+pub const PRESETS: &[(&str, &str)] = &[
+    // SSD1306 / SH1106 I2C OLEDs: packed frames, XOR deltas for the slow bus
+    ("oled128x64", "w = 128\nh = 64\nfps = 15\nformat = \"bitmap\"\nxor = true"),
+    ("oled128x32", "w = 128\nh = 32\nfps = 15\nformat = \"bitmap\"\nxor = true"),
+    // HUB75 panels, streamed with `bad-apple stream`
+    ("led64x32", "w = 64\nh = 32\nfps = 30\nth_mul = 0.9"),
+    // The userscript and browser players
+    ("web256", "w = 256\nh = 192\nfps = 30"),
+    ("web480", "w = 480\nh = 360\nfps = 30\nthreshold = \"tiles:16\""),
+];
+
+/// Which settings a command takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Settings {
+    /// `[convert]` and every preset key
+    Convert,
+    /// Only the preset keys the server has flags for
+    Serve,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Converter settings, by flag name
+    #[serde(default)]
+    pub convert: toml::Table,
+
+    /// User presets, by name
+    #[serde(default)]
+    pub presets: BTreeMap<String, toml::Table>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed reading {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text)
+            .with_context(|| format!("Failed parsing {}", path.display()))?;
+        config.path = path.to_path_buf();
+        Ok(config)
+    }
+
+    /// The file named by `--config` in `args`, else `CONFIG_FILE` if it exists.
+    pub fn find(args: &[OsString]) -> Result<Option<Self>> {
+        if let Some(path) = flag_value(args, "--config")? {
+            return Self::load(Path::new(&path)).map(Some);
+        }
+        let default = Path::new(CONFIG_FILE);
+        if default.is_file() {
+            return Self::load(default).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Preset `name`, from `[presets]` or `PRESETS`.
+    pub fn preset(&self, name: &str) -> Result<toml::Table> {
+        if let Some(table) = self.presets.get(name) {
+            return Ok(table.clone());
+        }
+        match PRESETS.iter().find(|(n, _)| *n == name) {
+            Some((_, text)) => Ok(toml::from_str(text).expect("valid built-in preset")),
+            None => {
+                let mut names: Vec<&str> = PRESETS.iter().map(|(n, _)| *n).collect();
+                names.extend(self.presets.keys().map(String::as_str));
+                bail!("Unknown preset `{name}` (available: {})", names.join(", "))
+            }
+        }
+    }
+
+    /// Sets the `[convert]` values (converter only), then those of `preset`,
+    /// as the defaults of `cmd`'s arguments. Without `preset`, the converter
+    /// takes `preset = ".."` from `[convert]`.
+    pub fn apply(
+        &self,
+        mut cmd: Command,
+        settings: Settings,
+        preset: Option<&str>,
+    ) -> Result<Command> {
+        let convert = settings == Settings::Convert;
+        let mut preset = preset.map(str::to_string);
+        if convert {
+            let what = format!("{} [convert]", self.path.display());
+            cmd = apply_table(cmd, &self.convert, &what, true)?;
+            if preset.is_none() {
+                preset = self
+                    .convert
+                    .get("preset")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+            }
+        }
+        if let Some(name) = preset {
+            let what = format!("preset {name}");
+            cmd = apply_table(cmd, &self.preset(&name)?, &what, convert)?;
+        }
+        Ok(cmd)
+    }
+}
+
+/// Sets `table`'s values as argument defaults; keys without a matching
+/// argument are an error when `strict`, skipped otherwise.
+pub(crate) fn apply_table(
+    mut cmd: Command,
+    table: &toml::Table,
+    what: &str,
+    strict: bool,
+) -> Result<Command> {
+    for (key, value) in table {
+        let id = key.replace('-', "_");
+        if !cmd.get_arguments().any(|a| a.get_id() == id.as_str()) {
+            if strict {
+                bail!("{what}: unknown setting `{key}`");
+            }
+            continue;
+        }
+        let values = match value {
+            toml::Value::Array(items) => items
+                .iter()
+                .map(|v| value_str(what, key, v))
+                .collect::<Result<Vec<_>>>()?,
+            v => vec![value_str(what, key, v)?],
+        };
+        cmd = cmd.mut_arg(id, |a| a.default_values(values));
+    }
+    Ok(cmd)
+}
+
+fn value_str(what: &str, key: &str, value: &toml::Value) -> Result<String> {
+    Ok(match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Integer(n) => n.to_string(),
+        toml::Value::Float(x) => x.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        _ => bail!("{what}: `{key}` must be a string, number, boolean or a list of them"),
+    })
+}
+
+/// Value of `--flag VALUE` / `--flag=VALUE` in `args`, read before clap parses them.
+fn flag_value(args: &[OsString], flag: &str) -> Result<Option<OsString>> {
+    let prefix = format!("{flag}=");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            let value = args.next().with_context(|| format!("{flag} needs a value"))?;
+            return Ok(Some(value.clone()));
+        }
+        if let Some(value) = arg.to_str().and_then(|a| a.strip_prefix(&prefix)) {
+            return Ok(Some(value.into()));
+        }
+    }
+    Ok(None)
+}
+
+/// `P::parse()` with the config file and `--preset` applied first, to each
+/// of `commands`: a subcommand name (None for the top-level command) and the
+/// settings it takes. Subcommands missing from `P` are skipped.
+pub fn parse_with_config<P: Parser>(commands: &[(Option<&str>, Settings)]) -> Result<P> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let config = Config::find(&args)?.unwrap_or_default();
+    let preset = flag_value(&args, "--preset")?;
+    let preset = preset.as_ref().and_then(|p| p.to_str());
+
+    let mut cmd = <P as CommandFactory>::command();
+    for &(subcommand, settings) in commands {
+        cmd = match subcommand {
+            Some(name) => {
+                let Some(sub) = cmd.find_subcommand(name).cloned() else {
+                    continue;
+                };
+                let sub = config.apply(sub, settings, preset)?;
+                cmd.mut_subcommand(name, |_| sub)
+            }
+            None => config.apply(cmd, settings, preset)?,
+        };
+    }
+    let matches = cmd.get_matches();
+    Ok(<P as FromArgMatches>::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
+}
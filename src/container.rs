@@ -0,0 +1,265 @@
+// src/lib/container.rs
+// Payload containers: rectFrames.json, the "BAPL" binary (binary.rs) and
+// MessagePack, each optionally gzipped (feature "gzip"). Repacking moves the
+// rects between them as they are; no frame is re-encoded.
+//
+// MessagePack mirrors the JSON document field for field (same names, same
+// nesting), so anything that reads one can read the other.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use crate::binary::{decode_payload, encode_payload_with, BinaryOpts, MAGIC};
+use crate::decode;
+use crate::rectframes::Payload;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Container {
+    Json,
+    /// `binary.rs` layout: rects and the basic header only (no per-frame
+    /// stats, preprocess steps or other optional fields)
+    Binary,
+    Msgpack,
+}
+
+/// A container plus whether it is gzipped, e.g. `msgpack.gz`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packing {
+    pub container: Container,
+    pub gzip: bool,
+}
+
+impl Packing {
+    /// From the file extension: `.json`, `.bin` / `.bapl`, `.msgpack` /
+    /// `.mpk`, each optionally followed by `.gz`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        let (name, gzip) = match name.strip_suffix(".gz") {
+            Some(name) => (name.to_owned(), true),
+            None => (name, false),
+        };
+        let ext = name.rsplit_once('.')?.1;
+        let container = match ext {
+            "bapl" => Container::Binary,
+            "mpk" => Container::Msgpack,
+            ext => ext.parse().ok()?,
+        };
+        Some(Packing { container, gzip })
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Container::Json => "json",
+            Container::Binary => "bin",
+            Container::Msgpack => "msgpack",
+        })
+    }
+}
+
+impl FromStr for Container {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Container::Json),
+            "bin" | "binary" => Ok(Container::Binary),
+            "msgpack" => Ok(Container::Msgpack),
+            _ => Err(format!(
+                "unknown container {s:?} (expected json|bin|msgpack)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Packing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.container)?;
+        if self.gzip {
+            write!(f, ".gz")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `json`, `bin`, `msgpack`, optionally with a `.gz` suffix.
+impl FromStr for Packing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (container, gzip) = match s.strip_suffix(".gz") {
+            Some(c) => (c, true),
+            None => (s, false),
+        };
+        Ok(Packing {
+            container: container.parse()?,
+            gzip,
+        })
+    }
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Encodes the payload in `packing`; `bin` only applies to `Container::Binary`.
+pub fn pack(payload: &Payload, packing: Packing, bin: &BinaryOpts) -> Result<Vec<u8>> {
+    let bytes = match packing.container {
+        Container::Json => serde_json::to_vec(payload)?,
+        Container::Binary => encode_payload_with(payload, bin)?,
+        // Through `Value`, so maps keep the JSON field names and enums their strings
+        Container::Msgpack => rmp_serde::to_vec_named(&serde_json::to_value(payload)?)?,
+    };
+    if packing.gzip {
+        return gzip(&bytes);
+    }
+    Ok(bytes)
+}
+
+/// Decodes any container, recognised by its first bytes rather than the
+/// file name, and says which one it was.
+pub fn unpack(bytes: &[u8]) -> Result<(Payload, Packing)> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let (payload, inner) = unpack(&gunzip(bytes)?)?;
+        if inner.gzip {
+            bail!("payload is gzipped twice");
+        }
+        return Ok((
+            payload,
+            Packing {
+                gzip: true,
+                ..inner
+            },
+        ));
+    }
+
+    let (payload, container) = if bytes.starts_with(MAGIC) {
+        (decode_payload(bytes)?, Container::Binary)
+    } else if matches!(bytes.first(), Some(0x80..=0x8f | 0xde | 0xdf)) {
+        let mut input = bytes;
+        let value = Value::deserialize(&mut rmp_serde::Deserializer::new(&mut input))
+            .context("MessagePack payload")?;
+        if !input.is_empty() {
+            bail!(
+                "{} trailing bytes after the MessagePack payload",
+                input.len()
+            );
+        }
+        let payload: Payload = serde_json::from_value(value).context("MessagePack payload")?;
+        (payload.migrate()?, Container::Msgpack)
+    } else {
+        let json = std::str::from_utf8(bytes).context("payload is not UTF-8 JSON")?;
+        (Payload::from_json(json)?, Container::Json)
+    };
+    Ok((
+        payload,
+        Packing {
+            container,
+            gzip: false,
+        },
+    ))
+}
+
+pub fn read_packed(path: &Path) -> Result<(Payload, Packing)> {
+    let bytes = fs::read(path).with_context(|| format!("Failed reading {}", path.display()))?;
+    unpack(&bytes).with_context(|| format!("Failed decoding payload: {}", path.display()))
+}
+
+/// What `verify` found in a payload that decoded cleanly.
+#[derive(Clone, Copy, Debug)]
+pub struct Verified {
+    pub packing: Packing,
+    pub frames_count: usize,
+    /// Binary payload with per-frame CRC32s, so every frame was checked
+    pub crc32: bool,
+}
+
+/// Checks that a payload in any container decodes completely. Binary payloads
+/// go through `decode::verify` (truncation, trailing bytes, per-frame CRC32);
+/// the others are parsed in full.
+pub fn verify(bytes: &[u8]) -> Result<Verified> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let inner = verify(&gunzip(bytes)?)?;
+        if inner.packing.gzip {
+            bail!("payload is gzipped twice");
+        }
+        return Ok(Verified {
+            packing: Packing {
+                gzip: true,
+                ..inner.packing
+            },
+            ..inner
+        });
+    }
+
+    if bytes.starts_with(MAGIC) {
+        let header = decode::verify(bytes)?;
+        return Ok(Verified {
+            packing: Packing {
+                container: Container::Binary,
+                gzip: false,
+            },
+            frames_count: header.frames_count,
+            crc32: header.crc32,
+        });
+    }
+
+    let (payload, packing) = unpack(bytes)?;
+    if payload.rect_frames.len() != payload.frames_count {
+        bail!(
+            "frames_count is {} but the payload has {} frames",
+            payload.frames_count,
+            payload.rect_frames.len()
+        );
+    }
+    Ok(Verified {
+        packing,
+        frames_count: payload.frames_count,
+        crc32: false,
+    })
+}
+
+/// Writes the payload in `packing`; returns the number of bytes written.
+pub fn write_packed(
+    payload: &Payload,
+    out_file: &Path,
+    packing: Packing,
+    bin: &BinaryOpts,
+) -> Result<usize> {
+    let bytes = pack(payload, packing, bin)?;
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(out_file, &bytes)
+        .with_context(|| format!("Failed writing payload: {}", out_file.display()))?;
+    Ok(bytes.len())
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    gz.write_all(bytes)?;
+    Ok(gz.finish()?)
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip(_: &[u8]) -> Result<Vec<u8>> {
+    bail!("gzip containers need feature \"gzip\"")
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_: &[u8]) -> Result<Vec<u8>> {
+    bail!("gzip containers need feature \"gzip\"")
+}
@@ -0,0 +1,281 @@
+// src/lib/decode.rs
+// Payload decoding shared by the Rust tools and the WASM player (wasm.rs):
+// the "BAPL" binary container (layout in binary.rs) and frame rasterization.
+//
+// `decode` yields frames one at a time (`Frames`), so a sequential player,
+// e.g. firmware streaming from flash, never needs the whole payload as rects
+// in memory. The WASM and C players collect every frame up front instead,
+// for random access (seeking, frame-by-frame stepping).
+//
+// Only `core` and `alloc` are used here: with `default-features = false`
+// this is the only module built, so firmware can depend on the crate for
+// decoding without std.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+pub const MAGIC: &[u8; 4] = b"BAPL";
+pub const VERSION: u8 = 1;
+/// Written instead of `VERSION` when frames carry a CRC32, so a v1-only
+/// decoder refuses the payload rather than reading the CRCs as rects
+pub const VERSION_CRC32: u8 = 2;
+pub const HEADER_LEN: usize = 22;
+pub const RECT_LEN: usize = 9;
+
+pub(crate) const FLAG_INVERT: u8 = 1 << 0;
+pub(crate) const FLAG_CRC32: u8 = 1 << 1;
+/// Flag bits this decoder understands; any other bit is an error
+const KNOWN_FLAGS: u8 = FLAG_INVERT | FLAG_CRC32;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    /// 1 = black/on
+    pub v: u8,
+}
+
+/// Everything in a binary payload except the frames.
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub threshold: u32,
+    pub th_mul: f32,
+    pub invert: bool,
+    pub frames_count: usize,
+    /// Each frame is followed by its CRC32, checked while decoding
+    pub crc32: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// Header flag bits this decoder doesn't know (they may change the layout)
+    UnknownFlags(u8),
+    /// Input ended early, at this byte offset
+    Truncated(usize),
+    /// Frame at this index doesn't match its CRC32
+    Checksum(usize),
+    /// Bytes left over after the last frame, starting at this offset
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => f.write_str("Not a binary payload (bad magic)"),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(f, "Unsupported binary payload version {v}")
+            }
+            DecodeError::UnknownFlags(bits) => {
+                write!(f, "Unknown binary payload flags 0x{bits:02x}")
+            }
+            DecodeError::Truncated(pos) => write!(f, "Truncated binary payload at byte {pos}"),
+            DecodeError::Checksum(i) => write!(f, "Frame {i} is corrupted (CRC32 mismatch)"),
+            DecodeError::TrailingBytes(pos) => {
+                write!(f, "Trailing bytes after the last frame, at byte {pos}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// CRC32 (IEEE, as in zip/gzip/PNG) lookup table, built at compile time.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// CRC32 (IEEE) of `bytes`, the per-frame checksum of the binary format.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |c, &b| {
+        CRC32_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos + n;
+        if end > self.buf.len() {
+            return Err(DecodeError::Truncated(self.pos));
+        }
+        let s = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(s)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+/// Iterator over the frames of a binary payload, decoding one frame per step.
+pub struct Frames<'a> {
+    reader: Reader<'a>,
+    remaining: usize,
+    index: usize,
+    crc32: bool,
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<Vec<Rect>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let index = self.index;
+        self.index += 1;
+
+        let crc32_enabled = self.crc32;
+        let r = &mut self.reader;
+        let start = r.pos;
+        let frame = (|| {
+            let n = r.u32()? as usize;
+            let mut rects = Vec::with_capacity(n.min(r.buf.len() / RECT_LEN));
+            for _ in 0..n {
+                rects.push(Rect {
+                    x: r.u16()? as u32,
+                    y: r.u16()? as u32,
+                    w: r.u16()? as u32,
+                    h: r.u16()? as u32,
+                    v: r.u8()?,
+                });
+            }
+            if crc32_enabled {
+                let end = r.pos;
+                if r.u32()? != crc32(&r.buf[start..end]) {
+                    return Err(DecodeError::Checksum(index));
+                }
+            }
+            Ok(rects)
+        })();
+
+        if frame.is_err() {
+            self.remaining = 0;
+        }
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+/// Parses the header and returns it with a lazy iterator over the frames.
+pub fn decode(bytes: &[u8]) -> Result<(Header, Frames<'_>), DecodeError> {
+    let mut r = Reader { buf: bytes, pos: 0 };
+
+    if r.take(4)? != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = r.u8()?;
+    if version != VERSION && version != VERSION_CRC32 {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let flags = r.u8()?;
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(DecodeError::UnknownFlags(flags & !KNOWN_FLAGS));
+    }
+    let header = Header {
+        width: r.u16()? as u32,
+        height: r.u16()? as u32,
+        fps: r.u16()? as u32,
+        threshold: r.u16()? as u32,
+        th_mul: r.f32()?,
+        invert: flags & FLAG_INVERT != 0,
+        frames_count: r.u32()? as usize,
+        crc32: flags & FLAG_CRC32 != 0,
+    };
+
+    let frames = Frames {
+        remaining: header.frames_count,
+        reader: r,
+        index: 0,
+        crc32: header.crc32,
+    };
+    Ok((header, frames))
+}
+
+/// Decodes every frame without keeping the rects, to check a payload before
+/// playing it: catches truncation, trailing bytes and (for payloads written
+/// with per-frame CRC32) corrupted frames.
+pub fn verify(bytes: &[u8]) -> Result<Header, DecodeError> {
+    let (header, mut frames) = decode(bytes)?;
+    for frame in frames.by_ref() {
+        frame?;
+    }
+    let pos = frames.reader.pos;
+    if pos != bytes.len() {
+        return Err(DecodeError::TrailingBytes(pos));
+    }
+    Ok(header)
+}
+
+/// Paints rects into a 1/0 pixel buffer of `w`×`h` (cleared first).
+pub fn rasterize_rects_into(rects: &[Rect], w: usize, h: usize, frame: &mut [u8]) {
+    frame.fill(0);
+    for r in rects {
+        let x0 = (r.x as usize).min(w);
+        let x1 = (r.x as usize + r.w as usize).min(w);
+        let y0 = (r.y as usize).min(h);
+        let y1 = (r.y as usize + r.h as usize).min(h);
+        for y in y0..y1 {
+            frame[y * w + x0..y * w + x1].fill(r.v);
+        }
+    }
+}
+
+/// Paints rects back into a 1/0 pixel buffer of `w`×`h`.
+pub fn rasterize_rects(rects: &[Rect], w: usize, h: usize) -> Vec<u8> {
+    let mut frame = vec![0u8; w * h];
+    rasterize_rects_into(rects, w, h, &mut frame);
+    frame
+}
+
+/// Expands a 1/0 pixel buffer to RGBA, `on` for 1 and `off` for 0.
+pub fn frame_to_rgba(frame: &[u8], on: [u8; 4], off: [u8; 4], rgba: &mut [u8]) {
+    for (px, &v) in rgba.chunks_exact_mut(4).zip(frame) {
+        px.copy_from_slice(if v != 0 { &on } else { &off });
+    }
+}
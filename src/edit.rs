@@ -0,0 +1,153 @@
+// src/lib/edit.rs
+// Edits on an existing payload: they work on the rects (rasterizing where
+// needed), so no source frames or re-conversion are involved.
+
+use anyhow::{bail, Result};
+
+use crate::raster::downscale;
+use crate::rectframes::{merge_frame_to_rects, Payload};
+
+/// Re-encodes the payload at `width`×`height`: every frame is rasterized,
+/// box-downscaled (see `raster::downscale`) and merged again. Per-frame
+/// thresholds carry over; rect counts are recomputed.
+pub fn rescale(payload: Payload, width: u32, height: u32) -> Payload {
+    let (sw, sh) = (payload.width as usize, payload.height as usize);
+    let (dw, dh) = (width as usize, height as usize);
+    let rect_frames: Vec<_> = (0..payload.rect_frames.len())
+        .map(|i| {
+            let frame = downscale(&payload.rasterize_frame(i), sw, sh, dw, dh);
+            merge_frame_to_rects(&frame, dw, dh, payload.off_rects)
+        })
+        .collect();
+    let frame_rect_counts = payload
+        .frame_rect_counts
+        .is_some()
+        .then(|| rect_frames.iter().map(Vec::len).collect());
+
+    Payload {
+        width,
+        height,
+        rect_frames,
+        frame_rect_counts,
+        ..payload
+    }
+}
+
+/// Keeps the frames starting inside `[from, to)` seconds (`to` = None: up to
+/// the end). Everything but the frame list and its per-frame stats stays as is.
+pub fn trim(payload: Payload, from: f64, to: Option<f64>) -> Result<Payload> {
+    if let Some(to) = to
+        && to <= from
+    {
+        bail!("trim end {to}s must be after its start {from}s");
+    }
+    let n = payload.rect_frames.len();
+    let fps = payload.fps as f64;
+    // Frame i starts at i / fps; the epsilon keeps 30s @ 30fps on frame 900
+    let frame_at = |t: f64| ((t * fps - 1e-6).ceil().max(0.0) as usize).min(n);
+    let (start, end) = (frame_at(from), to.map_or(n, frame_at));
+    if start >= end {
+        bail!(
+            "no frames from {from}s{} (payload is {:.2}s long)",
+            to.map(|to| format!(" to {to}s")).unwrap_or_default(),
+            n as f64 / fps
+        );
+    }
+
+    let frame_thresholds = payload
+        .frame_thresholds
+        .map(|v| v.get(start..end).unwrap_or_default().to_vec());
+    let frame_rect_counts = payload
+        .frame_rect_counts
+        .map(|v| v.get(start..end).unwrap_or_default().to_vec());
+    let mut rect_frames = payload.rect_frames;
+    rect_frames.truncate(end);
+    rect_frames.drain(..start);
+
+    Ok(Payload {
+        frames_count: rect_frames.len(),
+        rect_frames,
+        frame_thresholds,
+        frame_rect_counts,
+        ..payload
+    })
+}
+
+/// Splices the payloads' frames one after another. They must share size,
+/// fps and `off_rects` (a renderer paints `v: 0` rects instead of clearing
+/// frames, so the two kinds can't mix); the rest of the header comes from the first one, with the average
+/// threshold weighted by frame count. Per-frame stats survive only if every
+/// payload has them.
+pub fn concat(payloads: Vec<Payload>) -> Result<Payload> {
+    let mut parts = payloads.into_iter();
+    let Some(mut out) = parts.next() else {
+        bail!("nothing to concatenate");
+    };
+    let mut th_sum = out.threshold as f64 * out.rect_frames.len() as f64;
+
+    for (i, part) in parts.enumerate() {
+        if (part.width, part.height, part.fps) != (out.width, out.height, out.fps) {
+            bail!(
+                "payload {} is {}×{} @ {}fps, the first one {}×{} @ {}fps",
+                i + 2,
+                part.width,
+                part.height,
+                part.fps,
+                out.width,
+                out.height,
+                out.fps
+            );
+        }
+        if part.off_rects != out.off_rects {
+            bail!(
+                "payload {} {} off rects, the first one {}",
+                i + 2,
+                if part.off_rects { "has" } else { "has no" },
+                if out.off_rects { "does" } else { "doesn't" }
+            );
+        }
+        th_sum += part.threshold as f64 * part.rect_frames.len() as f64;
+        out.frame_thresholds = out
+            .frame_thresholds
+            .zip(part.frame_thresholds)
+            .map(|(a, b)| [a, b].concat());
+        out.frame_rect_counts = out
+            .frame_rect_counts
+            .zip(part.frame_rect_counts)
+            .map(|(a, b)| [a, b].concat());
+        if out.interpolated != part.interpolated {
+            out.interpolated = None;
+        }
+        out.rect_frames.extend(part.rect_frames);
+    }
+
+    out.frames_count = out.rect_frames.len();
+    out.threshold = (th_sum / out.frames_count.max(1) as f64).round() as u32;
+    Ok(out)
+}
+
+/// Parses a time as seconds: `90`, `90s`, `1.5s`, `1500ms`, `1:30` or
+/// `0:01:30.5`.
+pub fn parse_time(s: &str) -> std::result::Result<f64, String> {
+    let bad = || format!("invalid time {s:?} (expected e.g. 90s, 1500ms or 1:30)");
+    let secs = if let Some(ms) = s.strip_suffix("ms") {
+        ms.trim().parse::<f64>().map_err(|_| bad())? / 1000.0
+    } else if s.contains(':') {
+        s.split(':').try_fold(0.0, |acc, part| {
+            part.trim()
+                .parse::<f64>()
+                .map(|v| acc * 60.0 + v)
+                .map_err(|_| bad())
+        })?
+    } else {
+        s.strip_suffix('s')
+            .unwrap_or(s)
+            .trim()
+            .parse()
+            .map_err(|_| bad())?
+    };
+    if !(secs.is_finite() && secs >= 0.0) {
+        return Err(bad());
+    }
+    Ok(secs)
+}
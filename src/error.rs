@@ -0,0 +1,86 @@
+// src/lib/error.rs
+// Typed errors of the rectframes and subs APIs, so callers can match on the
+// kind of failure. The rest of the crate uses anyhow (these convert with `?`).
+
+use std::{error::Error, io, path::PathBuf};
+use thiserror::Error;
+
+pub type Result<T, E = BadAppleError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BadAppleError {
+    /// Input path missing, or nothing usable in it (no PNG frames, no cues)
+    #[error("{what} not found: {}", path.display())]
+    MissingInput { what: &'static str, path: PathBuf },
+
+    /// A frame does not have the configured size
+    #[error("❌ Frame size mismatch in {frame}: got {}×{}, expected {}×{}", got.0, got.1, expected.0, expected.1)]
+    SizeMismatch {
+        frame: String,
+        got: (u32, u32),
+        expected: (u32, u32),
+    },
+
+    /// Input that could not be decoded (PNG frame, payload JSON)
+    #[error("Failed parsing {what}")]
+    ParseError {
+        what: String,
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+
+    /// Payload written by a newer version of this crate
+    #[error("Payload version {found} is newer than supported ({supported}), update bad-apple")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    /// Stopped through a `CancelToken`
+    #[error("Conversion cancelled")]
+    Cancelled,
+
+    /// Rejected by `ConvertRectframesOptsBuilder::build`
+    #[error("Invalid options: {0}")]
+    InvalidOpts(String),
+
+    /// HTTP fetch that still failed after every retry (feature "http")
+    #[error("Failed downloading {url} after {attempts} attempt(s)")]
+    Download {
+        url: String,
+        attempts: u32,
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+
+    /// wgpu device or readback failure (feature "gpu")
+    #[error("GPU binarization failed: {0}")]
+    Gpu(String),
+
+    #[error("Failed {action} {}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl BadAppleError {
+    pub(crate) fn io(action: &'static str, path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| BadAppleError::Io {
+            action,
+            path,
+            source,
+        }
+    }
+
+    pub(crate) fn parse<E: Into<Box<dyn Error + Send + Sync>>>(
+        what: impl Into<String>,
+    ) -> impl FnOnce(E) -> Self {
+        let what = what.into();
+        move |source| BadAppleError::ParseError {
+            what,
+            source: source.into(),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// src/lib/export/asciicast.rs
+// asciinema v2 recording of the terminal playback (see term.rs).
+//
+// The first line is the JSON header, every following line one
+// `[time, "o", data]` output event. Each frame homes the cursor and redraws
+// the whole grid; frames whose text did not change are skipped, so static
+// parts of the video cost nothing.
+
+use anyhow::Result;
+use serde_json::json;
+use std::{fmt::Write, path::Path};
+
+use super::write_output;
+use crate::rectframes::Payload;
+use crate::term::Renderer;
+
+#[derive(Clone, Debug)]
+pub struct AsciicastOpts<'a> {
+    /// blocks or braille (asciinema-player cannot show sixel)
+    pub renderer: Renderer,
+    /// Max terminal size; the grid keeps the frame's aspect ratio inside it
+    pub cols: usize,
+    pub rows: usize,
+    pub invert: bool,
+    pub title: &'a str,
+}
+
+pub fn payload_to_asciicast(payload: &Payload, opts: &AsciicastOpts<'_>) -> Result<String> {
+    if !opts.renderer.is_text() {
+        anyhow::bail!("asciicast needs a text renderer (blocks or braille)");
+    }
+
+    let (fw, fh) = (payload.width as usize, payload.height as usize);
+    let (cols, rows) = opts.renderer.fit_grid(fw, fh, opts.cols, opts.rows);
+    if cols == 0 || rows == 0 {
+        anyhow::bail!("Nothing to render: empty frame or terminal size");
+    }
+    let fps = payload.fps.max(1) as f64;
+
+    let header = json!({
+        "version": 2,
+        "width": cols,
+        "height": rows,
+        "title": opts.title,
+        "env": { "TERM": "xterm-256color" },
+    });
+
+    let mut out = header.to_string();
+    out.push('\n');
+    // Clear the screen and hide the cursor
+    let _ = writeln!(out, "{}", json!([0.0, "o", "\x1b[2J\x1b[?25l"]));
+
+    let mut last = String::new();
+    for i in 0..payload.rect_frames.len() {
+        let text = opts.renderer.render(
+            &payload.rasterize_frame(i),
+            fw,
+            fh,
+            cols,
+            rows,
+            opts.invert,
+        );
+        if text == last {
+            continue;
+        }
+
+        let time = (i as f64 / fps * 1e6).round() / 1e6;
+        let _ = writeln!(out, "{}", json!([time, "o", format!("\x1b[H{text}")]));
+        last = text;
+    }
+
+    // Hold the last frame until the end of the video
+    let end = payload.rect_frames.len() as f64 / fps;
+    let _ = writeln!(out, "{}", json!([end, "o", "\x1b[?25h"]));
+    Ok(out)
+}
+
+pub fn export_asciicast_file(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &AsciicastOpts<'_>,
+) -> Result<()> {
+    let cast = payload_to_asciicast(payload, opts)?;
+    write_output(out_file, cast.as_bytes())
+}
@@ -0,0 +1,83 @@
+// src/lib/export/c_header.rs
+// Writes the binary payload (see binary.rs) as a C header for embedded targets.
+//
+// Output:
+//   #define BAD_APPLE_WIDTH 256
+//   ...
+//   static const uint8_t bad_apple_frames[BAD_APPLE_FRAMES_LEN] = { 0x42, ... };
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::{check_ident, write_output};
+use crate::binary::encode_payload;
+use crate::rectframes::Payload;
+
+/// Formats bytes as a comma-separated C initializer, `per_line` values per row.
+pub(crate) fn c_byte_list(bytes: &[u8], per_line: usize, indent: &str) -> String {
+    let mut out = String::with_capacity(bytes.len() * 6);
+    for (i, chunk) in bytes.chunks(per_line).enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(indent);
+        for b in chunk {
+            let _ = write!(out, "0x{b:02x}, ");
+        }
+        out.truncate(out.trim_end().len());
+    }
+    out
+}
+
+pub fn payload_to_c_header(payload: &Payload, name: &str) -> Result<String> {
+    check_ident(name)?;
+
+    let bytes = encode_payload(payload)?;
+    let upper = name.to_uppercase();
+    let guard = format!("{upper}_FRAMES_H");
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by bad-apple — do not edit.")?;
+    writeln!(
+        out,
+        "// Array layout: \"BAPL\" v1 binary payload (little-endian)."
+    )?;
+    writeln!(
+        out,
+        "//   header: magic[4] version:u8 flags:u8 width:u16 height:u16 fps:u16"
+    )?;
+    writeln!(
+        out,
+        "//           threshold:u16 th_mul:f32 frames_count:u32"
+    )?;
+    writeln!(
+        out,
+        "//   frame:  rect_count:u32, then rect_count x {{ x:u16 y:u16 w:u16 h:u16 v:u8 }}"
+    )?;
+    writeln!(out, "#ifndef {guard}")?;
+    writeln!(out, "#define {guard}")?;
+    writeln!(out)?;
+    writeln!(out, "#include <stdint.h>")?;
+    writeln!(out)?;
+    writeln!(out, "#define {upper}_WIDTH {}", payload.width)?;
+    writeln!(out, "#define {upper}_HEIGHT {}", payload.height)?;
+    writeln!(out, "#define {upper}_FPS {}", payload.fps)?;
+    writeln!(out, "#define {upper}_FRAMES_COUNT {}", payload.frames_count)?;
+    writeln!(out, "#define {upper}_FRAMES_LEN {}", bytes.len())?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "static const uint8_t {name}_frames[{upper}_FRAMES_LEN] = {{"
+    )?;
+    writeln!(out, "{}", c_byte_list(&bytes, 16, "    "))?;
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "#endif // {guard}")?;
+
+    Ok(out)
+}
+
+pub fn export_c_header_file(payload: &Payload, out_file: &Path, name: &str) -> Result<()> {
+    let header = payload_to_c_header(payload, name)?;
+    write_output(out_file, header.as_bytes())
+}
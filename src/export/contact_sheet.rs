@@ -0,0 +1,106 @@
+// src/lib/export/contact_sheet.rs
+// One PNG tiling every Nth encoded frame, for checking a whole conversion at
+// a glance (threshold drift, flicker, lost detail) without playing it.
+//
+// Tiles are the rasterized rects (black silhouette on white), scaled to
+// `tile_width` with the payload's aspect ratio, on a gray grid.
+
+use anyhow::{bail, Context, Result};
+use image::{GrayImage, ImageFormat, Luma};
+use std::{io::Cursor, path::Path};
+
+use super::write_output;
+use crate::raster::{resample, Fit};
+use crate::rectframes::Payload;
+
+/// Grid lines between tiles.
+const GAP_LUMA: u8 = 128;
+
+#[derive(Clone, Debug)]
+pub struct ContactSheetOpts {
+    /// Keep every Nth frame; None spreads `tiles` frames over the payload
+    pub step: Option<usize>,
+    /// Frame count to aim for when `step` is None
+    pub tiles: usize,
+    pub columns: u32,
+    /// Tile width in pixels (height follows the payload's aspect ratio)
+    pub tile_width: u32,
+    /// Grid line width in pixels
+    pub gap: u32,
+}
+
+impl Default for ContactSheetOpts {
+    fn default() -> Self {
+        Self {
+            step: None,
+            tiles: 100,
+            columns: 10,
+            tile_width: 160,
+            gap: 4,
+        }
+    }
+}
+
+/// Renders the sheet; returns it and the source frame index of each tile.
+pub fn payload_to_contact_sheet(
+    payload: &Payload,
+    opts: &ContactSheetOpts,
+) -> Result<(GrayImage, Vec<usize>)> {
+    let frames = payload.rect_frames.len();
+    if frames == 0 || payload.width == 0 || payload.height == 0 {
+        bail!("Empty payload");
+    }
+    if opts.columns == 0 || opts.tile_width == 0 || opts.step == Some(0) {
+        bail!("columns, tile width and step must be >= 1");
+    }
+
+    let step = opts
+        .step
+        .unwrap_or_else(|| frames.div_ceil(opts.tiles.max(1)))
+        .max(1);
+    let indices: Vec<usize> = (0..frames).step_by(step).collect();
+
+    let (pw, ph) = (payload.width as usize, payload.height as usize);
+    let tw = opts.tile_width;
+    let th = ((tw as usize * ph / pw) as u32).max(1);
+    let columns = opts.columns.min(indices.len() as u32);
+    let rows = (indices.len() as u32).div_ceil(columns);
+    let gap = opts.gap;
+    let mut img = GrayImage::from_pixel(
+        columns * (tw + gap) + gap,
+        rows * (th + gap) + gap,
+        Luma([GAP_LUMA]),
+    );
+
+    for (k, &i) in indices.iter().enumerate() {
+        let x = gap + (k as u32 % columns) * (tw + gap);
+        let y = gap + (k as u32 / columns) * (th + gap);
+        let px = resample(
+            &payload.rasterize_frame(i),
+            pw,
+            ph,
+            tw as usize,
+            th as usize,
+            Fit::Stretch,
+        );
+        for (n, &v) in px.iter().enumerate() {
+            let (dx, dy) = ((n % tw as usize) as u32, (n / tw as usize) as u32);
+            img.put_pixel(x + dx, y + dy, Luma([if v != 0 { 0 } else { 255 }]));
+        }
+    }
+    Ok((img, indices))
+}
+
+/// Writes the sheet as a PNG; returns the source frame index of each tile.
+pub fn export_contact_sheet_file(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &ContactSheetOpts,
+) -> Result<Vec<usize>> {
+    let (img, indices) = payload_to_contact_sheet(payload, opts)?;
+    let mut png = Cursor::new(Vec::new());
+    img.write_to(&mut png, ImageFormat::Png)
+        .with_context(|| format!("Failed encoding {}", out_file.display()))?;
+    write_output(out_file, png.get_ref())?;
+    Ok(indices)
+}
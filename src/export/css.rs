@@ -0,0 +1,104 @@
+// src/lib/export/css.rs
+// Pure-CSS player: zero JavaScript.
+//
+// A black pseudo-element covers the canvas and is clipped to the frame's rects
+// with `clip-path: path(...)` (one subpath per rect). A keyframe per frame swaps
+// the path, and `steps(1, end)` holds each one until the next keyframe.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::write_output;
+use crate::rectframes::{Payload, Rect};
+
+#[derive(Clone, Debug)]
+pub struct CssOpts<'a> {
+    /// Keep every Nth frame (1 = all)
+    pub step: usize,
+    /// Integer upscale applied with `transform: scale()`
+    pub scale: u32,
+    pub fg: &'a str,
+    pub bg: &'a str,
+}
+
+fn frame_path(rects: &[Rect]) -> String {
+    let mut d = String::new();
+    for r in rects.iter().filter(|r| r.v == 1) {
+        let _ = write!(d, "M{} {}h{}v{}h-{}z", r.x, r.y, r.w, r.h, r.w);
+    }
+    if d.is_empty() {
+        // Empty path clips everything away
+        d.push_str("M0 0");
+    }
+    d
+}
+
+pub fn payload_to_css_player(payload: &Payload, opts: &CssOpts<'_>) -> Result<String> {
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+    if payload.fps == 0 {
+        anyhow::bail!("payload fps must be > 0");
+    }
+
+    let frames: Vec<usize> = (0..payload.rect_frames.len()).step_by(opts.step).collect();
+    let n = frames.len().max(1);
+    let duration = n as f64 * opts.step as f64 / payload.fps as f64;
+    let (w, h) = (payload.width, payload.height);
+    let scale = opts.scale.max(1);
+
+    let mut css = String::new();
+    writeln!(
+        css,
+        "body{{margin:0;background:#111;display:flex;align-items:center;justify-content:center;min-height:100vh}}"
+    )?;
+    writeln!(css, ".wrap{{width:{}px;height:{}px}}", w * scale, h * scale)?;
+    writeln!(
+        css,
+        ".ba{{position:relative;width:{w}px;height:{h}px;background:{};transform:scale({scale});transform-origin:0 0;overflow:hidden}}",
+        opts.bg
+    )?;
+    writeln!(
+        css,
+        ".ba::after{{content:\"\";position:absolute;inset:0;background:{};clip-path:path('M0 0');animation:ba {duration:.4}s steps(1,end) infinite}}",
+        opts.fg
+    )?;
+
+    css.push_str("@keyframes ba{\n");
+    for (k, &i) in frames.iter().enumerate() {
+        let pct = k as f64 * 100.0 / n as f64;
+        writeln!(
+            css,
+            "{pct:.6}%{{clip-path:path('{}')}}",
+            frame_path(&payload.rect_frames[i])
+        )?;
+    }
+    css.push_str("}\n");
+
+    let mut out = String::new();
+    writeln!(out, "<!doctype html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Bad Apple!! (CSS only)</title>")?;
+    writeln!(out, "<style>")?;
+    out.push_str(&css);
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(
+        out,
+        "<body><div class=\"wrap\"><div class=\"ba\"></div></div></body>"
+    )?;
+    writeln!(out, "</html>")?;
+
+    Ok(out)
+}
+
+pub fn export_css_player_file(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &CssOpts<'_>,
+) -> Result<()> {
+    let html = payload_to_css_player(payload, opts)?;
+    write_output(out_file, html.as_bytes())
+}
@@ -0,0 +1,111 @@
+// src/lib/export/desmos.rs
+// Desmos graph state: every kept frame becomes one expression of rectangle
+// inequalities, shown only while the frame slider `f` matches it.
+//
+// Rects are passed as parallel lists, which Desmos broadcasts element-wise:
+//   [x0…] ≤ x ≤ [x1…] {[y0…] ≤ y ≤ [y1…]} {f = k}
+// y is flipped so the image is upright in graph coordinates. A ticker
+// advances `f` at the payload fps. Load the JSON in the calculator with
+// `Calc.setState(<json>)` from the browser console.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::write_output;
+use crate::rectframes::Payload;
+
+#[derive(Clone, Debug)]
+pub struct DesmosOpts {
+    /// Keep every Nth frame
+    pub step: usize,
+    /// Stop after this many kept frames (Desmos slows down with huge states)
+    pub max_frames: usize,
+}
+
+fn latex_list(values: impl Iterator<Item = u32>) -> String {
+    let items = values.map(|v| v.to_string()).collect::<Vec<_>>();
+    format!("\\left[{}\\right]", items.join(","))
+}
+
+pub fn payload_to_desmos(payload: &Payload, opts: &DesmosOpts) -> Result<Value> {
+    if opts.step == 0 || opts.max_frames == 0 {
+        anyhow::bail!("step and max frames must be >= 1");
+    }
+
+    let kept: Vec<usize> = (0..payload.rect_frames.len())
+        .step_by(opts.step)
+        .take(opts.max_frames)
+        .collect();
+    if kept.is_empty() {
+        anyhow::bail!("Payload has no frames");
+    }
+
+    let h = payload.height;
+    let last = kept.len() - 1;
+    let step_ms = 1000.0 * opts.step as f64 / payload.fps.max(1) as f64;
+
+    let mut list = vec![json!({
+        "type": "expression",
+        "id": "frame",
+        "latex": "f=0",
+        "slider": {
+            "hardMin": true,
+            "hardMax": true,
+            "min": "0",
+            "max": last.to_string(),
+            "step": "1",
+        },
+    })];
+
+    for (k, &i) in kept.iter().enumerate() {
+        let rects: Vec<_> = payload.rect_frames[i].iter().filter(|r| r.v != 0).collect();
+        if rects.is_empty() {
+            continue;
+        }
+
+        let x0 = latex_list(rects.iter().map(|r| r.x));
+        let x1 = latex_list(rects.iter().map(|r| r.x + r.w));
+        let y0 = latex_list(rects.iter().map(|r| h.saturating_sub(r.y + r.h)));
+        let y1 = latex_list(rects.iter().map(|r| h.saturating_sub(r.y)));
+
+        list.push(json!({
+            "type": "expression",
+            "id": format!("f{k}"),
+            "color": "#000000",
+            "latex": format!(
+                "{x0}\\le x\\le {x1}\\left\\{{{y0}\\le y\\le {y1}\\right\\}}\\left\\{{f={k}\\right\\}}"
+            ),
+            "fillOpacity": "1",
+            "lines": false,
+        }));
+    }
+
+    Ok(json!({
+        "version": 11,
+        "graph": {
+            "viewport": {
+                "xmin": 0,
+                "xmax": payload.width,
+                "ymin": 0,
+                "ymax": h,
+            },
+            "showGrid": false,
+            "showXAxis": false,
+            "showYAxis": false,
+        },
+        "expressions": {
+            "list": list,
+            "ticker": {
+                "handlerLatex": format!("f\\to\\operatorname{{mod}}\\left(f+1,{}\\right)", kept.len()),
+                "minStepLatex": format!("{step_ms:.0}"),
+                "open": true,
+            },
+        },
+    }))
+}
+
+pub fn export_desmos_file(payload: &Payload, out_file: &Path, opts: &DesmosOpts) -> Result<()> {
+    let state = payload_to_desmos(payload, opts)?;
+    write_output(out_file, serde_json::to_string(&state)?.as_bytes())
+}
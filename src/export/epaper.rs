@@ -0,0 +1,153 @@
+// src/lib/export/epaper.rs
+// Sparse keyframes for e-paper displays.
+//
+// E-paper panels take hundreds of milliseconds per refresh, so the payload is
+// sampled down to a low fps and a frame is only emitted when enough pixels
+// changed since the last emitted one. Each emitted frame carries the
+// bounding box of the change (x and width aligned to 8 pixels, as partial
+// refresh windows usually require) and just that region packed at 1bpp, MSB
+// first, rows padded to a byte, base64 encoded. The first frame is always a
+// full refresh.
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use std::path::Path;
+
+use super::write_output;
+use crate::raster::{pack_1bpp, resample, Fit};
+use crate::rectframes::Payload;
+
+#[derive(Clone, Debug)]
+pub struct EpaperOpts {
+    pub width: u32,
+    pub height: u32,
+    pub fit: Fit,
+    /// Sampling rate of the output, in frames per second
+    pub fps: f32,
+    /// Minimum fraction of changed pixels (0..1) for a frame to be emitted
+    pub diff_threshold: f32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EpaperFrame {
+    /// Index of the source frame in the payload
+    pub source_frame: usize,
+    /// Display time in seconds
+    pub time: f64,
+    /// Changed region [x, y, w, h]
+    pub bbox: [usize; 4],
+    /// Fraction of the display that changed
+    pub diff: f32,
+    /// The bbox region, packed 1bpp, base64
+    pub data: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EpaperExport {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+    pub frames: Vec<EpaperFrame>,
+}
+
+/// Bounding box of the pixels that differ, with x/w widened to multiples of 8.
+fn diff_bbox(a: &[u8], b: &[u8], w: usize, h: usize) -> Option<[usize; 4]> {
+    let (mut x0, mut y0, mut x1, mut y1) = (w, h, 0, 0);
+    for y in 0..h {
+        for x in 0..w {
+            if a[y * w + x] != b[y * w + x] {
+                x0 = x0.min(x);
+                y0 = y0.min(y);
+                x1 = x1.max(x + 1);
+                y1 = y1.max(y + 1);
+            }
+        }
+    }
+    if x0 >= x1 {
+        return None;
+    }
+
+    let x0 = x0 / 8 * 8;
+    let x1 = x1.div_ceil(8).saturating_mul(8).min(w);
+    Some([x0, y0, x1 - x0, y1 - y0])
+}
+
+fn crop(px: &[u8], w: usize, [x, y, cw, ch]: [usize; 4]) -> Vec<u8> {
+    (y..y + ch)
+        .flat_map(|row| px[row * w + x..row * w + x + cw].iter().copied())
+        .collect()
+}
+
+pub fn payload_to_epaper(payload: &Payload, opts: &EpaperOpts) -> Result<EpaperExport> {
+    if opts.fps <= 0.0 {
+        anyhow::bail!("fps must be > 0");
+    }
+    if !(0.0..=1.0).contains(&opts.diff_threshold) {
+        anyhow::bail!("diff threshold must be between 0 and 1");
+    }
+
+    let (dw, dh) = (opts.width as usize, opts.height as usize);
+    let src_fps = payload.fps.max(1) as f64;
+    let duration = payload.rect_frames.len() as f64 / src_fps;
+    let total = (dw * dh).max(1) as f32;
+
+    let mut frames = Vec::new();
+    let mut shown: Option<Vec<u8>> = None;
+
+    for k in 0.. {
+        let time = k as f64 / opts.fps as f64;
+        if time >= duration {
+            break;
+        }
+        let source_frame = ((time * src_fps) as usize).min(payload.rect_frames.len() - 1);
+        let px = resample(
+            &payload.rasterize_frame(source_frame),
+            payload.width as usize,
+            payload.height as usize,
+            dw,
+            dh,
+            opts.fit,
+        );
+
+        let (bbox, diff) = match &shown {
+            None => ([0, 0, dw, dh], 1.0),
+            Some(prev) => {
+                let changed = prev.iter().zip(&px).filter(|(a, b)| a != b).count();
+                let diff = changed as f32 / total;
+                match diff_bbox(prev, &px, dw, dh) {
+                    Some(bbox) if diff >= opts.diff_threshold => (bbox, diff),
+                    _ => continue,
+                }
+            }
+        };
+
+        let region = crop(&px, dw, bbox);
+        frames.push(EpaperFrame {
+            source_frame,
+            time,
+            bbox,
+            diff,
+            data: STANDARD.encode(pack_1bpp(&region, bbox[2], bbox[3])),
+        });
+        shown = Some(px);
+    }
+
+    Ok(EpaperExport {
+        width: opts.width,
+        height: opts.height,
+        fps: opts.fps,
+        frames,
+    })
+}
+
+pub fn export_epaper_file(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &EpaperOpts,
+) -> Result<EpaperExport> {
+    let export = payload_to_epaper(payload, opts)?;
+    let json = serde_json::to_string(&export)?;
+    write_output(out_file, json.as_bytes())?;
+    Ok(export)
+}
@@ -0,0 +1,135 @@
+// src/lib/export/html.rs
+// Self-contained single-file HTML player: canvas renderer, the rect data and
+// optional subtitle tracks all inlined, so it plays from file:// with no server.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::Serialize;
+use std::{fs, path::Path, str::FromStr};
+
+use super::write_output;
+use crate::binary::encode_payload;
+use crate::rectframes::Payload;
+use crate::subs::{read_cues_file, Cue, SubsOpts};
+
+const TEMPLATE: &str = include_str!("player.html");
+const OBS_TEMPLATE: &str = include_str!("obs.html");
+
+/// How the payload is embedded in the page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum HtmlData {
+    /// rectFrames.json as a JS object literal
+    #[default]
+    Json,
+    /// Base64 of the binary payload (see binary.rs), roughly 3× smaller
+    Base64,
+}
+
+impl FromStr for HtmlData {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(HtmlData::Json),
+            "base64" => Ok(HtmlData::Base64),
+            _ => Err(format!("unknown data mode {s:?} (expected json|base64)")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SubtitleTrack {
+    pub label: String,
+    pub cues: Vec<Cue>,
+}
+
+impl SubtitleTrack {
+    /// Loads a track from cue `.json` or any subtitle file `subs` reads (tags
+    /// stripped); the label is the file stem.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let cues = if path.extension().is_some_and(|e| e == "json") {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("Failed reading subtitles: {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("Failed parsing cues: {}", path.display()))?
+        } else {
+            read_cues_file(path, &SubsOpts::default())?
+        };
+
+        let label = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self { label, cues })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HtmlOpts<'a> {
+    pub title: &'a str,
+    pub data: HtmlData,
+    pub tracks: &'a [SubtitleTrack],
+}
+
+/// Makes a JSON string safe to inline inside a <script> element.
+pub(crate) fn script_safe(json: String) -> String {
+    json.replace("</", "<\\/")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Replaces each placeholder of `template` in one pass, so values that
+/// contain a placeholder name (subtitle text, a title) are left as they are.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some((at, key, value)) = values
+        .iter()
+        .filter_map(|&(key, value)| rest.find(key).map(|at| (at, key, value)))
+        .min_by_key(|&(at, ..)| at)
+    {
+        out.push_str(&rest[..at]);
+        out.push_str(value);
+        rest = &rest[at + key.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn payload_to_html(payload: &Payload, opts: &HtmlOpts<'_>) -> Result<String> {
+    let (kind, data) = match opts.data {
+        HtmlData::Json => ("json", script_safe(serde_json::to_string(payload)?)),
+        HtmlData::Base64 => {
+            let b64 = base64::engine::general_purpose::STANDARD.encode(encode_payload(payload)?);
+            ("base64", format!("\"{b64}\""))
+        }
+    };
+    let tracks = script_safe(serde_json::to_string(opts.tracks)?);
+
+    Ok(fill_template(
+        TEMPLATE,
+        &[
+            ("__TITLE__", &html_escape(opts.title)),
+            ("__KIND__", kind),
+            ("__TRACKS__", &tracks),
+            ("__PAYLOAD__", &data),
+        ],
+    ))
+}
+
+pub fn export_html_file(payload: &Payload, out_file: &Path, opts: &HtmlOpts<'_>) -> Result<()> {
+    let html = payload_to_html(payload, opts)?;
+    write_output(out_file, html.as_bytes())
+}
+
+/// Controls-free, transparent player page for OBS browser sources that
+/// fetches rectFrames.json from `payload_url` (see obs.html for the query params).
+pub fn obs_page(payload_url: &str) -> String {
+    OBS_TEMPLATE.replace("__PAYLOAD_URL__", &payload_url.replace('"', "%22"))
+}
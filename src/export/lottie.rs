@@ -0,0 +1,155 @@
+// src/lib/export/lottie.rs
+// Lottie (bodymovin) JSON: one shape layer per frame, visible for exactly one
+// frame (ip = k, op = k + 1), holding a rect shape per payload rect, plus a
+// solid background layer underneath.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::write_output;
+use crate::rectframes::Payload;
+
+#[derive(Clone, Copy, Debug)]
+pub struct LottieOpts {
+    /// Keep every Nth frame (1 = all)
+    pub step: usize,
+    /// Foreground RGB (0..1)
+    pub fg: [f32; 3],
+    /// Background RGB (0..1)
+    pub bg: [f32; 3],
+}
+
+fn static_value(v: Value) -> Value {
+    json!({ "a": 0, "k": v })
+}
+
+fn layer_transform() -> Value {
+    json!({
+        "o": static_value(json!(100)),
+        "r": static_value(json!(0)),
+        "p": static_value(json!([0, 0, 0])),
+        "a": static_value(json!([0, 0, 0])),
+        "s": static_value(json!([100, 100, 100])),
+    })
+}
+
+fn group_transform() -> Value {
+    json!({
+        "ty": "tr",
+        "p": static_value(json!([0, 0])),
+        "a": static_value(json!([0, 0])),
+        "s": static_value(json!([100, 100])),
+        "r": static_value(json!(0)),
+        "o": static_value(json!(100)),
+    })
+}
+
+/// Parses `#rgb` / `#rrggbb` into Lottie's 0..1 floats.
+pub fn parse_hex_color(s: &str) -> Result<[f32; 3], String> {
+    let hex = s.trim_start_matches('#');
+    let expanded: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return Err(format!("invalid color {s:?} (expected #rgb or #rrggbb)")),
+    };
+    let mut rgb = [0f32; 3];
+    for (i, c) in rgb.iter_mut().enumerate() {
+        let v = u8::from_str_radix(&expanded[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("invalid color {s:?}"))?;
+        *c = v as f32 / 255.0;
+    }
+    Ok(rgb)
+}
+
+pub fn payload_to_lottie(payload: &Payload, opts: &LottieOpts) -> Result<Value> {
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+
+    let frames: Vec<usize> = (0..payload.rect_frames.len()).step_by(opts.step).collect();
+    let n = frames.len();
+    let fps = payload.fps as f64 / opts.step as f64;
+    let [fr, fg, fb] = opts.fg;
+
+    let mut layers: Vec<Value> = Vec::with_capacity(n + 1);
+
+    for (k, &i) in frames.iter().enumerate() {
+        let mut items: Vec<Value> = payload.rect_frames[i]
+            .iter()
+            .filter(|r| r.v == 1)
+            .map(|r| {
+                json!({
+                    "ty": "rc",
+                    "d": 1,
+                    "p": static_value(json!([
+                        r.x as f64 + r.w as f64 / 2.0,
+                        r.y as f64 + r.h as f64 / 2.0
+                    ])),
+                    "s": static_value(json!([r.w, r.h])),
+                    "r": static_value(json!(0)),
+                })
+            })
+            .collect();
+
+        items.push(json!({
+            "ty": "fl",
+            "c": static_value(json!([fr, fg, fb, 1])),
+            "o": static_value(json!(100)),
+            "r": 1,
+        }));
+        items.push(group_transform());
+
+        layers.push(json!({
+            "ddd": 0,
+            "ind": k + 1,
+            "ty": 4,
+            "nm": format!("frame {i}"),
+            "sr": 1,
+            "ks": layer_transform(),
+            "ao": 0,
+            "shapes": [{ "ty": "gr", "nm": "rects", "it": items }],
+            "ip": k,
+            "op": k + 1,
+            "st": 0,
+            "bm": 0,
+        }));
+    }
+
+    let [br, bg, bb] = opts.bg;
+    let to_byte = |c: f32| (c * 255.0).round() as u8;
+    layers.push(json!({
+        "ddd": 0,
+        "ind": n + 1,
+        "ty": 1,
+        "nm": "background",
+        "sr": 1,
+        "ks": layer_transform(),
+        "ao": 0,
+        "sc": format!("#{:02x}{:02x}{:02x}", to_byte(br), to_byte(bg), to_byte(bb)),
+        "sw": payload.width,
+        "sh": payload.height,
+        "ip": 0,
+        "op": n,
+        "st": 0,
+        "bm": 0,
+    }));
+
+    Ok(json!({
+        "v": "5.7.4",
+        "fr": fps,
+        "ip": 0,
+        "op": n,
+        "w": payload.width,
+        "h": payload.height,
+        "nm": "Bad Apple!!",
+        "ddd": 0,
+        "assets": [],
+        "layers": layers,
+    }))
+}
+
+pub fn export_lottie_file(payload: &Payload, out_file: &Path, opts: &LottieOpts) -> Result<()> {
+    let lottie = payload_to_lottie(payload, opts)?;
+    write_output(out_file, serde_json::to_string(&lottie)?.as_bytes())
+}
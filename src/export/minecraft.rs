@@ -0,0 +1,216 @@
+// src/lib/export/minecraft.rs
+// Minecraft datapack that plays the payload on a wall of concrete blocks.
+//
+// The wall stands in the X/Y plane at `origin` (its bottom-left block), with
+// image row 0 at the top. Every frame is one function: it wipes the previous
+// silhouette (`fill … replace <on_block>`) and fills each rect, scaled down by
+// `downscale`. Frames are chained with `/schedule`, each one scheduling the
+// next at its tick (20 ticks per second); frames landing on the same tick as
+// a later one are dropped. /fill is capped at 32768 blocks, so larger
+// volumes are split into row bands.
+//
+// `/function <ns>:play` starts playback, `/function <ns>:stop` stops it.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::{check_ident, write_output};
+use crate::rectframes::Payload;
+
+pub const TICKS_PER_SECOND: f64 = 20.0;
+const FILL_LIMIT: usize = 32768;
+
+#[derive(Clone, Debug)]
+pub struct MinecraftOpts<'a> {
+    pub namespace: &'a str,
+    /// Bottom-left block of the wall
+    pub origin: (i32, i32, i32),
+    /// Source pixels per block (1 = one block per pixel)
+    pub downscale: u32,
+    pub on_block: &'a str,
+    pub off_block: &'a str,
+    /// pack.mcmeta format; 48 is Minecraft 1.21
+    pub pack_format: u32,
+}
+
+impl MinecraftOpts<'_> {
+    fn wall_size(&self, payload: &Payload) -> (usize, usize) {
+        let n = self.downscale.max(1);
+        (
+            payload.width.div_ceil(n) as usize,
+            payload.height.div_ceil(n) as usize,
+        )
+    }
+}
+
+/// Parses a block position like `0,100,0`.
+pub fn parse_origin(s: &str) -> Result<(i32, i32, i32), String> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid origin {s:?} (expected x,y,z)"))?;
+    match parts[..] {
+        [x, y, z] => Ok((x, y, z)),
+        _ => Err(format!("invalid origin {s:?} (expected x,y,z)")),
+    }
+}
+
+/// Appends `fill` commands covering columns x0..x1 and rows y0..y1 (image coordinates).
+fn push_fill(
+    out: &mut String,
+    opts: &MinecraftOpts<'_>,
+    wall_h: usize,
+    (x0, x1): (usize, usize),
+    (y0, y1): (usize, usize),
+    block: &str,
+    replace: Option<&str>,
+) {
+    let (ox, oy, oz) = opts.origin;
+    let w = x1 - x0;
+    let band = (FILL_LIMIT / w.max(1)).max(1);
+
+    let mut y = y0;
+    while y < y1 {
+        let end = (y + band).min(y1);
+        // Image rows grow downwards, world Y grows upwards
+        let top = oy + (wall_h - 1 - y) as i32;
+        let bottom = oy + (wall_h - end) as i32;
+        let _ = write!(
+            out,
+            "fill {} {} {oz} {} {} {oz} {block}",
+            ox + x0 as i32,
+            bottom,
+            ox + x1 as i32 - 1,
+            top,
+        );
+        if let Some(filter) = replace {
+            let _ = write!(out, " replace {filter}");
+        }
+        out.push('\n');
+        y = end;
+    }
+}
+
+/// Tick at which frame `i` is shown.
+fn frame_tick(i: usize, fps: u32) -> u64 {
+    (i as f64 * TICKS_PER_SECOND / fps.max(1) as f64).round() as u64
+}
+
+/// Renders the datapack as (relative path, contents) pairs.
+pub fn payload_to_datapack(
+    payload: &Payload,
+    opts: &MinecraftOpts<'_>,
+) -> Result<Vec<(String, String)>> {
+    check_ident(opts.namespace)?;
+    if opts.namespace.chars().any(|c| c.is_ascii_uppercase()) {
+        anyhow::bail!("Namespace must be lowercase: {:?}", opts.namespace);
+    }
+
+    let ns = opts.namespace;
+    let n = opts.downscale.max(1) as f64;
+    let (wall_w, wall_h) = opts.wall_size(payload);
+    // 1.21 renamed the "functions" folder to "function"
+    let fn_dir = if opts.pack_format >= 45 {
+        format!("data/{ns}/function")
+    } else {
+        format!("data/{ns}/functions")
+    };
+
+    // Keep the last frame of each tick
+    let mut shown: Vec<(usize, u64)> = Vec::new();
+    for i in 0..payload.rect_frames.len() {
+        let tick = frame_tick(i, payload.fps);
+        match shown.last_mut() {
+            Some(last) if last.1 == tick => last.0 = i,
+            _ => shown.push((i, tick)),
+        }
+    }
+
+    let mut files = Vec::new();
+    files.push((
+        "pack.mcmeta".to_string(),
+        format!(
+            "{{\"pack\":{{\"pack_format\":{},\"description\":\"Bad Apple!! ({}×{} wall, {}fps)\"}}}}\n",
+            opts.pack_format, wall_w, wall_h, payload.fps
+        ),
+    ));
+
+    let mut play = String::new();
+    let _ = writeln!(play, "scoreboard objectives add {ns} dummy");
+    let _ = writeln!(play, "scoreboard players set #stop {ns} 0");
+    push_fill(
+        &mut play,
+        opts,
+        wall_h,
+        (0, wall_w),
+        (0, wall_h),
+        opts.off_block,
+        None,
+    );
+    if !shown.is_empty() {
+        let _ = writeln!(play, "function {ns}:frame/0");
+    }
+    files.push((format!("{fn_dir}/play.mcfunction"), play));
+
+    let mut stop = String::new();
+    let _ = writeln!(stop, "scoreboard players set #stop {ns} 1");
+    files.push((format!("{fn_dir}/stop.mcfunction"), stop));
+
+    for (k, &(i, tick)) in shown.iter().enumerate() {
+        let mut f = String::new();
+        let _ = writeln!(f, "execute if score #stop {ns} matches 1 run return 0");
+        push_fill(
+            &mut f,
+            opts,
+            wall_h,
+            (0, wall_w),
+            (0, wall_h),
+            opts.off_block,
+            Some(opts.on_block),
+        );
+
+        for r in payload.rect_frames[i].iter().filter(|r| r.v != 0) {
+            let x0 = ((r.x as f64 / n).round() as usize).min(wall_w);
+            let x1 = (((r.x + r.w) as f64 / n).round() as usize).min(wall_w);
+            let y0 = ((r.y as f64 / n).round() as usize).min(wall_h);
+            let y1 = (((r.y + r.h) as f64 / n).round() as usize).min(wall_h);
+            if x0 < x1 && y0 < y1 {
+                push_fill(
+                    &mut f,
+                    opts,
+                    wall_h,
+                    (x0, x1),
+                    (y0, y1),
+                    opts.on_block,
+                    None,
+                );
+            }
+        }
+
+        if let Some(&(_, next_tick)) = shown.get(k + 1) {
+            let _ = writeln!(
+                f,
+                "schedule function {ns}:frame/{} {}t",
+                k + 1,
+                next_tick - tick
+            );
+        }
+        files.push((format!("{fn_dir}/frame/{k}.mcfunction"), f));
+    }
+
+    Ok(files)
+}
+
+/// Writes the datapack into `out_dir` (drop it into `<world>/datapacks/`).
+pub fn export_datapack_dir(
+    payload: &Payload,
+    out_dir: &Path,
+    opts: &MinecraftOpts<'_>,
+) -> Result<usize> {
+    let files = payload_to_datapack(payload, opts)?;
+    for (rel, contents) in &files {
+        write_output(&out_dir.join(rel), contents.as_bytes())?;
+    }
+    Ok(files.len())
+}
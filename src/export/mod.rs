@@ -0,0 +1,48 @@
+// src/lib/export/mod.rs
+// Exporters that turn an encoded payload into files for other targets.
+
+pub mod asciicast;
+pub mod c_header;
+pub mod contact_sheet;
+pub mod css;
+pub mod desmos;
+pub mod epaper;
+pub mod html;
+pub mod lottie;
+pub mod minecraft;
+pub mod pdf;
+pub mod progmem;
+pub mod rust_source;
+pub mod spritesheet;
+pub mod svg;
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+/// Rejects symbol names that are not valid C/Rust identifiers.
+pub(crate) fn check_ident(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let ok = chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !ok {
+        anyhow::bail!("Invalid symbol name: {name:?}");
+    }
+    Ok(())
+}
+
+/// Writes an exporter's output, creating parent directories as needed.
+pub(crate) fn write_output(out_file: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating dir: {}", parent.display()))?;
+    }
+
+    fs::write(out_file, contents)
+        .with_context(|| format!("Failed writing {}", out_file.display()))?;
+
+    Ok(())
+}
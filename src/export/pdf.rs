@@ -0,0 +1,147 @@
+// src/lib/export/pdf.rs
+// Printable flipbook: one PDF page per kept frame, drawn as vector rects.
+//
+// The PDF is written by hand (PDF 1.4, uncompressed content streams, the
+// built-in Helvetica for page numbers), so no PDF crate is needed. Each page
+// leaves a `binding` strip on the left for staples/glue and fits the frame in
+// the remaining area; the payload's y axis is flipped once with a `cm`
+// transform so rects keep their top-left coordinates.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::write_output;
+use crate::rectframes::Payload;
+
+const PT_PER_MM: f32 = 72.0 / 25.4;
+
+#[derive(Clone, Debug)]
+pub struct PdfOpts {
+    /// Keep every Nth frame
+    pub step: usize,
+    /// Page size in millimetres (width, height)
+    pub page_mm: (f32, f32),
+    /// Margin around the frame, in millimetres
+    pub margin_mm: f32,
+    /// Extra blank strip on the left edge for binding, in millimetres
+    pub binding_mm: f32,
+    /// Print the frame number in the bottom-left corner
+    pub numbers: bool,
+}
+
+/// Common flipbook page sizes in millimetres (landscape).
+pub fn page_preset(name: &str) -> Option<(f32, f32)> {
+    match name.to_ascii_lowercase().as_str() {
+        "a5" => Some((210.0, 148.0)),
+        "a6" => Some((148.0, 105.0)),
+        "a7" => Some((105.0, 74.0)),
+        "index-card" => Some((127.0, 76.2)),
+        _ => None,
+    }
+}
+
+/// Content stream for one frame.
+fn page_content(payload: &Payload, i: usize, opts: &PdfOpts) -> String {
+    let (pw, ph) = (opts.page_mm.0 * PT_PER_MM, opts.page_mm.1 * PT_PER_MM);
+    let margin = opts.margin_mm * PT_PER_MM;
+    let left = margin + opts.binding_mm * PT_PER_MM;
+
+    // Fit the frame into the printable box, anchored to the right (the flipping edge)
+    let (bw, bh) = ((pw - left - margin).max(1.0), (ph - 2.0 * margin).max(1.0));
+    let (fw, fh) = (payload.width.max(1) as f32, payload.height.max(1) as f32);
+    let s = (bw / fw).min(bh / fh);
+    let x0 = pw - margin - fw * s;
+    let y0 = (ph - fh * s) / 2.0;
+
+    let mut c = String::new();
+    // Frame outline
+    let _ = writeln!(
+        c,
+        "0.5 w 0.6 G {x0:.2} {y0:.2} {:.2} {:.2} re S",
+        fw * s,
+        fh * s
+    );
+    // Payload space: origin at the frame's top-left, y pointing down
+    let _ = writeln!(c, "q {s:.4} 0 0 {:.4} {x0:.2} {:.2} cm 0 g", -s, y0 + fh * s);
+    let mut any = false;
+    for r in payload.rect_frames[i].iter().filter(|r| r.v != 0) {
+        let _ = writeln!(c, "{} {} {} {} re", r.x, r.y, r.w, r.h);
+        any = true;
+    }
+    // `f` needs a path; a blank frame just closes the state
+    c.push_str(if any { "f Q\n" } else { "Q\n" });
+
+    if opts.numbers {
+        let _ = writeln!(
+            c,
+            "BT /F1 7 Tf 0.4 g {:.2} {:.2} Td ({}) Tj ET",
+            margin,
+            margin,
+            i + 1
+        );
+    }
+    c
+}
+
+pub fn payload_to_pdf(payload: &Payload, opts: &PdfOpts) -> Result<Vec<u8>> {
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+    let pages: Vec<usize> = (0..payload.rect_frames.len()).step_by(opts.step).collect();
+    if pages.is_empty() {
+        anyhow::bail!("Payload has no frames");
+    }
+
+    let (pw, ph) = (opts.page_mm.0 * PT_PER_MM, opts.page_mm.1 * PT_PER_MM);
+
+    // Objects: 1 catalog, 2 page tree, 3 font, then (page, content) per frame
+    let mut objects: Vec<String> = Vec::with_capacity(3 + pages.len() * 2);
+    let kids = (0..pages.len())
+        .map(|n| format!("{} 0 R", 4 + n * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{kids}] /Count {} >>",
+        pages.len()
+    ));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string());
+
+    for (n, &i) in pages.iter().enumerate() {
+        let content_id = 5 + n * 2;
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {pw:.2} {ph:.2}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {content_id} 0 R >>"
+        ));
+        let content = page_content(payload, i, opts);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (n, obj) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{obj}\nendobj\n", n + 1);
+    }
+
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for off in offsets {
+        let _ = writeln!(out, "{off:010} 00000 n ");
+    }
+    let _ = write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+
+    Ok(out.into_bytes())
+}
+
+pub fn export_pdf_file(payload: &Payload, out_file: &Path, opts: &PdfOpts) -> Result<()> {
+    let pdf = payload_to_pdf(payload, opts)?;
+    write_output(out_file, &pdf)
+}
@@ -0,0 +1,152 @@
+// src/lib/export/progmem.rs
+// Bit-packed per-frame bitmaps for Arduino/ESP32 displays.
+//
+// Each frame is rasterized, resampled to the display size and packed at 1bpp
+// (MSB first, rows padded to a byte — the layout Adafruit_GFX::drawBitmap
+// expects). Frames are grouped into PROGMEM arrays no larger than
+// `chunk_bytes`, since AVR toolchains choke on single objects over 32 KiB.
+//
+// Frame i lives in chunk i / FRAMES_PER_CHUNK at byte offset
+// (i % FRAMES_PER_CHUNK) * FRAME_BYTES.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::{c_header::c_byte_list, check_ident, write_output};
+use crate::raster::{pack_1bpp, resample, row_bytes, Fit};
+use crate::rectframes::Payload;
+
+/// Common monochrome OLED/TFT resolutions, by controller name.
+pub const DISPLAY_PRESETS: &[(&str, u32, u32)] = &[
+    ("ssd1306", 128, 64),
+    ("ssd1306-32", 128, 32),
+    ("sh1106", 128, 64),
+    ("ssd1309", 128, 64),
+    ("st7735", 160, 128),
+    ("st7789", 240, 240),
+    ("ili9341", 320, 240),
+];
+
+pub fn display_preset(name: &str) -> Option<(u32, u32)> {
+    DISPLAY_PRESETS
+        .iter()
+        .find(|(n, _, _)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, w, h)| (w, h))
+}
+
+#[derive(Clone, Debug)]
+pub struct ProgmemOpts<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub fit: Fit,
+    /// Keep every Nth frame (1 = all)
+    pub step: usize,
+    pub chunk_bytes: usize,
+    pub name: &'a str,
+}
+
+pub fn payload_to_progmem(payload: &Payload, opts: &ProgmemOpts<'_>) -> Result<String> {
+    check_ident(opts.name)?;
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+
+    let (dw, dh) = (opts.width as usize, opts.height as usize);
+    let frame_bytes = row_bytes(dw) * dh;
+    if frame_bytes > opts.chunk_bytes {
+        anyhow::bail!(
+            "One {}×{} frame ({} bytes) exceeds chunk size {}",
+            dw,
+            dh,
+            frame_bytes,
+            opts.chunk_bytes
+        );
+    }
+    let per_chunk = opts.chunk_bytes / frame_bytes;
+
+    let frames: Vec<Vec<u8>> = (0..payload.rect_frames.len())
+        .step_by(opts.step)
+        .map(|i| {
+            let src = payload.rasterize_frame(i);
+            let scaled = resample(
+                &src,
+                payload.width as usize,
+                payload.height as usize,
+                dw,
+                dh,
+                opts.fit,
+            );
+            pack_1bpp(&scaled, dw, dh)
+        })
+        .collect();
+
+    let chunks: Vec<&[Vec<u8>]> = frames.chunks(per_chunk.max(1)).collect();
+    let name = opts.name;
+    let upper = name.to_uppercase();
+    let guard = format!("{upper}_PROGMEM_H");
+    let fps = payload.fps as f64 / opts.step as f64;
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by bad-apple — do not edit.")?;
+    writeln!(
+        out,
+        "// 1bpp bitmaps, MSB first, rows padded to a byte (drawBitmap layout)."
+    )?;
+    writeln!(
+        out,
+        "// Frame i: {name}_chunks[i / {upper}_FRAMES_PER_CHUNK] + (i % {upper}_FRAMES_PER_CHUNK) * {upper}_FRAME_BYTES"
+    )?;
+    writeln!(out, "#ifndef {guard}")?;
+    writeln!(out, "#define {guard}")?;
+    writeln!(out)?;
+    writeln!(out, "#include <stdint.h>")?;
+    writeln!(out, "#if defined(ARDUINO)")?;
+    writeln!(out, "#include <Arduino.h>")?;
+    writeln!(out, "#else")?;
+    writeln!(out, "#define PROGMEM")?;
+    writeln!(out, "#endif")?;
+    writeln!(out)?;
+    writeln!(out, "#define {upper}_WIDTH {dw}")?;
+    writeln!(out, "#define {upper}_HEIGHT {dh}")?;
+    writeln!(out, "#define {upper}_FPS {fps}")?;
+    writeln!(out, "#define {upper}_ROW_BYTES {}", row_bytes(dw))?;
+    writeln!(out, "#define {upper}_FRAME_BYTES {frame_bytes}")?;
+    writeln!(out, "#define {upper}_FRAMES_COUNT {}", frames.len())?;
+    writeln!(out, "#define {upper}_FRAMES_PER_CHUNK {per_chunk}")?;
+    writeln!(out, "#define {upper}_CHUNKS {}", chunks.len())?;
+
+    for (ci, chunk) in chunks.iter().enumerate() {
+        let bytes: Vec<u8> = chunk.concat();
+        writeln!(out)?;
+        writeln!(
+            out,
+            "static const uint8_t {name}_chunk_{ci}[{}] PROGMEM = {{",
+            bytes.len()
+        )?;
+        writeln!(out, "{}", c_byte_list(&bytes, 16, "    "))?;
+        writeln!(out, "}};")?;
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "static const uint8_t *const {name}_chunks[{upper}_CHUNKS] PROGMEM = {{"
+    )?;
+    for ci in 0..chunks.len() {
+        writeln!(out, "    {name}_chunk_{ci},")?;
+    }
+    writeln!(out, "}};")?;
+    writeln!(out)?;
+    writeln!(out, "#endif // {guard}")?;
+
+    Ok(out)
+}
+
+pub fn export_progmem_file(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &ProgmemOpts<'_>,
+) -> Result<()> {
+    let header = payload_to_progmem(payload, opts)?;
+    write_output(out_file, header.as_bytes())
+}
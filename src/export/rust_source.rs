@@ -0,0 +1,160 @@
+// src/lib/export/rust_source.rs
+// Writes a `.rs` file embedding the binary payload (see binary.rs) plus a tiny
+// zero-dependency decoder, meant to be pulled in with:
+//
+//   #[allow(dead_code)]
+//   mod bad_apple { include!("bad_apple_frames.rs"); }
+//
+// With `bytes_file` the payload goes to a sibling `.bin` referenced through
+// `include_bytes!`, which compiles much faster than a giant array literal.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::{c_header::c_byte_list, write_output};
+use crate::binary::{encode_payload, HEADER_LEN, RECT_LEN};
+use crate::rectframes::Payload;
+
+const DECODER: &str = r#"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub w: u16,
+    pub h: u16,
+    pub v: u8,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    start: usize,
+    len: usize,
+}
+
+impl Frame {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn rects(&self) -> impl Iterator<Item = Rect> + '_ {
+        (0..self.len).map(move |i| {
+            let at = self.start + i * RECT_LEN;
+            Rect {
+                x: read_u16(at),
+                y: read_u16(at + 2),
+                w: read_u16(at + 4),
+                h: read_u16(at + 6),
+                v: FRAMES[at + 8],
+            }
+        })
+    }
+}
+
+pub struct Frames {
+    pos: usize,
+    left: usize,
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+
+        let len = read_u32(self.pos) as usize;
+        let start = self.pos + 4;
+        self.pos = start + len * RECT_LEN;
+        Some(Frame { start, len })
+    }
+}
+
+/// Iterates all frames in playback order.
+pub fn frames() -> Frames {
+    Frames {
+        pos: HEADER_LEN,
+        left: FRAMES_COUNT,
+    }
+}
+
+fn read_u16(at: usize) -> u16 {
+    u16::from_le_bytes([FRAMES[at], FRAMES[at + 1]])
+}
+
+fn read_u32(at: usize) -> u32 {
+    u32::from_le_bytes([FRAMES[at], FRAMES[at + 1], FRAMES[at + 2], FRAMES[at + 3]])
+}
+"#;
+
+/// Builds the `.rs` source. `bytes_file` names a sibling file for `include_bytes!`;
+/// when `None` the bytes are inlined as an array literal.
+pub fn payload_to_rust_source(payload: &Payload, bytes_file: Option<&str>) -> Result<String> {
+    let bytes = encode_payload(payload)?;
+    render_source(payload, &bytes, bytes_file)
+}
+
+fn render_source(payload: &Payload, bytes: &[u8], bytes_file: Option<&str>) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "// Generated by bad-apple — do not edit.")?;
+    writeln!(out, "// Embeds a \"BAPL\" v1 binary payload. Include with:")?;
+    writeln!(
+        out,
+        "//   #[allow(dead_code)] mod bad_apple {{ include!(\"<this file>\"); }}"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "pub const WIDTH: u32 = {};", payload.width)?;
+    writeln!(out, "pub const HEIGHT: u32 = {};", payload.height)?;
+    writeln!(out, "pub const FPS: u32 = {};", payload.fps)?;
+    writeln!(
+        out,
+        "pub const FRAMES_COUNT: usize = {};",
+        payload.frames_count
+    )?;
+    writeln!(out, "const HEADER_LEN: usize = {HEADER_LEN};")?;
+    writeln!(out, "const RECT_LEN: usize = {RECT_LEN};")?;
+    writeln!(out)?;
+
+    match bytes_file {
+        Some(file) => {
+            writeln!(
+                out,
+                "pub static FRAMES: [u8; {}] = *include_bytes!({file:?});",
+                bytes.len()
+            )?;
+        }
+        None => {
+            writeln!(out, "pub static FRAMES: [u8; {}] = [", bytes.len())?;
+            writeln!(out, "{}", c_byte_list(bytes, 16, "    "))?;
+            writeln!(out, "];")?;
+        }
+    }
+
+    out.push_str(DECODER);
+
+    Ok(out)
+}
+
+pub fn export_rust_source_file(payload: &Payload, out_file: &Path, bytes_file: bool) -> Result<()> {
+    let bytes = encode_payload(payload)?;
+
+    if bytes_file {
+        let bin_file = out_file.with_extension("bin");
+        let bin_name = bin_file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let src = render_source(payload, &bytes, Some(&bin_name))?;
+        write_output(&bin_file, &bytes)?;
+        write_output(out_file, src.as_bytes())
+    } else {
+        let src = render_source(payload, &bytes, None)?;
+        write_output(out_file, src.as_bytes())
+    }
+}
@@ -0,0 +1,164 @@
+// src/lib/export/spritesheet.rs
+// Spritesheet PNGs plus a JSON atlas for texture-based playback in game engines.
+//
+// Frames are rasterized (optionally resampled to `frame_size`) and laid out
+// left-to-right, top-to-bottom in a `columns`-wide grid. When a sheet would
+// exceed `max_sheet` another one is started. Sheets are 8-bit grayscale:
+// black silhouette on white.
+
+use anyhow::{Context, Result};
+use image::{GrayImage, ImageFormat, Luma};
+use serde::Serialize;
+use std::{io::Cursor, path::Path};
+
+use super::write_output;
+use crate::raster::{resample, Fit};
+use crate::rectframes::Payload;
+
+#[derive(Clone, Debug)]
+pub struct SpritesheetOpts {
+    /// Frame size on the sheet; defaults to the payload size
+    pub frame_size: Option<(u32, u32)>,
+    pub fit: Fit,
+    /// Frames per row; 0 = as many as fit in `max_sheet`
+    pub columns: u32,
+    /// Largest sheet (width, height) in pixels
+    pub max_sheet: (u32, u32),
+    /// Keep every Nth frame
+    pub step: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AtlasFrame {
+    pub sheet: usize,
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    /// Index of the source frame in the payload
+    pub source_frame: usize,
+    pub duration_ms: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Atlas {
+    /// Sheet file names, relative to the atlas
+    pub sheets: Vec<String>,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub fps: f64,
+    pub frames: Vec<AtlasFrame>,
+}
+
+/// Lays out the sheets; returns the atlas and one grayscale image per sheet.
+pub fn payload_to_spritesheets(
+    payload: &Payload,
+    opts: &SpritesheetOpts,
+    sheet_name: &str,
+) -> Result<(Atlas, Vec<GrayImage>)> {
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+
+    let (fw, fh) = opts.frame_size.unwrap_or((payload.width, payload.height));
+    let (max_w, max_h) = opts.max_sheet;
+    if fw == 0 || fh == 0 || fw > max_w || fh > max_h {
+        anyhow::bail!(
+            "Frame size {}×{} does not fit a {}×{} sheet",
+            fw,
+            fh,
+            max_w,
+            max_h
+        );
+    }
+
+    let fit_cols = max_w / fw;
+    let columns = if opts.columns == 0 {
+        fit_cols
+    } else {
+        opts.columns.min(fit_cols)
+    };
+    let rows = max_h / fh;
+    let per_sheet = (columns * rows) as usize;
+
+    let indices: Vec<usize> = (0..payload.rect_frames.len()).step_by(opts.step).collect();
+    let sheet_count = indices.len().div_ceil(per_sheet);
+    let fps = payload.fps.max(1) as f64 / opts.step as f64;
+
+    let mut sheets = Vec::with_capacity(sheet_count);
+    let mut frames = Vec::with_capacity(indices.len());
+
+    for (s, chunk) in indices.chunks(per_sheet).enumerate() {
+        let used_rows = (chunk.len() as u32).div_ceil(columns);
+        let sheet_w = columns.min(chunk.len() as u32) * fw;
+        let mut img = GrayImage::from_pixel(sheet_w, used_rows * fh, Luma([255]));
+
+        for (k, &i) in chunk.iter().enumerate() {
+            let (x, y) = ((k as u32 % columns) * fw, (k as u32 / columns) * fh);
+            let px = resample(
+                &payload.rasterize_frame(i),
+                payload.width as usize,
+                payload.height as usize,
+                fw as usize,
+                fh as usize,
+                opts.fit,
+            );
+            for (n, &v) in px.iter().enumerate() {
+                if v != 0 {
+                    let (dx, dy) = ((n % fw as usize) as u32, (n / fw as usize) as u32);
+                    img.put_pixel(x + dx, y + dy, Luma([0]));
+                }
+            }
+
+            frames.push(AtlasFrame {
+                sheet: s,
+                x,
+                y,
+                w: fw,
+                h: fh,
+                source_frame: i,
+                duration_ms: 1000.0 / fps,
+            });
+        }
+        sheets.push(img);
+    }
+
+    let atlas = Atlas {
+        sheets: (0..sheet_count)
+            .map(|s| format!("{sheet_name}_{s}.png"))
+            .collect(),
+        frame_width: fw,
+        frame_height: fh,
+        columns,
+        rows,
+        fps,
+        frames,
+    };
+    Ok((atlas, sheets))
+}
+
+/// Writes `<stem>.json` (the atlas at `out_file`) and `<stem>_<n>.png` next to it.
+pub fn export_spritesheet_files(
+    payload: &Payload,
+    out_file: &Path,
+    opts: &SpritesheetOpts,
+) -> Result<Atlas> {
+    let stem = out_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "bad_apple_sprites".to_string());
+    let (atlas, sheets) = payload_to_spritesheets(payload, opts, &stem)?;
+
+    for (name, img) in atlas.sheets.iter().zip(&sheets) {
+        let mut png = Cursor::new(Vec::new());
+        img.write_to(&mut png, ImageFormat::Png)
+            .with_context(|| format!("Failed encoding {name}"))?;
+        write_output(&out_file.with_file_name(name), png.get_ref())?;
+    }
+
+    let json = serde_json::to_string_pretty(&atlas)?;
+    write_output(out_file, json.as_bytes())?;
+    Ok(atlas)
+}
@@ -0,0 +1,84 @@
+// src/lib/export/svg.rs
+// Animated SVG: one <g> of <rect>s per frame, switched on/off with CSS.
+//
+// Every group is hidden by default and runs the same keyframes
+// (visible for 1/N of the loop, `step-end` so there is no fading), offset by
+// `animation-delay: i / fps`. Works anywhere SVG is allowed but scripts are not.
+
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use super::write_output;
+use crate::rectframes::Payload;
+
+#[derive(Clone, Debug)]
+pub struct SvgOpts<'a> {
+    /// Keep every Nth frame (1 = all)
+    pub step: usize,
+    pub fg: &'a str,
+    pub bg: &'a str,
+}
+
+pub fn payload_to_svg(payload: &Payload, opts: &SvgOpts<'_>) -> Result<String> {
+    if opts.step == 0 {
+        anyhow::bail!("step must be >= 1");
+    }
+    if payload.fps == 0 {
+        anyhow::bail!("payload fps must be > 0");
+    }
+
+    let frames: Vec<usize> = (0..payload.rect_frames.len()).step_by(opts.step).collect();
+    let n = frames.len().max(1);
+    let frame_secs = opts.step as f64 / payload.fps as f64;
+    let duration = n as f64 * frame_secs;
+    let visible_pct = 100.0 / n as f64;
+
+    let (w, h) = (payload.width, payload.height);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" shape-rendering="crispEdges">"#
+    )?;
+    writeln!(out, "<style>")?;
+    writeln!(
+        out,
+        "g{{fill:{};visibility:hidden;animation:ba {duration:.4}s step-end infinite}}",
+        opts.fg
+    )?;
+    writeln!(
+        out,
+        "@keyframes ba{{0%{{visibility:visible}}{visible_pct:.6}%{{visibility:hidden}}}}"
+    )?;
+    writeln!(out, "</style>")?;
+    writeln!(
+        out,
+        r#"<rect width="{w}" height="{h}" fill="{}"/>"#,
+        opts.bg
+    )?;
+
+    for (k, &i) in frames.iter().enumerate() {
+        write!(
+            out,
+            r#"<g style="animation-delay:{:.4}s">"#,
+            k as f64 * frame_secs
+        )?;
+        for r in payload.rect_frames[i].iter().filter(|r| r.v == 1) {
+            write!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+                r.x, r.y, r.w, r.h
+            )?;
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    writeln!(out, "</svg>")?;
+
+    Ok(out)
+}
+
+pub fn export_svg_file(payload: &Payload, out_file: &Path, opts: &SvgOpts<'_>) -> Result<()> {
+    let svg = payload_to_svg(payload, opts)?;
+    write_output(out_file, svg.as_bytes())
+}
@@ -0,0 +1,163 @@
+// src/lib/ffi.rs
+// C API for the decoder (feature "ffi"), so C/C++ firmware and game engines
+// can link the crate instead of reimplementing the binary format. The header
+// is include/bad_apple.h, generated by cbindgen with cbindgen.toml.
+//
+//   BadApplePayload *p = bad_apple_open(data, len);
+//   uint8_t *pixels = malloc(bad_apple_width(p) * bad_apple_height(p));
+//   for (size_t i = 0; i < bad_apple_frame_count(p); i++)
+//       if (bad_apple_rasterize(p, i, pixels, size) == BAD_APPLE_STATUS_OK) draw(pixels);
+//   bad_apple_free(p);
+//
+// Like decode.rs this only uses `core` and `alloc`, though the feature turns
+// on std so the library has a panic handler and allocator.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{ptr, slice};
+
+use crate::decode::{decode, rasterize_rects_into, Rect};
+
+/// Opaque decoded payload, created by `bad_apple_open`.
+pub struct BadApplePayload {
+    width: u32,
+    height: u32,
+    fps: u32,
+    frames: Vec<Vec<Rect>>,
+}
+
+/// Result of `bad_apple_rasterize`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadAppleStatus {
+    Ok = 0,
+    NullPointer = -1,
+    FrameOutOfRange = -2,
+    BufferTooSmall = -3,
+}
+
+/// Decodes a binary ("BAPL") payload. The bytes are copied, so `data` can be
+/// freed afterwards. Returns NULL if `data` is NULL or not a valid payload.
+///
+/// # Safety
+///
+/// `data` must be NULL or point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_open(data: *const u8, len: usize) -> *mut BadApplePayload {
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+    // SAFETY: caller guarantees `len` readable bytes at `data`
+    let bytes = unsafe { slice::from_raw_parts(data, len) };
+
+    let Ok((header, frames)) = decode(bytes) else {
+        return ptr::null_mut();
+    };
+    let Ok(frames) = frames.collect::<Result<Vec<_>, _>>() else {
+        return ptr::null_mut();
+    };
+
+    Box::into_raw(Box::new(BadApplePayload {
+        width: header.width,
+        height: header.height,
+        fps: header.fps,
+        frames,
+    }))
+}
+
+/// Frees a payload returned by `bad_apple_open`. NULL is ignored.
+///
+/// # Safety
+///
+/// `payload` must be NULL or a pointer from `bad_apple_open` not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_free(payload: *mut BadApplePayload) {
+    if !payload.is_null() {
+        // SAFETY: caller guarantees it came from Box::into_raw in bad_apple_open
+        drop(unsafe { Box::from_raw(payload) });
+    }
+}
+
+/// Shared `&BadApplePayload` from a possibly NULL pointer.
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`.
+unsafe fn payload_ref<'a>(payload: *const BadApplePayload) -> Option<&'a BadApplePayload> {
+    // SAFETY: forwarded from the caller
+    unsafe { payload.as_ref() }
+}
+
+/// Frame width in pixels (0 for NULL).
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_width(payload: *const BadApplePayload) -> u32 {
+    unsafe { payload_ref(payload) }.map_or(0, |p| p.width)
+}
+
+/// Frame height in pixels (0 for NULL).
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_height(payload: *const BadApplePayload) -> u32 {
+    unsafe { payload_ref(payload) }.map_or(0, |p| p.height)
+}
+
+/// Frames per second (0 for NULL).
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_fps(payload: *const BadApplePayload) -> u32 {
+    unsafe { payload_ref(payload) }.map_or(0, |p| p.fps)
+}
+
+/// Number of frames (0 for NULL).
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_frame_count(payload: *const BadApplePayload) -> usize {
+    unsafe { payload_ref(payload) }.map_or(0, |p| p.frames.len())
+}
+
+/// Paints frame `index` into `out` as one byte per pixel, row-major, 1 = black/on
+/// and 0 = off. `out_len` must be at least width × height.
+///
+/// # Safety
+///
+/// `payload` must be NULL or a live pointer from `bad_apple_open`, and `out`
+/// must be NULL or point to `out_len` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bad_apple_rasterize(
+    payload: *const BadApplePayload,
+    index: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> BadAppleStatus {
+    let Some(p) = (unsafe { payload_ref(payload) }) else {
+        return BadAppleStatus::NullPointer;
+    };
+    if out.is_null() {
+        return BadAppleStatus::NullPointer;
+    }
+    let Some(rects) = p.frames.get(index) else {
+        return BadAppleStatus::FrameOutOfRange;
+    };
+
+    let (w, h) = (p.width as usize, p.height as usize);
+    if out_len < w * h {
+        return BadAppleStatus::BufferTooSmall;
+    }
+
+    // SAFETY: caller guarantees `out_len` (>= w * h) writable bytes at `out`
+    let frame = unsafe { slice::from_raw_parts_mut(out, w * h) };
+    rasterize_rects_into(rects, w, h, frame);
+    BadAppleStatus::Ok
+}
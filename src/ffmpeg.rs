@@ -0,0 +1,292 @@
+// src/lib/ffmpeg.rs
+// Runs ffmpeg for the steps before conversion, so the whole pipeline goes
+// through one tool: a video becomes a frames directory the converter reads
+// (numbered grayscale PNGs at the payload size) and the audio track the
+// players sync to. The other way round, a payload is rasterized and piped
+// into ffmpeg as raw gray frames to get a normal video of the result.
+
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::raster::{resample, Fit};
+use crate::rectframes::Payload;
+
+/// File name pattern of extracted frames (`frame_00001.png`, ...).
+pub const FRAME_PATTERN: &str = "frame_%05d.png";
+
+#[derive(Clone, Debug)]
+pub struct ExtractOpts {
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Unsharp mask after scaling, keeps silhouette edges crisp at low sizes
+    pub sharpen: bool,
+    /// Delete PNGs already in the output directory instead of refusing
+    pub force: bool,
+    /// ffmpeg executable, looked up on PATH unless it's a path
+    pub ffmpeg: PathBuf,
+}
+
+impl Default for ExtractOpts {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            width: 256,
+            height: 192,
+            sharpen: true,
+            force: false,
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+}
+
+/// The `-vf` chain: resample to `fps`, lanczos scale, sharpen, grayscale.
+pub fn frames_filter(opts: &ExtractOpts) -> String {
+    let mut vf = format!(
+        "fps={},scale={}:{}:flags=lanczos",
+        opts.fps, opts.width, opts.height
+    );
+    if opts.sharpen {
+        vf.push_str(",unsharp=5:5:1.2:5:5:0.0");
+    }
+    vf.push_str(",format=gray");
+    vf
+}
+
+/// Extracts `video` into `out_dir` as `FRAME_PATTERN` PNGs; returns how many
+/// frames were written.
+pub fn extract_frames(video: &Path, out_dir: &Path, opts: &ExtractOpts) -> Result<usize> {
+    if opts.fps == 0 || opts.width == 0 || opts.height == 0 {
+        bail!("fps and size must be non-zero");
+    }
+    if !video.is_file() {
+        bail!("Video not found: {}", video.display());
+    }
+
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed creating {}", out_dir.display()))?;
+    // Leftovers from a longer video would end up after the new frames
+    let old = pngs(out_dir)?;
+    if !old.is_empty() {
+        if !opts.force {
+            bail!(
+                "{} already holds {} PNGs, pass --force to replace them",
+                out_dir.display(),
+                old.len()
+            );
+        }
+        for png in old {
+            fs::remove_file(&png)
+                .with_context(|| format!("Failed removing {}", png.display()))?;
+        }
+    }
+
+    let mut cmd = Command::new(&opts.ffmpeg);
+    cmd.args(["-y", "-hide_banner", "-loglevel", "error", "-i"])
+        .arg(video)
+        .arg("-vf")
+        .arg(frames_filter(opts))
+        .arg(out_dir.join(FRAME_PATTERN));
+    run(cmd, &opts.ffmpeg)?;
+
+    Ok(pngs(out_dir)?.len())
+}
+
+/// Encoder arguments for an audio file, picked from its extension.
+fn audio_codec(out: &Path) -> Result<&'static [&'static str]> {
+    let ext = out
+        .extension()
+        .and_then(|x| x.to_str())
+        .map(str::to_ascii_lowercase);
+    let codec: &'static [&'static str] = match ext.as_deref() {
+        Some("mp3") => &["-c:a", "libmp3lame", "-q:a", "2"],
+        Some("ogg") => &["-c:a", "libvorbis", "-q:a", "5"],
+        Some("opus") => &["-c:a", "libopus", "-b:a", "128k"],
+        Some("m4a" | "aac") => &["-c:a", "aac", "-b:a", "192k"],
+        Some("flac") => &["-c:a", "flac"],
+        Some("wav") => &["-c:a", "pcm_s16le"],
+        _ => bail!(
+            "Can't tell the audio format from {} (mp3, ogg, opus, m4a, flac or wav)",
+            out.display()
+        ),
+    };
+    Ok(codec)
+}
+
+/// Extracts the audio track of `video` into `out`, encoded by extension
+/// (`audio_codec`); returns the file size in bytes.
+pub fn extract_audio(video: &Path, out: &Path, ffmpeg: &Path) -> Result<u64> {
+    let codec = audio_codec(out)?;
+    if !video.is_file() {
+        bail!("Video not found: {}", video.display());
+    }
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-hide_banner", "-loglevel", "error", "-i"])
+        .arg(video)
+        .arg("-vn")
+        .args(codec)
+        .arg(out);
+    run(cmd, ffmpeg)?;
+
+    Ok(fs::metadata(out)
+        .with_context(|| format!("ffmpeg wrote no {}", out.display()))?
+        .len())
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderVideoOpts {
+    /// Integer upscale of the payload size
+    pub scale: u32,
+    /// White silhouette on black instead of black on white
+    pub invert: bool,
+    /// Soundtrack muxed in, cut to the video length
+    pub audio: Option<PathBuf>,
+    pub ffmpeg: PathBuf,
+}
+
+impl Default for RenderVideoOpts {
+    fn default() -> Self {
+        Self {
+            scale: 1,
+            invert: false,
+            audio: None,
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+}
+
+/// Video and audio encoder arguments for a video file, picked from its extension.
+fn video_codec(out: &Path) -> Result<(&'static [&'static str], &'static [&'static str])> {
+    let ext = out
+        .extension()
+        .and_then(|x| x.to_str())
+        .map(str::to_ascii_lowercase);
+    let codecs: (&'static [&'static str], &'static [&'static str]) = match ext.as_deref() {
+        Some("mp4" | "mov" | "mkv") => (
+            &["-c:v", "libx264", "-crf", "18", "-pix_fmt", "yuv420p"],
+            &["-c:a", "aac", "-b:a", "192k"],
+        ),
+        Some("webm") => (
+            &["-c:v", "libvpx-vp9", "-crf", "30", "-b:v", "0", "-pix_fmt", "yuv420p"],
+            &["-c:a", "libopus", "-b:a", "128k"],
+        ),
+        _ => bail!(
+            "Can't tell the video format from {} (mp4, mov, mkv or webm)",
+            out.display()
+        ),
+    };
+    Ok(codecs)
+}
+
+/// Rasterizes every frame of `payload` and encodes them into `out` (codec by
+/// extension, `video_codec`); returns the file size in bytes.
+pub fn render_video(payload: &Payload, out: &Path, opts: &RenderVideoOpts) -> Result<u64> {
+    let (video, audio) = video_codec(out)?;
+    if payload.fps == 0 || payload.rect_frames.is_empty() {
+        bail!("Nothing to render");
+    }
+    if let Some(path) = &opts.audio
+        && !path.is_file()
+    {
+        bail!("Audio not found: {}", path.display());
+    }
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+
+    let (w, h) = (payload.width as usize, payload.height as usize);
+    let scale = opts.scale.max(1) as usize;
+    let (vw, vh) = (w * scale, h * scale);
+
+    let mut cmd = Command::new(&opts.ffmpeg);
+    cmd.args(["-y", "-hide_banner", "-loglevel", "error"])
+        .args(["-f", "rawvideo", "-pix_fmt", "gray", "-s"])
+        .arg(format!("{vw}x{vh}"))
+        .arg("-r")
+        .arg(payload.fps.to_string())
+        .args(["-i", "-"]);
+    if let Some(path) = &opts.audio {
+        cmd.arg("-i").arg(path).args(["-map", "0:v", "-map", "1:a", "-shortest"]);
+        cmd.args(audio);
+    }
+    // yuv420p needs even dimensions
+    cmd.args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .args(video)
+        .arg(out)
+        .stdin(Stdio::piped());
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => bail!(
+            "{} not found, install ffmpeg or pass --ffmpeg <path>",
+            opts.ffmpeg.display()
+        ),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed running {}", opts.ffmpeg.display()));
+        }
+    };
+
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let (on, off) = if opts.invert { (255, 0) } else { (0, 255) };
+    let mut written = Ok(());
+    for i in 0..payload.rect_frames.len() {
+        let px = resample(&payload.rasterize_frame(i), w, h, vw, vh, Fit::Stretch);
+        let gray: Vec<u8> = px.iter().map(|&v| if v != 0 { on } else { off }).collect();
+        // A broken pipe means ffmpeg quit; its exit status says why
+        written = stdin.write_all(&gray);
+        if written.is_err() {
+            break;
+        }
+    }
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed running {}", opts.ffmpeg.display()))?;
+    if !status.success() {
+        bail!("{} failed ({status})", opts.ffmpeg.display());
+    }
+    written.context("Failed piping frames to ffmpeg")?;
+
+    Ok(fs::metadata(out)
+        .with_context(|| format!("ffmpeg wrote no {}", out.display()))?
+        .len())
+}
+
+/// Runs ffmpeg, turning a missing executable or a failed run into an error.
+fn run(mut cmd: Command, ffmpeg: &Path) -> Result<()> {
+    let status = match cmd.status() {
+        Ok(status) => status,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            bail!(
+                "{} not found, install ffmpeg or pass --ffmpeg <path>",
+                ffmpeg.display()
+            )
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed running {}", ffmpeg.display())),
+    };
+    if !status.success() {
+        bail!("{} failed ({status})", ffmpeg.display());
+    }
+    Ok(())
+}
+
+fn pngs(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(fs::read_dir(dir)
+        .with_context(|| format!("Failed reading {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "png"))
+        .collect())
+}
@@ -0,0 +1,124 @@
+// src/lib/flat.rs
+// Flat-array payload for game engines and typed-array players.
+//
+// Instead of one object per rect, all rects of all frames live in a single
+// `rects` integer array as x,y,w,h quadruples, and frame i spans
+// `rects[frame_offsets[i]..frame_offsets[i + 1]]` (offsets are array indices,
+// so always multiples of 4; there are frames_count + 1 of them). Only "on"
+// rects are stored, which is every rect the converter emits.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::rectframes::{Payload, Rect, PAYLOAD_VERSION};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FlatPayload {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub threshold: u32,
+    pub th_mul: f32,
+    pub invert: bool,
+    pub frames_count: usize,
+    pub frame_offsets: Vec<u32>,
+    pub rects: Vec<u32>,
+}
+
+impl FlatPayload {
+    pub fn from_payload(payload: &Payload) -> Self {
+        let mut frame_offsets = Vec::with_capacity(payload.rect_frames.len() + 1);
+        let mut rects = Vec::new();
+
+        frame_offsets.push(0);
+        for frame in &payload.rect_frames {
+            for r in frame.iter().filter(|r| r.v != 0) {
+                rects.extend_from_slice(&[r.x, r.y, r.w, r.h]);
+            }
+            frame_offsets.push(rects.len() as u32);
+        }
+
+        Self {
+            width: payload.width,
+            height: payload.height,
+            fps: payload.fps,
+            threshold: payload.threshold,
+            th_mul: payload.th_mul,
+            invert: payload.invert,
+            frames_count: payload.rect_frames.len(),
+            frame_offsets,
+            rects,
+        }
+    }
+
+    pub fn to_payload(&self) -> Result<Payload> {
+        if self.frame_offsets.len() != self.frames_count + 1 {
+            anyhow::bail!(
+                "Expected {} frame offsets, found {}",
+                self.frames_count + 1,
+                self.frame_offsets.len()
+            );
+        }
+
+        let rect_frames = self
+            .frame_offsets
+            .windows(2)
+            .map(|w| {
+                let (start, end) = (w[0] as usize, w[1] as usize);
+                let quads = self
+                    .rects
+                    .get(start..end)
+                    .filter(|q| start % 4 == 0 && q.len() % 4 == 0)
+                    .context("Frame offsets out of range or misaligned")?;
+                Ok(quads
+                    .chunks_exact(4)
+                    .map(|q| Rect {
+                        x: q[0],
+                        y: q[1],
+                        w: q[2],
+                        h: q[3],
+                        v: 1,
+                    })
+                    .collect())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Payload {
+            version: PAYLOAD_VERSION,
+            width: self.width,
+            height: self.height,
+            fps: self.fps,
+            threshold: self.threshold,
+            th_mul: self.th_mul,
+            invert: self.invert,
+            frames_count: self.frames_count,
+            rect_frames,
+            frame_thresholds: None,
+            frame_rect_counts: None,
+            preprocess: Vec::new(),
+            off_rects: false,
+            interpolated: None,
+        })
+    }
+}
+
+pub fn write_flat_file(flat: &FlatPayload, out_file: &Path) -> Result<()> {
+    let json = serde_json::to_string(flat)?;
+
+    if let Some(parent) = out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(out_file, json)
+        .with_context(|| format!("Failed writing flat payload: {}", out_file.display()))?;
+
+    Ok(())
+}
+
+pub fn read_flat_file(path: &Path) -> Result<FlatPayload> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("Failed reading flat payload: {}", path.display()))?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("Failed parsing flat payload: {}", path.display()))
+}
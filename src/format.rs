@@ -0,0 +1,135 @@
+// src/lib/format.rs
+// Output formats a converted payload can be written as.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::bitmap::{encode_bitmap, write_bitmap_file, BitmapPayload};
+use crate::flat::{write_flat_file, FlatPayload};
+use crate::rectframes::{write_payload_json_with, Payload};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// rectFrames.json (merged rects)
+    #[default]
+    Json,
+    /// Packed 1bpp frames (see bitmap.rs)
+    Bitmap,
+    /// One flat x,y,w,h integer array plus frame offsets (see flat.rs)
+    Flat,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "bitmap" => Ok(OutputFormat::Bitmap),
+            "flat" => Ok(OutputFormat::Flat),
+            _ => Err(format!("unknown format {s:?} (expected json|bitmap|flat)")),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Bitmap => "bitmap",
+            OutputFormat::Flat => "flat",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriteOpts {
+    pub format: OutputFormat,
+    /// Bitmap only: store frames XOR'd against the previous one
+    pub xor: bool,
+    /// Json only: indented instead of compact
+    pub pretty: bool,
+}
+
+pub fn write_payload(payload: &Payload, out_file: &Path, opts: &WriteOpts) -> Result<()> {
+    match opts.format {
+        OutputFormat::Json => Ok(write_payload_json_with(payload, out_file, opts.pretty)?),
+        OutputFormat::Bitmap => {
+            write_bitmap_file(&BitmapPayload::from_payload(payload), opts.xor, out_file)
+        }
+        OutputFormat::Flat => write_flat_file(&FlatPayload::from_payload(payload), out_file),
+    }
+}
+
+/// The bytes `write_payload` writes, in memory (e.g. for stdout).
+pub fn payload_bytes(payload: &Payload, opts: &WriteOpts) -> Result<Vec<u8>> {
+    Ok(match opts.format {
+        OutputFormat::Json if opts.pretty => serde_json::to_vec_pretty(payload)?,
+        OutputFormat::Json => serde_json::to_vec(payload)?,
+        OutputFormat::Bitmap => encode_bitmap(&BitmapPayload::from_payload(payload), opts.xor)?,
+        OutputFormat::Flat => serde_json::to_vec(&FlatPayload::from_payload(payload))?,
+    })
+}
+
+/// Size in bytes `write_payload` would write, computed in memory.
+pub fn encoded_len(payload: &Payload, opts: &WriteOpts) -> Result<usize> {
+    Ok(payload_bytes(payload, opts)?.len())
+}
+
+/// Lists the payloads `write_sizes` wrote (the conversion size first), so a
+/// player can pick the resolution that suits the device.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SizesManifest {
+    pub sizes: Vec<SizeEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SizeEntry {
+    pub width: u32,
+    pub height: u32,
+    /// File name, relative to the manifest
+    pub file: String,
+    pub format: String,
+}
+
+/// `rectFrames.json` at 128×96 -> `rectFrames_128x96.json`.
+pub fn sized_path(out_file: &Path, width: u32, height: u32) -> PathBuf {
+    let stem = out_file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match out_file.extension() {
+        Some(ext) => format!("{stem}_{width}x{height}.{}", ext.to_string_lossy()),
+        None => format!("{stem}_{width}x{height}"),
+    };
+    out_file.with_file_name(name)
+}
+
+/// Writes each payload next to `out_file` (see `sized_path`) plus
+/// `<stem>.manifest.json` listing them; returns the manifest path.
+pub fn write_sizes(payloads: &[Payload], out_file: &Path, opts: &WriteOpts) -> Result<PathBuf> {
+    let mut sizes = Vec::with_capacity(payloads.len());
+    for payload in payloads {
+        let path = sized_path(out_file, payload.width, payload.height);
+        write_payload(payload, &path, opts)?;
+        sizes.push(SizeEntry {
+            width: payload.width,
+            height: payload.height,
+            file: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            format: opts.format.to_string(),
+        });
+    }
+
+    let stem = out_file.file_stem().unwrap_or_default().to_string_lossy();
+    let manifest = out_file.with_file_name(format!("{stem}.manifest.json"));
+    let json = serde_json::to_string_pretty(&SizesManifest { sizes })?;
+    fs::write(&manifest, json)
+        .with_context(|| format!("Failed writing manifest: {}", manifest.display()))?;
+    Ok(manifest)
+}
@@ -0,0 +1,175 @@
+// src/lib/golden.rs
+// Regression check of a freshly converted payload against a committed
+// reference ("golden") one, for encoder changes across releases.
+//
+// Settings that shape the output (size, fps, frame count, th_mul, invert,
+// preprocessing, off rects, interpolation) must match exactly. The frames
+// themselves may drift a little, e.g. after a change to threshold rounding:
+//   - thresholds: average and per-frame (when both recorded them) within
+//     `max_threshold_drift` luma levels
+//   - pixels: every frame's pixel accuracy against the reference's at least
+//     `min_accuracy` (rects may split differently as long as they cover the
+//     same pixels)
+//   - rect count: the total within `max_rect_drift` (relative)
+
+use crate::metrics::{compare_frames, FrameQuality};
+use crate::rectframes::Payload;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenOpts {
+    /// Luma levels the thresholds may move
+    pub max_threshold_drift: f32,
+    /// Lowest pixel accuracy of any frame (0-1)
+    pub min_accuracy: f64,
+    /// Relative change allowed in the total rect count
+    pub max_rect_drift: f64,
+}
+
+impl Default for GoldenOpts {
+    fn default() -> Self {
+        Self {
+            max_threshold_drift: 2.0,
+            min_accuracy: 0.995,
+            max_rect_drift: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GoldenReport {
+    pub frames: usize,
+    /// Frames whose pixels differ at all from the reference
+    pub changed_frames: usize,
+    /// Frame with the lowest pixel accuracy, None when frames weren't compared
+    pub worst: Option<FrameQuality>,
+    /// Largest threshold difference, average or per-frame
+    pub threshold_drift: f32,
+    /// (actual - reference) / reference rect count
+    pub rect_drift: f64,
+    /// What went past the tolerances; empty when the payload passes
+    pub failures: Vec<String>,
+}
+
+impl GoldenReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Compares `actual` against `reference` within the tolerances of `opts`.
+pub fn compare_golden(reference: &Payload, actual: &Payload, opts: &GoldenOpts) -> GoldenReport {
+    let mut failures = Vec::new();
+    let mut setting = |name: &str, expected: String, got: String| {
+        if expected != got {
+            failures.push(format!("{name}: {got}, reference has {expected}"));
+        }
+    };
+    setting(
+        "size",
+        format!("{}×{}", reference.width, reference.height),
+        format!("{}×{}", actual.width, actual.height),
+    );
+    setting("fps", reference.fps.to_string(), actual.fps.to_string());
+    setting(
+        "frames",
+        reference.rect_frames.len().to_string(),
+        actual.rect_frames.len().to_string(),
+    );
+    setting("th_mul", format!("{:.4}", reference.th_mul), format!("{:.4}", actual.th_mul));
+    setting("invert", reference.invert.to_string(), actual.invert.to_string());
+    setting(
+        "preprocess",
+        format!("{:?}", reference.preprocess),
+        format!("{:?}", actual.preprocess),
+    );
+    setting("off_rects", reference.off_rects.to_string(), actual.off_rects.to_string());
+    setting(
+        "interpolated",
+        format!("{:?}", reference.interpolated),
+        format!("{:?}", actual.interpolated),
+    );
+
+    let mut threshold_drift = (reference.threshold as f32 - actual.threshold as f32).abs();
+    if threshold_drift > opts.max_threshold_drift {
+        failures.push(format!(
+            "average threshold {}, reference has {}",
+            actual.threshold, reference.threshold
+        ));
+    }
+    if let (Some(expected), Some(got)) = (&reference.frame_thresholds, &actual.frame_thresholds) {
+        let mut over = Vec::new();
+        for (i, (a, b)) in expected.iter().zip(got).enumerate() {
+            let drift = (a - b).abs();
+            if drift > opts.max_threshold_drift {
+                over.push(i);
+            }
+            threshold_drift = threshold_drift.max(drift);
+        }
+        if let Some(&first) = over.first() {
+            failures.push(format!(
+                "{} frame thresholds drift over {:.1} (first: frame {first})",
+                over.len(),
+                opts.max_threshold_drift
+            ));
+        }
+    }
+
+    let rects = |p: &Payload| p.rect_frames.iter().map(Vec::len).sum::<usize>();
+    let (expected_rects, got_rects) = (rects(reference), rects(actual));
+    let rect_drift = if expected_rects == 0 {
+        if got_rects == 0 { 0.0 } else { f64::INFINITY }
+    } else {
+        (got_rects as f64 - expected_rects as f64) / expected_rects as f64
+    };
+    if rect_drift.abs() > opts.max_rect_drift {
+        failures.push(format!(
+            "{got_rects} rects, reference has {expected_rects} ({:+.1}%)",
+            rect_drift * 100.0
+        ));
+    }
+
+    // Pixels only compare between frames of the same size and count
+    let comparable = reference.width == actual.width
+        && reference.height == actual.height
+        && reference.rect_frames.len() == actual.rect_frames.len();
+    let mut changed_frames = 0;
+    let mut worst: Option<FrameQuality> = None;
+    if comparable {
+        for frame in 0..reference.rect_frames.len() {
+            if reference.rect_frames[frame] == actual.rect_frames[frame] {
+                continue;
+            }
+            let (accuracy, iou) =
+                compare_frames(&reference.rasterize_frame(frame), &actual.rasterize_frame(frame));
+            if accuracy < 1.0 {
+                changed_frames += 1;
+            }
+            if worst.is_none_or(|w| accuracy < w.accuracy) {
+                worst = Some(FrameQuality {
+                    frame,
+                    accuracy,
+                    iou,
+                });
+            }
+        }
+        if let Some(w) = worst
+            && w.accuracy < opts.min_accuracy
+        {
+            failures.push(format!(
+                "frame {}: {:.2}% pixel accuracy, under {:.2}%",
+                w.frame,
+                w.accuracy * 100.0,
+                opts.min_accuracy * 100.0
+            ));
+        }
+    }
+
+    GoldenReport {
+        frames: reference.rect_frames.len(),
+        changed_frames,
+        worst,
+        threshold_drift,
+        rect_drift,
+        failures,
+    }
+}
@@ -0,0 +1,336 @@
+// src/lib/gpu.rs
+// wgpu compute path for grayscale conversion + thresholding (feature "gpu"),
+// for 1080p colour dumps where CPU binarization dominates. Two passes over a
+// frame: RGBA -> luma with the luma sum reduced on the GPU, then the compare
+// against `ceil(mean * th_mul)`. The threshold is computed on the CPU between
+// the passes with the same f32 formula as `binarize_luma`. Luma uses image's
+// Rec. 709 weights in f32 but not its exact rounding, so a rare colour pixel can
+// land one level off `to_luma8` (grayscale PNGs stay on the CPU and are exact).
+// Without an adapter everything stays on the CPU.
+
+use image::DynamicImage;
+use std::{path::Path, sync::mpsc};
+use tracing::{info, warn};
+use wgpu::util::DeviceExt;
+
+use crate::error::{BadAppleError, Result};
+use crate::rectframes::{binarize_image, check_size, FrameParams};
+
+const SHADER: &str = r#"
+struct Params {
+    n: u32,
+    cut: u32,
+    flip: u32,
+    _pad: u32,
+}
+
+@group(0) @binding(0) var<storage, read> rgba: array<u32>;
+@group(0) @binding(1) var<storage, read_write> luma: array<u32>;
+@group(0) @binding(2) var<storage, read_write> sum: atomic<u32>;
+@group(0) @binding(3) var<uniform> params: Params;
+@group(0) @binding(4) var<storage, read_write> bits: array<u32>;
+
+var<workgroup> partial: atomic<u32>;
+
+// Rec. 709 weights as image's to_luma8 derives them
+@compute @workgroup_size(256)
+fn to_luma(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) li: u32,
+) {
+    let i = id.x;
+    if i < params.n {
+        let p = rgba[i];
+        let c = unpack4x8unorm(p).rgb;
+        let l = u32(round(dot(c, vec3<f32>(0.21263901, 0.71516868, 0.07219232)) * 255.0));
+        luma[i] = l;
+        atomicAdd(&partial, l);
+    }
+    workgroupBarrier();
+    if li == 0u {
+        atomicAdd(&sum, atomicLoad(&partial));
+    }
+}
+
+// One invocation per 4 pixels, packed little-endian into one word
+@compute @workgroup_size(256)
+fn threshold(@builtin(global_invocation_id) id: vec3<u32>) {
+    let base = id.x * 4u;
+    if base >= params.n {
+        return;
+    }
+    var word = 0u;
+    for (var k = 0u; k < 4u; k++) {
+        let i = base + k;
+        if i < params.n {
+            let on = select(0u, 1u, luma[i] < params.cut) ^ params.flip;
+            word |= on << (8u * k);
+        }
+    }
+    bits[id.x] = word;
+}
+"#;
+
+const WORKGROUP: u32 = 256;
+/// Largest frame one dispatch covers (also keeps the u32 luma sum exact).
+const MAX_PIXELS: usize = 65535 * WORKGROUP as usize;
+
+pub struct GpuBinarizer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    to_luma: wgpu::ComputePipeline,
+    threshold: wgpu::ComputePipeline,
+}
+
+impl GpuBinarizer {
+    /// Opens the default adapter, or None (with a warning) when there is none.
+    pub fn new() -> Option<Self> {
+        match pollster::block_on(Self::open()) {
+            Ok(gpu) => Some(gpu),
+            Err(e) => {
+                warn!("No GPU adapter ({e}), binarizing on the CPU");
+                None
+            }
+        }
+    }
+
+    async fn open() -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("bad-apple"),
+                ..Default::default()
+            })
+            .await?;
+        info!("🖥️  GPU: {}", adapter.get_info().name);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("binarize"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("binarize"),
+            entries: &[
+                storage(0, true),
+                storage(1, false),
+                storage(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(4, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("binarize"),
+            bind_group_layouts: &[Some(&layout)],
+            immediate_size: 0,
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Ok(Self {
+            to_luma: pipeline("to_luma"),
+            threshold: pipeline("threshold"),
+            layout,
+            device,
+            queue,
+        })
+    }
+
+    /// Decodes a PNG frame and binarizes it, on the GPU for 8-bit colour
+    /// frames and on the CPU otherwise (grayscale PNGs need no conversion,
+    /// 16-bit ones would round differently).
+    pub(crate) fn binarize_png(
+        &self,
+        fp: &Path,
+        bytes: &[u8],
+        params: &FrameParams,
+    ) -> Result<(Vec<u8>, f32)> {
+        let img = image::load_from_memory(bytes)
+            .map_err(BadAppleError::parse(fp.display().to_string()))?;
+        if params.cpu_only() {
+            // Hysteresis floods and temporal flags need the luma on the CPU
+            return binarize_image(&img, fp, params);
+        }
+        let rgba = match img {
+            DynamicImage::ImageRgba8(rgba) => rgba,
+            DynamicImage::ImageRgb8(_) => img.to_rgba8(),
+            _ => return binarize_image(&img, fp, params),
+        };
+        check_size(fp, rgba.dimensions(), params)?;
+
+        if rgba.as_raw().len() / 4 > MAX_PIXELS {
+            return binarize_image(&DynamicImage::ImageRgba8(rgba), fp, params);
+        }
+        self.binarize_rgba(rgba.as_raw(), params.invert, params.th_mul)
+    }
+
+    /// `binarize_luma(to_luma8(rgba))` on the GPU; `rgba` is 4 bytes per pixel.
+    pub fn binarize_rgba(&self, rgba: &[u8], invert: bool, th_mul: f32) -> Result<(Vec<u8>, f32)> {
+        let n = rgba.len() / 4;
+        if n == 0 || n > MAX_PIXELS {
+            return Err(BadAppleError::Gpu(format!("frame of {n} pixels")));
+        }
+        let words = n.div_ceil(4);
+        let d = &self.device;
+
+        let buffer = |label, size: usize, usage| {
+            d.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let input = d.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("rgba"),
+            contents: rgba,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let luma = buffer("luma", n * 4, wgpu::BufferUsages::STORAGE);
+        let sum = d.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sum"),
+            contents: &0u32.to_le_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let uniform = d.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: &params_bytes(n, 0, invert),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bits = buffer(
+            "bits",
+            words * 4,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let sum_read = buffer(
+            "sum readback",
+            4,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+        let bits_read = buffer(
+            "bits readback",
+            words * 4,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let bind_group = d.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("binarize"),
+            layout: &self.layout,
+            entries: &[&input, &luma, &sum, &uniform, &bits]
+                .iter()
+                .enumerate()
+                .map(|(i, b)| wgpu::BindGroupEntry {
+                    binding: i as u32,
+                    resource: b.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        // Pass 1: luma + sum
+        self.dispatch(&self.to_luma, &bind_group, n, |enc| {
+            enc.copy_buffer_to_buffer(&sum, 0, &sum_read, 0, 4)
+        });
+        let total = u32::from_le_bytes(self.read(&sum_read)?[..4].try_into().unwrap());
+
+        // Same formula as adaptive_threshold / binarize_luma
+        let th = (total as f32) / (n as f32) * th_mul;
+        let cut = th.ceil().clamp(0.0, 256.0) as u32;
+        self.queue
+            .write_buffer(&uniform, 0, &params_bytes(n, cut, invert));
+
+        // Pass 2: compare
+        self.dispatch(&self.threshold, &bind_group, words, |enc| {
+            enc.copy_buffer_to_buffer(&bits, 0, &bits_read, 0, (words * 4) as u64)
+        });
+        let mut frame = self.read(&bits_read)?;
+        frame.truncate(n);
+
+        Ok((frame, th))
+    }
+
+    fn dispatch(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+        invocations: usize,
+        then: impl FnOnce(&mut wgpu::CommandEncoder),
+    ) {
+        let mut enc = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = enc.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups((invocations as u32).div_ceil(WORKGROUP), 1, 1);
+        }
+        then(&mut enc);
+        self.queue.submit([enc.finish()]);
+    }
+
+    /// Maps a readback buffer and copies it out.
+    fn read(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>> {
+        let (tx, rx) = mpsc::channel();
+        buffer.map_async(wgpu::MapMode::Read, .., move |r| {
+            let _ = tx.send(r);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| BadAppleError::Gpu(e.to_string()))?;
+        rx.recv()
+            .map_err(|e| BadAppleError::Gpu(e.to_string()))?
+            .map_err(|e| BadAppleError::Gpu(e.to_string()))?;
+
+        let bytes = buffer
+            .get_mapped_range(..)
+            .map_err(|e| BadAppleError::Gpu(e.to_string()))?
+            .to_vec();
+        buffer.unmap();
+        Ok(bytes)
+    }
+}
+
+fn params_bytes(n: usize, cut: u32, invert: bool) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for (chunk, v) in out
+        .chunks_exact_mut(4)
+        .zip([n as u32, cut, invert as u32, 0])
+    {
+        chunk.copy_from_slice(&v.to_le_bytes());
+    }
+    out
+}
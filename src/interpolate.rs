@@ -0,0 +1,244 @@
+// src/lib/interpolate.rs
+// Frame doubling for high-refresh displays: an in-between frame is inserted
+// after every frame, so a 30fps conversion plays back at 60fps for the same
+// duration. The in-between frames are built from the rasterized neighbours
+// and merged back into rects like any other frame.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::rectframes::{merge_frame_to_rects, Payload};
+
+/// How the frame between two neighbours is made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolate {
+    /// 50% blend, binarized: pixels both neighbours agree on keep their
+    /// value, the changed ones are on in every other row
+    Blend,
+    /// Shape morph: the neighbours' signed distance fields are averaged, so
+    /// edges move halfway instead of dissolving
+    Morph,
+}
+
+impl fmt::Display for Interpolate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interpolate::Blend => write!(f, "blend"),
+            Interpolate::Morph => write!(f, "morph"),
+        }
+    }
+}
+
+/// Parses `blend` or `morph`.
+impl FromStr for Interpolate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blend" => Ok(Interpolate::Blend),
+            "morph" => Ok(Interpolate::Morph),
+            _ => Err(format!("expected blend or morph, got {s:?}")),
+        }
+    }
+}
+
+/// Doubles the payload's frame rate: frame `i` is followed by the
+/// in-between of frames `i` and `i + 1` (the last one is held). Per-frame
+/// stats, when present, are interleaved the same way.
+pub fn double_frames(payload: Payload, mode: Interpolate) -> Payload {
+    let (w, h) = (payload.width as usize, payload.height as usize);
+    let n = payload.rect_frames.len();
+    let fields = |i: usize| match mode {
+        Interpolate::Morph => signed_distance(&payload.rasterize_frame(i), w, h),
+        Interpolate::Blend => None,
+    };
+
+    let mut rect_frames = Vec::with_capacity(n * 2);
+    let (mut frame, mut field) = match n {
+        0 => (Vec::new(), None),
+        _ => (payload.rasterize_frame(0), fields(0)),
+    };
+    for i in 0..n {
+        rect_frames.push(payload.rect_frames[i].clone());
+        if i + 1 == n {
+            rect_frames.push(payload.rect_frames[i].clone());
+            break;
+        }
+
+        let next = payload.rasterize_frame(i + 1);
+        let next_field = fields(i + 1);
+        let mid = match (&field, &next_field) {
+            (Some(a), Some(b)) => morph(&frame, &next, a, b, w),
+            _ => blend(&frame, &next, w),
+        };
+        rect_frames.push(merge_frame_to_rects(&mid, w, h, payload.off_rects));
+        (frame, field) = (next, next_field);
+    }
+
+    let frame_thresholds = payload.frame_thresholds.as_ref().map(|ths| {
+        ths.iter()
+            .zip(ths.iter().skip(1).chain(ths.last()))
+            .flat_map(|(&a, &b)| [a, (a + b) / 2.0])
+            .collect()
+    });
+    let frame_rect_counts = payload
+        .frame_rect_counts
+        .is_some()
+        .then(|| rect_frames.iter().map(Vec::len).collect());
+
+    Payload {
+        fps: payload.fps * 2,
+        frames_count: rect_frames.len(),
+        rect_frames,
+        frame_thresholds,
+        frame_rect_counts,
+        interpolated: Some(mode),
+        ..payload
+    }
+}
+
+/// Tie-breaker for pixels the two neighbours split evenly: alternate rows
+/// rather than a checkerboard, which would merge into 1×1 rects.
+#[inline]
+fn stripe(i: usize, w: usize) -> u8 {
+    (i / w).is_multiple_of(2) as u8
+}
+
+fn blend(a: &[u8], b: &[u8], w: usize) -> Vec<u8> {
+    a.iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (&a, &b))| if a == b { a } else { stripe(i, w) })
+        .collect()
+}
+
+/// On where the averaged fields are inside. A frame that is all on or all
+/// off has no edge to move, so `signed_distance` gives None and we blend.
+fn morph(a: &[u8], b: &[u8], da: &[f32], db: &[f32], w: usize) -> Vec<u8> {
+    da.iter()
+        .zip(db)
+        .enumerate()
+        .map(|(i, (&da, &db))| {
+            let s = da + db;
+            if s == 0.0 && a[i] != b[i] {
+                stripe(i, w)
+            } else {
+                (s > 0.0) as u8
+            }
+        })
+        .collect()
+}
+
+/// Distance from each pixel centre to the silhouette edge, positive inside
+/// (on pixels) and negative outside; None without both on and off pixels.
+fn signed_distance(frame: &[u8], w: usize, h: usize) -> Option<Vec<f32>> {
+    let on = frame.iter().filter(|&&v| v != 0).count();
+    if on == 0 || on == frame.len() {
+        return None;
+    }
+    let to_off = distance_to(frame, w, h, 0);
+    let to_on = distance_to(frame, w, h, 1);
+    Some(
+        frame
+            .iter()
+            .zip(to_off.iter().zip(&to_on))
+            .map(|(&v, (&d_off, &d_on))| match v {
+                0 => 0.5 - d_on,
+                _ => d_off - 0.5,
+            })
+            .collect(),
+    )
+}
+
+/// Euclidean distance to the nearest pixel equal to `target`: the vertical
+/// distance by two sweeps, then the Felzenszwalb–Huttenlocher 1-D squared
+/// distance transform along each row.
+fn distance_to(frame: &[u8], w: usize, h: usize, target: u8) -> Vec<f32> {
+    // Sweeps a whole row at a time so the inner loops run along x
+    let mut d = vec![f32::INFINITY; frame.len()];
+    for y in 0..h {
+        let (done, rest) = d.split_at_mut(y * w);
+        let above = done.get(done.len().wrapping_sub(w)..).unwrap_or(&[]);
+        for (x, o) in rest[..w].iter_mut().enumerate() {
+            *o = if (frame[y * w + x] != 0) == (target != 0) {
+                0.0
+            } else {
+                above.get(x).map_or(f32::INFINITY, |&a| a + 1.0)
+            };
+        }
+    }
+    for y in (0..h.saturating_sub(1)).rev() {
+        let (row, below) = d[y * w..].split_at_mut(w);
+        for (o, &b) in row.iter_mut().zip(&below[..w]) {
+            *o = o.min(b + 1.0);
+        }
+    }
+    d.iter_mut().for_each(|v| *v *= *v);
+
+    let mut line = Vec::new();
+    let mut scratch = Envelope::default();
+    for row in d.chunks_exact_mut(w) {
+        line.clear();
+        line.extend_from_slice(row);
+        for (o, v) in row.iter_mut().zip(scratch.transform(&line)) {
+            *o = v.sqrt();
+        }
+    }
+    d
+}
+
+/// Lower envelope of the parabolas `(q - p)² + f[p]`, reused across lines.
+#[derive(Default)]
+struct Envelope {
+    /// Parabola vertices
+    v: Vec<usize>,
+    /// Boundaries between neighbouring parabolas
+    z: Vec<f32>,
+}
+
+impl Envelope {
+    /// The squared distance transform of `f` (infinite = no source pixel).
+    fn transform<'f>(&'f mut self, f: &'f [f32]) -> impl Iterator<Item = f32> + 'f {
+        self.v.clear();
+        self.z.clear();
+        self.z.push(f32::NEG_INFINITY);
+
+        for (q, &fq) in f.iter().enumerate() {
+            if fq.is_infinite() {
+                continue;
+            }
+            let meet = |p: usize| {
+                ((fq + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * (q - p) as f32)
+            };
+            while let Some(&p) = self.v.last() {
+                let s = meet(p);
+                if s > self.z[self.z.len() - 1] {
+                    self.z.push(s);
+                    break;
+                }
+                self.v.pop();
+                self.z.pop();
+                if self.v.is_empty() {
+                    self.z.push(f32::NEG_INFINITY);
+                }
+            }
+            self.v.push(q);
+        }
+        self.z.push(f32::INFINITY);
+
+        let (v, z) = (&self.v, &self.z);
+        let mut k = 0;
+        (0..f.len()).map(move |q| {
+            if v.is_empty() {
+                return f32::INFINITY;
+            }
+            while z[k + 1] < q as f32 {
+                k += 1;
+            }
+            let dq = q as f32 - v[k] as f32;
+            dq * dq + f[v[k]]
+        })
+    }
+}
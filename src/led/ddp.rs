@@ -0,0 +1,100 @@
+// src/lib/led/ddp.rs
+// DDP (Distributed Display Protocol) over UDP, as spoken by WLED.
+//
+// Each packet has a 10-byte header (flags, sequence, data type, destination,
+// byte offset and length, big endian) followed by up to 1440 bytes of RGB
+// data. The last packet of a frame sets the PUSH flag so the controller
+// shows the whole frame at once.
+
+use anyhow::{Context, Result};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+use super::LedSink;
+
+pub const DEFAULT_PORT: u16 = 4048;
+
+const HEADER_LEN: usize = 10;
+/// 480 RGB pixels, keeps packets under a typical MTU
+const MAX_DATA: usize = 1440;
+
+const FLAG_VERSION_1: u8 = 0x40;
+const FLAG_PUSH: u8 = 0x01;
+const TYPE_RGB24: u8 = 0x0b;
+const DEST_DEFAULT: u8 = 0x01;
+
+/// Splits one RGB frame into DDP packets; `seq` is the 4-bit sequence number.
+pub fn frame_packets(rgb: &[u8], seq: u8) -> Vec<Vec<u8>> {
+    let chunks = rgb.chunks(MAX_DATA).collect::<Vec<_>>();
+    let last = chunks.len().saturating_sub(1);
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(n, chunk)| {
+            let mut flags = FLAG_VERSION_1;
+            if n == last {
+                flags |= FLAG_PUSH;
+            }
+            let offset = (n * MAX_DATA) as u32;
+
+            let mut pkt = Vec::with_capacity(HEADER_LEN + chunk.len());
+            pkt.extend_from_slice(&[flags, seq & 0x0f, TYPE_RGB24, DEST_DEFAULT]);
+            pkt.extend_from_slice(&offset.to_be_bytes());
+            pkt.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+            pkt.extend_from_slice(chunk);
+            pkt
+        })
+        .collect()
+}
+
+pub struct DdpSender {
+    socket: UdpSocket,
+    seq: u8,
+}
+
+/// Resolves `addr`: `host`, `host:port`, an IPv4 or IPv6 address (bare or in
+/// brackets), or `ip:port` / `[ipv6]:port`; the port defaults to 4048.
+fn resolve(addr: &str) -> Result<SocketAddr> {
+    if let Ok(target) = addr.parse::<SocketAddr>() {
+        return Ok(target);
+    }
+    let bare = addr.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, DEFAULT_PORT));
+    }
+    let with_port = match addr.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => {
+            addr.to_string()
+        }
+        _ => format!("{addr}:{DEFAULT_PORT}"),
+    };
+    with_port
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve DDP target {addr}"))?
+        .next()
+        .with_context(|| format!("DDP target {addr} has no address"))
+}
+
+impl DdpSender {
+    /// `addr` is `host` or `host:port` (port defaults to 4048), IPv6 included.
+    pub fn connect(addr: &str) -> Result<Self> {
+        let target = resolve(addr)?;
+        let local = if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+        let socket = UdpSocket::bind(local).context("Failed to bind UDP socket")?;
+        socket
+            .connect(target)
+            .with_context(|| format!("Failed to connect to DDP target {target}"))?;
+        Ok(Self { socket, seq: 0 })
+    }
+}
+
+impl LedSink for DdpSender {
+    fn show(&mut self, rgb: &[u8]) -> Result<()> {
+        // Sequence numbers run 1..=15, 0 means "not used"
+        self.seq = self.seq % 15 + 1;
+        for pkt in frame_packets(rgb, self.seq) {
+            self.socket.send(&pkt).context("Failed to send DDP packet")?;
+        }
+        Ok(())
+    }
+}
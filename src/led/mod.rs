@@ -0,0 +1,103 @@
+// src/lib/led/mod.rs
+// LED matrix playback targets.
+//
+// Frames are resampled to the matrix size and turned into RGB pixel data in
+// wiring order, then handed to an `LedSink` at the payload fps. Like the
+// terminal renderers, the white background is what lights up by default.
+
+pub mod ddp;
+#[cfg(feature = "rpi-matrix")]
+pub mod rgb_matrix;
+
+use anyhow::Result;
+use std::{thread, time::Duration};
+
+use crate::playback::PlaybackClock;
+use crate::raster::{resample, Fit};
+use crate::rectframes::Payload;
+
+pub struct LedOpts {
+    pub width: u32,
+    pub height: u32,
+    pub fit: Fit,
+    /// Odd rows run right-to-left (zig-zag wiring)
+    pub serpentine: bool,
+    /// Level of a lit LED, 0..=255
+    pub brightness: u8,
+    /// Gamma applied to `brightness` (1.0 = linear, ~2.2 for raw PWM panels)
+    pub gamma: f32,
+    /// Light up the silhouette instead of the background
+    pub invert: bool,
+    pub looping: bool,
+}
+
+/// Something that can show one RGB frame (`width * height * 3` bytes, wiring order).
+pub trait LedSink {
+    fn show(&mut self, rgb: &[u8]) -> Result<()>;
+}
+
+/// Maps a perceived level to the PWM level for the given gamma.
+pub fn gamma_level(level: u8, gamma: f32) -> u8 {
+    if gamma <= 0.0 || gamma == 1.0 {
+        return level;
+    }
+    ((level as f32 / 255.0).powf(gamma) * 255.0).round() as u8
+}
+
+/// Renders frame `i` as RGB bytes in wiring order.
+pub fn frame_to_rgb(payload: &Payload, i: usize, opts: &LedOpts) -> Vec<u8> {
+    let (w, h) = (opts.width as usize, opts.height as usize);
+    let px = resample(
+        &payload.rasterize_frame(i),
+        payload.width as usize,
+        payload.height as usize,
+        w,
+        h,
+        opts.fit,
+    );
+
+    let on = gamma_level(opts.brightness, opts.gamma);
+    let mut rgb = Vec::with_capacity(w * h * 3);
+    for y in 0..h {
+        for x in 0..w {
+            let x = if opts.serpentine && y % 2 == 1 {
+                w - 1 - x
+            } else {
+                x
+            };
+            // Silhouette pixels are 1, so the background lights up unless inverted
+            let lit = (px[y * w + x] != 0) == opts.invert;
+            let level = if lit { on } else { 0 };
+            rgb.extend_from_slice(&[level; 3]);
+        }
+    }
+    rgb
+}
+
+/// Plays the payload on `sink` in real time until the end (or forever when looping).
+pub fn play(payload: &Payload, opts: &LedOpts, sink: &mut impl LedSink) -> Result<()> {
+    if payload.rect_frames.is_empty() {
+        anyhow::bail!("Nothing to play: payload has no frames");
+    }
+
+    let fps = payload.fps.max(1) as f64;
+    let mut clock = PlaybackClock::new(payload.fps, payload.rect_frames.len());
+    if opts.looping {
+        clock.toggle_loop();
+    }
+
+    loop {
+        clock.tick();
+        if clock.is_finished() {
+            break;
+        }
+
+        let i = clock.frame();
+        // Resent every frame: controllers drop back to their own effects when the stream stalls
+        sink.show(&frame_to_rgb(payload, i, opts))?;
+
+        let wait = ((i + 1) as f64 / fps - clock.position()).clamp(0.0, 1.0 / fps);
+        thread::sleep(Duration::from_secs_f64(wait));
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+// src/lib/led/rgb_matrix.rs
+// HUB75 panels driven directly from a Raspberry Pi via rpi-rgb-led-matrix
+// (feature "rpi-matrix"). The C++ library handles the panel wiring, so frames
+// are plain row-major RGB.
+
+use anyhow::Result;
+use rpi_led_matrix::{LedCanvas, LedColor, LedMatrix, LedMatrixOptions, LedRuntimeOptions};
+
+use super::LedSink;
+
+pub struct RgbMatrixOpts<'a> {
+    /// Rows per panel
+    pub rows: u32,
+    /// Columns per panel
+    pub cols: u32,
+    /// Panels daisy-chained horizontally
+    pub chain: u32,
+    /// Parallel chains stacked vertically
+    pub parallel: u32,
+    /// Adapter wiring: regular, adafruit-hat, adafruit-hat-pwm, …
+    pub hardware_mapping: &'a str,
+    /// Slow down GPIO for faster Pis (0-4)
+    pub gpio_slowdown: u32,
+}
+
+impl RgbMatrixOpts<'_> {
+    /// Total display size in LEDs (width, height).
+    pub fn size(&self) -> (u32, u32) {
+        (self.cols * self.chain, self.rows * self.parallel)
+    }
+}
+
+pub struct RgbMatrix {
+    matrix: LedMatrix,
+    // Offscreen canvas, swapped in on vsync so frames never tear
+    canvas: Option<LedCanvas>,
+    width: usize,
+}
+
+impl RgbMatrix {
+    /// Initialises the panels; needs root (or GPIO access) on the Pi.
+    pub fn open(opts: &RgbMatrixOpts) -> Result<Self> {
+        let mut options = LedMatrixOptions::new();
+        options.set_rows(opts.rows);
+        options.set_cols(opts.cols);
+        options.set_chain_length(opts.chain);
+        options.set_parallel(opts.parallel);
+        options.set_hardware_mapping(opts.hardware_mapping);
+
+        let mut rt = LedRuntimeOptions::new();
+        rt.set_gpio_slowdown(opts.gpio_slowdown);
+
+        let matrix = LedMatrix::new(Some(options), Some(rt))
+            .map_err(|e| anyhow::anyhow!("Failed to open LED matrix: {e}"))?;
+        let canvas = matrix.offscreen_canvas();
+
+        Ok(Self {
+            matrix,
+            canvas: Some(canvas),
+            width: opts.size().0 as usize,
+        })
+    }
+}
+
+impl LedSink for RgbMatrix {
+    fn show(&mut self, rgb: &[u8]) -> Result<()> {
+        let Some(mut canvas) = self.canvas.take() else {
+            anyhow::bail!("LED matrix canvas lost");
+        };
+
+        for (i, px) in rgb.chunks_exact(3).enumerate() {
+            let color = LedColor {
+                red: px[0],
+                green: px[1],
+                blue: px[2],
+            };
+            canvas.set((i % self.width) as i32, (i / self.width) as i32, &color);
+        }
+
+        self.canvas = Some(self.matrix.swap(canvas));
+        Ok(())
+    }
+}
@@ -1,2 +1,84 @@
+// Everything but `decode` needs std (feature "std", on by default).
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod binarize;
+#[cfg(feature = "std")]
+pub mod binary;
+#[cfg(feature = "std")]
+pub mod bitmap;
+#[cfg(feature = "std")]
+pub mod cancel;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod container;
+pub mod decode;
+#[cfg(feature = "std")]
+pub mod edit;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod ffmpeg;
+#[cfg(feature = "std")]
+pub mod flat;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "std")]
+pub mod golden;
+#[cfg(feature = "std")]
+pub mod interpolate;
+#[cfg(feature = "std")]
+pub mod led;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "cli")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod playback;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod preprocess;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod raster;
+#[cfg(feature = "std")]
 pub mod rectframes;
+#[cfg(feature = "http")]
+pub mod remote;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
 pub mod subs;
+#[cfg(feature = "std")]
+pub mod term;
+#[cfg(feature = "wasm")]
+pub mod wasm;
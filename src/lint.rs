@@ -0,0 +1,226 @@
+// src/lib/lint.rs
+// Up-front checks on a frames directory, reporting every problem at once
+// instead of the converter stopping at the first bad frame.
+//
+// Frames are numbered by the last digit run of their file stem
+// (`frame_00042.png` is 42); only the PNG headers are read for sizes.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{BadAppleError, Result};
+use crate::source::frame_order;
+
+/// Extensions treated as frames (anything else in the directory is ignored).
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "webp", "tif", "tiff"];
+
+#[derive(Clone, Debug)]
+pub enum FrameIssue {
+    /// Image without a frame number in its name
+    Unnumbered(PathBuf),
+    /// Several files with the same frame number (e.g. `1.png` and `001.png`)
+    Duplicate { index: u64, files: Vec<PathBuf> },
+    /// Frame numbers missing between the first and the last frame
+    Gap(RangeInclusive<u64>),
+    /// More than one image type; the converter only reads the .png ones
+    MixedExtensions(Vec<(String, usize)>),
+    /// PNG whose size differs from the expected (or most common) one
+    SizeMismatch {
+        file: PathBuf,
+        got: (u32, u32),
+        expected: (u32, u32),
+    },
+    /// PNG whose header can't be read
+    Unreadable { file: PathBuf, reason: String },
+}
+
+impl fmt::Display for FrameIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameIssue::Unnumbered(file) => write!(f, "no frame number: {}", file.display()),
+            FrameIssue::Duplicate { index, files } => {
+                write!(f, "frame {index} appears {} times:", files.len())?;
+                for file in files {
+                    write!(f, " {}", file.display())?;
+                }
+                Ok(())
+            }
+            FrameIssue::Gap(range) if range.start() == range.end() => {
+                write!(f, "frame {} is missing", range.start())
+            }
+            FrameIssue::Gap(range) => write!(
+                f,
+                "frames {}-{} are missing ({})",
+                range.start(),
+                range.end(),
+                range.end() - range.start() + 1
+            ),
+            FrameIssue::MixedExtensions(counts) => {
+                let list: Vec<String> = counts
+                    .iter()
+                    .map(|(ext, n)| format!("{n} .{ext}"))
+                    .collect();
+                write!(
+                    f,
+                    "mixed image types ({}), only .png is converted",
+                    list.join(", ")
+                )
+            }
+            FrameIssue::SizeMismatch {
+                file,
+                got,
+                expected,
+            } => write!(
+                f,
+                "{}: {}×{}, expected {}×{}",
+                file.display(),
+                got.0,
+                got.1,
+                expected.0,
+                expected.1
+            ),
+            FrameIssue::Unreadable { file, reason } => {
+                write!(f, "{}: unreadable ({reason})", file.display())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LintReport {
+    /// Image files looked at
+    pub frames: usize,
+    /// Lowest and highest frame number
+    pub range: Option<(u64, u64)>,
+    /// Size the frames were checked against
+    pub size: Option<(u32, u32)>,
+    pub issues: Vec<FrameIssue>,
+}
+
+/// Checks `in_dir` for numbering gaps, duplicate numbers, mixed image types
+/// and PNGs of the wrong size. `size` is the expected frame size; without it
+/// the most common one is used.
+pub fn lint_frames(in_dir: &Path, size: Option<(u32, u32)>) -> Result<LintReport> {
+    if !in_dir.is_dir() {
+        return Err(BadAppleError::MissingInput {
+            what: "Input directory",
+            path: in_dir.to_path_buf(),
+        });
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(in_dir)
+        .map_err(BadAppleError::io("reading", in_dir))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && image_ext(p).is_some())
+        .collect();
+    files.sort_by(|a, b| frame_order(a, b));
+    if files.is_empty() {
+        return Err(BadAppleError::MissingInput {
+            what: "Image frames",
+            path: in_dir.to_path_buf(),
+        });
+    }
+
+    let mut report = LintReport {
+        frames: files.len(),
+        ..LintReport::default()
+    };
+
+    // Numbering: duplicates, then gaps over the sorted distinct numbers
+    let mut by_index: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for file in &files {
+        match frame_number(file) {
+            Some(i) => by_index.entry(i).or_default().push(file.clone()),
+            None => report.issues.push(FrameIssue::Unnumbered(file.clone())),
+        }
+    }
+    for (&index, files) in &by_index {
+        if files.len() > 1 {
+            report.issues.push(FrameIssue::Duplicate {
+                index,
+                files: files.clone(),
+            });
+        }
+    }
+    let mut prev = None;
+    for &i in by_index.keys() {
+        if let Some(p) = prev
+            && i > p + 1
+        {
+            report.issues.push(FrameIssue::Gap(p + 1..=i - 1));
+        }
+        prev = Some(i);
+    }
+    report.range = by_index
+        .keys()
+        .next()
+        .zip(by_index.keys().next_back())
+        .map(|(&a, &b)| (a, b));
+
+    let mut exts: BTreeMap<String, usize> = BTreeMap::new();
+    for file in &files {
+        *exts.entry(image_ext(file).unwrap_or_default()).or_default() += 1;
+    }
+    if exts.len() > 1 {
+        report
+            .issues
+            .push(FrameIssue::MixedExtensions(exts.into_iter().collect()));
+    }
+
+    // Sizes from the PNG headers, against `size` or the most common one
+    let mut sizes = Vec::new();
+    for file in files.iter().filter(|f| image_ext(f).as_deref() == Some("png")) {
+        match image::ImageReader::open(file)
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.into_dimensions().map_err(|e| e.to_string()))
+        {
+            Ok(dims) => sizes.push((file, dims)),
+            Err(reason) => report.issues.push(FrameIssue::Unreadable {
+                file: file.clone(),
+                reason,
+            }),
+        }
+    }
+    let expected = size.or_else(|| {
+        let mut counts: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+        for (_, dims) in &sizes {
+            *counts.entry(*dims).or_default() += 1;
+        }
+        counts.into_iter().max_by_key(|&(_, n)| n).map(|(dims, _)| dims)
+    });
+    if let Some(expected) = expected {
+        for (file, got) in sizes {
+            if got != expected {
+                report.issues.push(FrameIssue::SizeMismatch {
+                    file: file.clone(),
+                    got,
+                    expected,
+                });
+            }
+        }
+    }
+    report.size = expected;
+
+    Ok(report)
+}
+
+/// Lowercased extension if `path` is an image type from `IMAGE_EXTS`.
+fn image_ext(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    IMAGE_EXTS.contains(&ext.as_str()).then_some(ext)
+}
+
+/// Last digit run of the file stem, e.g. 42 for `frame_00042.png`.
+fn frame_number(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    stem[start..end].parse().ok()
+}
@@ -0,0 +1,147 @@
+// src/lib/logging.rs
+// Console subscriber for the binaries: the library only emits `tracing`
+// events, this prints them the way the tools always have (just the emoji
+// message, WARN/ERROR prefixed). Embedders install their own subscriber.
+//
+// Verbosity follows RUST_LOG (default "warn,bad_apple=info"), e.g. RUST_LOG=bad_apple=debug
+// adds per-frame threshold/rect counts. `--quiet` keeps warnings and errors only, and
+// `--log-format json` prints every event as one JSON object per line, fields included
+// (frame counts, thresholds, output sizes), for CI to parse.
+
+use clap::{Args, ValueEnum};
+use std::{fmt, io::IsTerminal};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    fmt::{format::Writer, FmtContext, FormatEvent, FormatFields},
+    registry::LookupSpan,
+    EnvFilter,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Emoji messages, as the tools always printed them
+    #[default]
+    Text,
+    /// One JSON object per event, with its structured fields
+    Json,
+}
+
+/// `--quiet` and `--log-format`, shared by the binaries (global on `bad-apple`).
+#[derive(Args, Clone, Copy, Debug, Default)]
+pub struct LogArgs {
+    /// Only print warnings and errors (no progress bar)
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Console output: text, or json (one object per line with frame counts, thresholds and
+    /// output sizes as fields, no progress bar)
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+}
+
+impl LogArgs {
+    /// `init_logging`, or `init_logging_stderr` when stdout carries the output.
+    pub fn init(&self, stderr: bool) {
+        init(self.log_format, self.quiet, stderr);
+    }
+}
+
+struct Console;
+
+/// Picks the `message` field out of an event.
+struct Message<'a>(&'a mut String);
+
+impl Visit for Message<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            use std::fmt::Write;
+            let _ = write!(self.0, "{value:?}");
+        }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for Console
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let level = *event.metadata().level();
+        match level {
+            Level::ERROR => write!(writer, "❌ ")?,
+            Level::WARN => write!(writer, "⚠️  ")?,
+            _ => {}
+        }
+        // Structured fields on info and up are for `--log-format json`, the
+        // message already says it all; debug output keeps them
+        if level <= Level::INFO {
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            write!(writer, "{message}")?;
+        } else {
+            ctx.field_format().format_fields(writer.by_ref(), event)?;
+        }
+        writeln!(writer)
+    }
+}
+
+/// Installs the console subscriber (no-op if one is already set).
+pub fn init_console() {
+    init_logging(LogFormat::Text, false);
+}
+
+/// Installs the subscriber for `format`; `quiet` drops everything below WARN.
+pub fn init_logging(format: LogFormat, quiet: bool) {
+    init(format, quiet, false);
+}
+
+/// `init_logging` on stderr, for runs whose stdout carries the output.
+pub fn init_logging_stderr(format: LogFormat, quiet: bool) {
+    init(format, quiet, true);
+}
+
+/// `init_console` on stderr, for commands whose stdout is meant for scripts.
+pub fn init_console_stderr() {
+    init(LogFormat::Text, false, true);
+}
+
+fn init(format: LogFormat, quiet: bool, stderr: bool) {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter(quiet))
+        .with_writer(move || -> Box<dyn std::io::Write> {
+            if stderr {
+                Box::new(std::io::stderr())
+            } else {
+                Box::new(std::io::stdout())
+            }
+        });
+    let ansi = if stderr {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    };
+    let _ = match format {
+        LogFormat::Text => builder.with_ansi(ansi).event_format(Console).try_init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .try_init(),
+    };
+}
+
+fn filter(quiet: bool) -> EnvFilter {
+    if quiet {
+        return EnvFilter::new("warn");
+    }
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn,bad_apple=info"))
+}
@@ -0,0 +1,1798 @@
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use encoding_rs::Encoding;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tracing::{error, info, warn};
+
+use bad_apple::bench::{bench_encoders, BenchOpts};
+use bad_apple::binarize::ThresholdMode;
+use bad_apple::binary::BinaryOpts;
+use bad_apple::cli::batch::{self, BatchArgs};
+use bad_apple::cli::convert::{self, ConvertArgs};
+use bad_apple::cli::play::{self, PlayArgs};
+#[cfg(feature = "server")]
+use bad_apple::cli::serve::{self, ServeArgs};
+use bad_apple::cli::tune::{self, TuneArgs};
+use bad_apple::config::{parse_with_config, Settings};
+use bad_apple::container::{self, read_packed, write_packed, Container, Packing};
+use bad_apple::edit::{self, parse_time};
+use bad_apple::export::asciicast::{export_asciicast_file, AsciicastOpts};
+use bad_apple::export::c_header::export_c_header_file;
+use bad_apple::export::contact_sheet::{export_contact_sheet_file, ContactSheetOpts};
+use bad_apple::export::css::{export_css_player_file, CssOpts};
+use bad_apple::export::desmos::{export_desmos_file, DesmosOpts};
+use bad_apple::export::epaper::{export_epaper_file, EpaperOpts};
+use bad_apple::export::html::{export_html_file, HtmlData, HtmlOpts, SubtitleTrack};
+use bad_apple::export::lottie::{export_lottie_file, parse_hex_color, LottieOpts};
+use bad_apple::export::minecraft::{export_datapack_dir, parse_origin, MinecraftOpts};
+use bad_apple::export::pdf::{export_pdf_file, page_preset, PdfOpts};
+use bad_apple::export::progmem::{display_preset, export_progmem_file, ProgmemOpts};
+use bad_apple::export::rust_source::export_rust_source_file;
+use bad_apple::export::spritesheet::{export_spritesheet_files, SpritesheetOpts};
+use bad_apple::export::svg::{export_svg_file, SvgOpts};
+use bad_apple::ffmpeg::{
+    extract_audio, extract_frames, render_video, ExtractOpts, RenderVideoOpts,
+};
+use bad_apple::format::sized_path;
+use bad_apple::golden::{compare_golden, GoldenOpts};
+#[cfg(feature = "rpi-matrix")]
+use bad_apple::led::rgb_matrix::{RgbMatrix, RgbMatrixOpts};
+use bad_apple::led::{self, ddp::DdpSender, LedOpts};
+use bad_apple::lint::lint_frames;
+#[cfg(feature = "http")]
+use bad_apple::logging::init_console_stderr;
+use bad_apple::logging::LogArgs;
+use bad_apple::raster::{parse_size, resample, Fit};
+use bad_apple::rectframes::{write_payload_json, Payload};
+use bad_apple::schema::{schema_json, write_schema_dir, SchemaKind};
+use bad_apple::stats::{payload_stats, Distribution};
+use bad_apple::subs::{
+    cues_to_srt_file, cues_to_vtt_file, lang_from_path, parse_encoding, parse_scale,
+    read_cues_file, read_cues_json, subs_to_json_file_with, write_subs_bundle, OverlapPolicy,
+    SubsFormat, SubsOpts, TagPolicy,
+};
+use bad_apple::term::{sixel_encode, Renderer};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert a frames directory into a payload (same options as convert_rectframes)
+    Convert(ConvertArgs),
+
+    /// Convert every project of a manifest (frames, lyrics, settings) into <out>/<name>/
+    Batch(BatchArgs),
+
+    /// Convert subtitles (SRT, WebVTT, ASS/SSA, LRC, TTML) into the cue JSON the players load
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Subs {
+        #[command(subcommand)]
+        action: Option<SubsAction>,
+
+        /// Subtitles to convert (.srt, .vtt, .ass, .ssa, .lrc, .ttml or .dfxp)
+        #[arg(required = true)]
+        input: Option<PathBuf>,
+
+        /// Input format: srt, vtt, ass, lrc or ttml (from the extension if omitted, else srt)
+        #[arg(long)]
+        format: Option<SubsFormat>,
+
+        /// Seconds added to every cue, negative to show them earlier
+        #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+        offset: f32,
+
+        /// Overlapping cues: keep, merge (one cue, text joined) or clamp (end at the next start)
+        #[arg(long, default_value = "keep")]
+        overlaps: OverlapPolicy,
+
+        /// Split cues lasting longer than this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_cue_secs: Option<f32>,
+
+        /// Split cues with more characters than this
+        #[arg(long, value_name = "CHARS")]
+        max_cue_chars: Option<usize>,
+
+        /// Factor applied to cue times before --offset, or a frame rate ratio (23.976/25 for
+        /// subs timed at 23.976 fps against a 25 fps payload)
+        #[arg(long, default_value = "1", value_parser = parse_scale)]
+        scale: f32,
+
+        /// Input text encoding, e.g. shift_jis or utf-16le (detected if omitted)
+        #[arg(long, value_parser = parse_encoding)]
+        encoding: Option<&'static Encoding>,
+
+        /// SRT formatting tags (<i>, <b>, <font color>, {\an8}): strip, keep, or style (the
+        /// cue's `f` field)
+        #[arg(long, default_value = "strip")]
+        tags: TagPolicy,
+
+        /// Keep ASS karaoke tags ({\k20}, {\kf}, {\ko}) in the cue text, and turn enhanced
+        /// LRC word timestamps into {\k} tags (word timing goes to `w` either way)
+        #[arg(long)]
+        keep_karaoke: bool,
+
+        /// Move furigana written as 漢字(かんじ), 漢字|かんじ or ｜base《reading》 out of the
+        /// text into the cue's `r` field
+        #[arg(long)]
+        ruby: bool,
+
+        /// Output file (defaults to the input path with .json); a .vtt one is written as WebVTT
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+
+        /// Indented JSON, for debugging
+        #[arg(long)]
+        pretty: bool,
+    },
+
+    /// Serve the payload and subtitles for the userscript, generating them first if missing
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+
+    /// Play a payload in the terminal
+    Play(PlayArgs),
+
+    /// Tune th_mul, the threshold mode and invert on a sample frame in the terminal, then print
+    /// the matching converter flags
+    Tune(TuneArgs),
+
+    /// Print rect/byte/coverage statistics for a rectFrames.json payload
+    Stats {
+        /// Payload to inspect
+        #[arg(default_value = "out/rectFrames.json")]
+        file: PathBuf,
+    },
+
+    /// Extract a video into a frames directory for the converter (needs ffmpeg)
+    Extract {
+        /// Source video
+        #[arg(long)]
+        video: PathBuf,
+
+        /// Frames per second to sample
+        #[arg(long, default_value_t = 30)]
+        fps: u32,
+
+        /// Frame size (WxH), use the converter's --w/--h
+        #[arg(long, default_value = "256x192", value_parser = parse_size)]
+        size: (u32, u32),
+
+        /// Frames directory
+        #[arg(long, default_value = "frames")]
+        out: PathBuf,
+
+        /// Skip the unsharp mask applied after scaling
+        #[arg(long)]
+        no_sharpen: bool,
+
+        /// Replace PNGs already in --out
+        #[arg(long)]
+        force: bool,
+
+        /// ffmpeg executable
+        #[arg(long, default_value = "ffmpeg")]
+        ffmpeg: PathBuf,
+    },
+
+    /// Extract a video's audio track for the players (needs ffmpeg)
+    Audio {
+        /// Source video
+        #[arg(long)]
+        video: PathBuf,
+
+        /// Output file; the extension picks the codec (mp3, ogg, opus, m4a, flac, wav)
+        #[arg(long, default_value = "out/audio.mp3")]
+        out: PathBuf,
+
+        /// ffmpeg executable
+        #[arg(long, default_value = "ffmpeg")]
+        ffmpeg: PathBuf,
+    },
+
+    /// Compare threshold settings and output formats on a sample of frames: rects/frame,
+    /// output size and encode time
+    Bench {
+        /// Frames directory
+        #[arg(default_value = "frames")]
+        dir: PathBuf,
+
+        /// Consecutive frames to sample
+        #[arg(long, default_value_t = 300)]
+        samples: usize,
+
+        /// First sampled frame (defaults to the middle of the video)
+        #[arg(long)]
+        from: Option<usize>,
+
+        /// Threshold multipliers to compare
+        #[arg(long, value_delimiter = ',', default_value = "0.95")]
+        th_mul: Vec<f32>,
+
+        /// Threshold modes to compare
+        #[arg(long, value_delimiter = ',', default_value = "global,tiles:16")]
+        threshold: Vec<ThresholdMode>,
+
+        #[arg(long, default_value_t = 0)]
+        invert: u8,
+    },
+
+    /// Check a frames directory for numbering gaps, duplicates, mixed image types and odd sizes
+    LintFrames {
+        /// Frames directory
+        #[arg(default_value = "frames")]
+        dir: PathBuf,
+
+        /// Expected frame size (WxH); defaults to the most common one
+        #[arg(long, value_parser = parse_size)]
+        size: Option<(u32, u32)>,
+    },
+
+    /// Convert a payload into files for other targets
+    Export {
+        /// Payload to export
+        #[arg(long, global = true, default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        /// Output file (defaults to out/bad_apple.<ext> for the target)
+        #[arg(long, global = true)]
+        out: Option<PathBuf>,
+
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+
+    /// Tile every Nth frame of a payload into one contact-sheet PNG, to check a whole conversion
+    /// at a glance
+    Preview {
+        /// Payload to preview
+        #[arg(default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        /// Keep every Nth frame (defaults to spreading --tiles frames over the payload)
+        #[arg(long)]
+        step: Option<usize>,
+
+        /// Number of tiles to aim for without --step
+        #[arg(long, default_value_t = 100)]
+        tiles: usize,
+
+        /// Tiles per row
+        #[arg(long, default_value_t = 10)]
+        columns: u32,
+
+        /// Tile width in pixels (height keeps the payload's aspect ratio)
+        #[arg(long, default_value_t = 160)]
+        tile_width: u32,
+
+        /// Output PNG
+        #[arg(short, long, default_value = "out/contact_sheet.png")]
+        out: PathBuf,
+    },
+
+    /// Re-encode a payload at a lower resolution (faster than converting the PNGs again)
+    Rescale {
+        /// Payload to rescale
+        #[arg(default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        /// Target size (WxH); frames are box-downscaled, so keep the aspect ratio yourself
+        #[arg(long, value_parser = parse_size)]
+        size: (u32, u32),
+
+        /// Output file (defaults to <input>_WxH.json next to the input)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Cut a payload to a time range, e.g. `trim --from 30s --to 90s`
+    Trim {
+        /// Payload to cut
+        #[arg(default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        /// Start time (90, 90s, 1500ms, 1:30)
+        #[arg(long, default_value = "0", value_parser = parse_time)]
+        from: f64,
+
+        /// End time, exclusive (defaults to the end of the payload)
+        #[arg(long, value_parser = parse_time)]
+        to: Option<f64>,
+
+        /// Output file
+        #[arg(short, long, default_value = "out/rectFrames_trimmed.json")]
+        out: PathBuf,
+    },
+
+    /// Join payloads of the same size and fps, e.g. a separately converted intro and outro
+    Concat {
+        /// Payloads, in playback order
+        #[arg(required = true, num_args = 2..)]
+        inputs: Vec<PathBuf>,
+
+        /// Output file
+        #[arg(short, long, default_value = "out/rectFrames_concat.json")]
+        out: PathBuf,
+    },
+
+    /// Convert a payload between containers (json, bin, msgpack, each optionally .gz) as is
+    Repack {
+        /// Payload in any container (recognised by its content)
+        input: PathBuf,
+
+        /// Output file; its extension picks the container unless --to is given
+        output: PathBuf,
+
+        /// Container to write: json, bin or msgpack, with .gz for gzip (e.g. msgpack.gz)
+        #[arg(long)]
+        to: Option<Packing>,
+
+        /// Append a CRC32 to every frame of a bin payload, checked by `verify` and the decoder
+        #[arg(long)]
+        crc32: bool,
+    },
+
+    /// Check that a payload decodes completely, e.g. after copying it to an SD card, and
+    /// optionally that it matches a committed reference payload
+    Verify {
+        /// Payload in any container (recognised by its content)
+        input: PathBuf,
+
+        /// Reference payload to compare against, e.g. one committed before an encoder change
+        #[arg(long, value_name = "PAYLOAD")]
+        golden: Option<PathBuf>,
+
+        /// Luma levels the average and per-frame thresholds may drift from the reference
+        #[arg(long, default_value_t = 2.0)]
+        max_threshold_drift: f32,
+
+        /// Lowest pixel accuracy of any frame against the reference (0-1)
+        #[arg(long, default_value_t = 0.995)]
+        min_accuracy: f64,
+
+        /// Relative change allowed in the total rect count (0.05 = ±5%)
+        #[arg(long, default_value_t = 0.05)]
+        max_rect_drift: f64,
+    },
+
+    /// Render payload frames for previews, or the whole payload as a video through ffmpeg
+    Render {
+        /// Payload to render
+        #[arg(long, default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = RenderFormat::Sixel)]
+        format: RenderFormat,
+
+        /// Frame index to render (sixel)
+        #[arg(long, default_value_t = 0)]
+        frame: usize,
+
+        /// Integer upscale
+        #[arg(long, default_value_t = 1)]
+        scale: u32,
+
+        /// Swap black and white
+        #[arg(long)]
+        invert: bool,
+
+        /// Output file (sixel: stdout if omitted, videos: out/bad_apple.<format>)
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Soundtrack to mux into the video, cut to its length
+        #[arg(long)]
+        audio: Option<PathBuf>,
+
+        /// ffmpeg executable
+        #[arg(long, default_value = "ffmpeg")]
+        ffmpeg: PathBuf,
+    },
+
+    /// Play a payload in real time on an LED matrix
+    Stream {
+        /// Payload to play
+        #[arg(long, global = true, default_value = "out/rectFrames.json")]
+        input: PathBuf,
+
+        /// Matrix size in LEDs (WxH)
+        #[arg(long, global = true, default_value = "32x16", value_parser = parse_size)]
+        size: (u32, u32),
+
+        /// contain (letterbox) or stretch
+        #[arg(long, global = true, default_value = "contain")]
+        fit: Fit,
+
+        /// Level of a lit LED (0-255)
+        #[arg(long, global = true, default_value_t = 64)]
+        brightness: u8,
+
+        /// Gamma correction for --brightness (1.0 = as is; try 2.2 for HUB75 panels)
+        #[arg(long, global = true, default_value_t = 1.0)]
+        gamma: f32,
+
+        /// Light up the silhouette instead of the background
+        #[arg(long, global = true)]
+        invert: bool,
+
+        /// Start over at the end instead of exiting
+        #[arg(long = "loop", global = true)]
+        looping: bool,
+
+        #[command(subcommand)]
+        target: StreamTarget,
+    },
+
+    /// Print JSON Schemas for the emitted JSON (payload, rect, cue, bundle)
+    Schema {
+        /// Schema to print; all of them are written to --out (a directory) if omitted
+        kind: Option<SchemaKind>,
+
+        /// Output file, or directory when no kind is given (defaults to stdout / out/schema)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Print shell completions, e.g. `bad-apple completions bash > ~/.local/share/bash-completion/completions/bad-apple`
+    Completions {
+        /// Shell to complete for
+        shell: Shell,
+    },
+
+    /// Print the man page, or write one page per subcommand into --out
+    Man {
+        /// Directory for bad-apple.1, bad-apple-convert.1, … (defaults to the main page on stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Download a frames archive, video or frame sequence into the cache and print its
+    /// local path (`--features http`), e.g. `ffmpeg -i $(bad-apple fetch URL) …`
+    #[cfg(feature = "http")]
+    Fetch {
+        /// http(s) URL; `%05d`-style counters fetch frame 1, 2, … until a 404
+        url: String,
+
+        #[arg(long, default_value = "cache")]
+        cache_dir: PathBuf,
+
+        /// Retries on network errors and 5xx responses
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SubsAction {
+    /// Convert JSON cues (e.g. edited by hand) back into SRT, or WebVTT for a .vtt output
+    Export {
+        /// Cue JSON, as `subs` writes it
+        input: PathBuf,
+
+        /// Output file (defaults to the input path with .srt)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Write every language's track into one subtitles.json, keyed by language code with a
+    /// label and text direction, so a client fetches a single file
+    Bundle {
+        /// Subtitle or cue JSON files, each optionally with its language code (default: the
+        /// file name's suffix, transcript_jp.srt -> jp)
+        #[arg(required = true, value_name = "FILE[=LANG]", value_parser = parse_bundle_input)]
+        inputs: Vec<(String, PathBuf)>,
+
+        /// Move furigana written into the text into the cues' `r` field (see `subs --ruby`)
+        #[arg(long)]
+        ruby: bool,
+
+        /// Output file
+        #[arg(short, long, default_value = "out/subtitles.json")]
+        out: PathBuf,
+
+        /// Indented JSON
+        #[arg(long)]
+        pretty: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum StreamTarget {
+    /// WLED (or any DDP receiver) over UDP
+    Wled {
+        /// Controller address: host, host:port, IP or [IPv6]:port (DDP port 4048 by default)
+        host: String,
+
+        /// Odd rows are wired right-to-left (zig-zag strips)
+        #[arg(long)]
+        serpentine: bool,
+    },
+
+    /// HUB75 panels through rpi-rgb-led-matrix (Raspberry Pi, `--features rpi-matrix`);
+    /// the display size comes from the panel layout, not --size
+    #[cfg(feature = "rpi-matrix")]
+    RgbMatrix {
+        /// Rows per panel
+        #[arg(long, default_value_t = 32)]
+        rows: u32,
+
+        /// Columns per panel
+        #[arg(long, default_value_t = 64)]
+        cols: u32,
+
+        /// Panels daisy-chained horizontally
+        #[arg(long, default_value_t = 1)]
+        chain: u32,
+
+        /// Parallel chains stacked vertically
+        #[arg(long, default_value_t = 1)]
+        parallel: u32,
+
+        /// Adapter wiring (regular, adafruit-hat, adafruit-hat-pwm, …)
+        #[arg(long, default_value = "regular")]
+        hardware_mapping: String,
+
+        /// GPIO slowdown for faster Pis (0-4)
+        #[arg(long, default_value_t = 1)]
+        gpio_slowdown: u32,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RenderFormat {
+    /// DEC sixel image, for in-terminal previews
+    Sixel,
+    /// H.264 video of every frame
+    Mp4,
+    /// VP9 video of every frame
+    Webm,
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportTarget {
+    /// C header with the binary payload as a `uint8_t` array plus #defines
+    CHeader {
+        /// Symbol prefix for the array and #defines
+        #[arg(long, default_value = "bad_apple")]
+        name: String,
+    },
+
+    /// Rust source with the binary payload as a static byte array plus a tiny decoder
+    RustSource {
+        /// Write the bytes to a sibling .bin and reference it via include_bytes!
+        #[arg(long)]
+        bytes_file: bool,
+    },
+
+    /// Bit-packed 1bpp frames in PROGMEM-sized chunks for Arduino/ESP32 displays
+    Progmem {
+        /// Target resolution (WxH)
+        #[arg(long, default_value = "128x64", value_parser = parse_size)]
+        size: (u32, u32),
+
+        /// Display controller preset (ssd1306, sh1106, st7735, ili9341, …); overrides --size
+        #[arg(long)]
+        display: Option<String>,
+
+        /// contain (letterbox) or stretch
+        #[arg(long, default_value = "contain")]
+        fit: Fit,
+
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 1)]
+        step: usize,
+
+        /// Max bytes per PROGMEM array
+        #[arg(long, default_value_t = 32767)]
+        chunk_bytes: usize,
+
+        /// Symbol prefix for the arrays and #defines
+        #[arg(long, default_value = "bad_apple")]
+        name: String,
+    },
+
+    /// Animated SVG (CSS steps), playable without JavaScript
+    Svg {
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 1)]
+        step: usize,
+
+        /// Foreground ("on") color
+        #[arg(long, default_value = "#000")]
+        fg: String,
+
+        /// Background color
+        #[arg(long, default_value = "#fff")]
+        bg: String,
+    },
+
+    /// Single self-contained .html with a canvas player, the data and subtitles inlined
+    Html {
+        /// How to embed the payload: json or base64 (binary)
+        #[arg(long, default_value = "json")]
+        data: HtmlData,
+
+        /// Subtitle track (any format `subs` reads, or cue .json); repeat for several tracks
+        #[arg(long = "subs")]
+        subs: Vec<PathBuf>,
+
+        /// Page title
+        #[arg(long, default_value = "Bad Apple!!")]
+        title: String,
+    },
+
+    /// Lottie JSON (one shape layer per frame) for mobile apps / After Effects
+    Lottie {
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 1)]
+        step: usize,
+
+        /// Foreground ("on") color
+        #[arg(long, default_value = "#000", value_parser = parse_hex_color)]
+        fg: [f32; 3],
+
+        /// Background color
+        #[arg(long, default_value = "#fff", value_parser = parse_hex_color)]
+        bg: [f32; 3],
+    },
+
+    /// Sparse low-fps keyframes with partial-refresh bounding boxes for e-paper displays
+    Epaper {
+        /// Display resolution (WxH); defaults to the payload size
+        #[arg(long, value_parser = parse_size)]
+        size: Option<(u32, u32)>,
+
+        /// contain (letterbox) or stretch
+        #[arg(long, default_value = "contain")]
+        fit: Fit,
+
+        /// Output frame rate
+        #[arg(long, default_value_t = 2.0)]
+        fps: f32,
+
+        /// Skip frames where less than this fraction of pixels changed
+        #[arg(long, default_value_t = 0.02)]
+        diff: f32,
+    },
+
+    /// Minecraft datapack playing the frames on a block wall with /fill (20 ticks per second)
+    Minecraft {
+        /// Datapack namespace (lowercase)
+        #[arg(long, default_value = "bad_apple")]
+        namespace: String,
+
+        /// Bottom-left block of the wall (x,y,z)
+        #[arg(long, default_value = "0,64,0", value_parser = parse_origin)]
+        origin: (i32, i32, i32),
+
+        /// Source pixels per block
+        #[arg(long, default_value_t = 2)]
+        downscale: u32,
+
+        /// Block for the silhouette
+        #[arg(long, default_value = "minecraft:black_concrete")]
+        on_block: String,
+
+        /// Block for the background
+        #[arg(long, default_value = "minecraft:white_concrete")]
+        off_block: String,
+
+        /// pack.mcmeta pack_format (48 = Minecraft 1.21)
+        #[arg(long, default_value_t = 48)]
+        pack_format: u32,
+    },
+
+    /// Spritesheet PNGs (grid of frames) plus a JSON atlas for game engines
+    Spritesheet {
+        /// Frame size on the sheets (WxH); defaults to the payload size
+        #[arg(long, value_parser = parse_size)]
+        frame_size: Option<(u32, u32)>,
+
+        /// contain (letterbox) or stretch
+        #[arg(long, default_value = "contain")]
+        fit: Fit,
+
+        /// Frames per row (0 = as many as fit)
+        #[arg(long, default_value_t = 0)]
+        columns: u32,
+
+        /// Maximum sheet size (WxH)
+        #[arg(long, default_value = "4096x4096", value_parser = parse_size)]
+        sheet_size: (u32, u32),
+
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 1)]
+        step: usize,
+    },
+
+    /// Printable flipbook PDF, one vector page per kept frame
+    Pdf {
+        /// Keep every Nth frame (a 30fps payload at --step 3 flips well by thumb)
+        #[arg(long, default_value_t = 3)]
+        step: usize,
+
+        /// Page size: a5, a6, a7, index-card or WxH in millimetres
+        #[arg(long, default_value = "a7")]
+        page: String,
+
+        /// Margin around the frame (mm)
+        #[arg(long, default_value_t = 5.0)]
+        margin: f32,
+
+        /// Blank strip on the left for binding (mm)
+        #[arg(long, default_value_t = 15.0)]
+        binding: f32,
+
+        /// Leave out the frame numbers
+        #[arg(long)]
+        no_numbers: bool,
+    },
+
+    /// asciinema v2 .cast of the terminal playback, for asciinema-player embeds
+    Asciicast {
+        /// blocks or braille
+        #[arg(long, default_value = "blocks")]
+        renderer: Renderer,
+
+        /// Max terminal columns
+        #[arg(long, default_value_t = 80)]
+        cols: usize,
+
+        /// Max terminal rows
+        #[arg(long, default_value_t = 30)]
+        rows: usize,
+
+        /// Light up the silhouette instead of the background
+        #[arg(long)]
+        invert: bool,
+
+        /// Recording title
+        #[arg(long, default_value = "Bad Apple!!")]
+        title: String,
+    },
+
+    /// Desmos graph state (rect inequalities per frame, ticker-driven frame slider)
+    Desmos {
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 3)]
+        step: usize,
+
+        /// Stop after this many kept frames
+        #[arg(long, default_value_t = 300)]
+        max_frames: usize,
+    },
+
+    /// Pure-CSS player page (clip-path keyframes, steps() timing), zero JavaScript
+    Css {
+        /// Keep every Nth frame
+        #[arg(long, default_value_t = 1)]
+        step: usize,
+
+        /// Integer upscale
+        #[arg(long, default_value_t = 3)]
+        scale: u32,
+
+        /// Foreground ("on") color
+        #[arg(long, default_value = "#000")]
+        fg: String,
+
+        /// Background color
+        #[arg(long, default_value = "#fff")]
+        bg: String,
+    },
+}
+
+fn schema(kind: Option<SchemaKind>, out: Option<PathBuf>) -> Result<()> {
+    let Some(kind) = kind else {
+        let dir = out.unwrap_or_else(|| PathBuf::from("out/schema"));
+        for path in write_schema_dir(&dir)? {
+            info!(out = %path.display(), "✅ Schema written: {}", path.display());
+        }
+        return Ok(());
+    };
+
+    let json = schema_json(kind)?;
+    match out {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, json)?;
+            info!(out = %path.display(), "✅ Schema written: {}", path.display());
+        }
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+fn man(out: Option<PathBuf>) -> Result<()> {
+    let cmd = Cli::command();
+    let Some(dir) = out else {
+        clap_mangen::Man::new(cmd).render(&mut io::stdout().lock())?;
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)?;
+    clap_mangen::generate_to(cmd, &dir)?;
+    info!(out = %dir.display(), "✅ Man pages written to {}", dir.display());
+    Ok(())
+}
+
+fn log_distribution(label: &str, stat: &str, d: &Distribution) {
+    info!(
+        stat,
+        min = d.min,
+        avg = d.avg,
+        max = d.max,
+        p50 = d.p50,
+        p90 = d.p90,
+        p99 = d.p99,
+        "{label}: min {:.0} / avg {:.1} / max {:.0} (p50 {:.0}, p90 {:.0}, p99 {:.0})",
+        d.min,
+        d.avg,
+        d.max,
+        d.p50,
+        d.p90,
+        d.p99
+    );
+}
+
+fn stats(file: &Path) -> Result<()> {
+    let payload = Payload::from_file(file)?;
+    let s = payload_stats(&payload)?;
+
+    info!(file = %file.display(), "📦 {}", file.display());
+    info!(
+        width = s.width,
+        height = s.height,
+        fps = s.fps,
+        "📐 {}×{} @ {}fps",
+        s.width,
+        s.height,
+        s.fps
+    );
+    info!(
+        frames_count = s.frames_count,
+        duration_secs = s.duration_secs,
+        "🎞️  Frames: {} ({:.2}s)",
+        s.frames_count,
+        s.duration_secs
+    );
+    info!(total_rects = s.total_rects, "🧱 Total rects: {}", s.total_rects);
+    log_distribution("🧮 Rects/frame", "rects_per_frame", &s.rects_per_frame);
+    log_distribution("💾 Bytes/frame", "bytes_per_frame", &s.bytes_per_frame);
+    info!(
+        coverage = s.coverage_ratio,
+        "⬛ Coverage: {:.2}%",
+        s.coverage_ratio * 100.0
+    );
+    if let Some(th) = &s.thresholds {
+        log_distribution("🎚️  Threshold/frame", "threshold", th);
+    }
+
+    Ok(())
+}
+
+fn subs(input: &Path, out: Option<PathBuf>, opts: &SubsOpts) -> Result<()> {
+    let out = out.unwrap_or_else(|| input.with_extension("json"));
+    if out.extension().is_some_and(|x| x.eq_ignore_ascii_case("vtt")) {
+        cues_to_vtt_file(&read_cues_file(input, opts)?, &out)?;
+    } else {
+        subs_to_json_file_with(input, &out, opts)?;
+    }
+    info!(out = %out.display(), "📝 Wrote {}", out.display());
+    Ok(())
+}
+
+fn subs_export(input: &Path, out: Option<PathBuf>) -> Result<()> {
+    let out = out.unwrap_or_else(|| input.with_extension("srt"));
+    let cues = read_cues_json(input)?;
+    if out.extension().is_some_and(|x| x.eq_ignore_ascii_case("vtt")) {
+        cues_to_vtt_file(&cues, &out)?;
+    } else {
+        cues_to_srt_file(&cues, &out)?;
+    }
+    info!(out = %out.display(), cues = cues.len(), "📝 Wrote {}", out.display());
+    Ok(())
+}
+
+/// `a.srt=en` -> (en, a.srt); `transcript_jp.srt` -> (jp, transcript_jp.srt).
+fn parse_bundle_input(s: &str) -> Result<(String, PathBuf), String> {
+    let (path, lang) = match s.rsplit_once('=') {
+        Some((path, lang)) => (PathBuf::from(path), lang.to_string()),
+        None => (PathBuf::from(s), lang_from_path(Path::new(s))),
+    };
+    if lang.is_empty() || path.as_os_str().is_empty() {
+        return Err(format!("expected FILE[=LANG], got {s:?}"));
+    }
+    Ok((lang, path))
+}
+
+fn subs_bundle(inputs: &[(String, PathBuf)], out: &Path, ruby: bool, pretty: bool) -> Result<()> {
+    let opts = SubsOpts {
+        ruby,
+        pretty,
+        ..SubsOpts::default()
+    };
+    let bundle = write_subs_bundle(inputs, out, &opts)?;
+    for (lang, track) in &bundle {
+        info!(lang = %lang, cues = track.cues.len(), "📝 {lang}: {}", track.label);
+    }
+    info!(out = %out.display(), tracks = bundle.len(), "📝 Wrote {}", out.display());
+    Ok(())
+}
+
+fn extract(video: &Path, out: &Path, opts: &ExtractOpts) -> Result<()> {
+    info!(
+        "🎬 Extracting {} at {}fps, {}×{}",
+        video.display(),
+        opts.fps,
+        opts.width,
+        opts.height
+    );
+    let frames = extract_frames(video, out, opts)?;
+    info!(
+        out = %out.display(),
+        frames,
+        "✅ {frames} frames written: {}",
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn audio(video: &Path, out: &Path, ffmpeg: &Path) -> Result<()> {
+    let bytes = extract_audio(video, out, ffmpeg)?;
+    info!(
+        out = %out.display(),
+        bytes,
+        "✅ Audio written: {} ({:.1} KiB)",
+        out.display(),
+        bytes as f64 / 1024.0
+    );
+
+    Ok(())
+}
+
+fn bench(dir: &Path, opts: &BenchOpts) -> Result<()> {
+    let report = bench_encoders(dir, opts)?;
+    info!(
+        width = report.width,
+        height = report.height,
+        from = report.frames.start,
+        to = report.frames.end,
+        total_frames = report.total_frames,
+        "🏁 {}×{}, frames {}..{} of {}",
+        report.width,
+        report.height,
+        report.frames.start,
+        report.frames.end,
+        report.total_frames
+    );
+    info!(
+        "{:<10} {:>6}  {:<10} {:>9} {:>10} {:>9} {:>9} {:>9}",
+        "threshold", "th_mul", "format", "rects/fr", "bytes", "B/frame", "ms/frame", "write ms"
+    );
+    let frames = report.frames.len().max(1) as f64;
+    for row in &report.rows {
+        let format = if row.format.xor {
+            format!("{}+xor", row.format.format)
+        } else {
+            row.format.format.to_string()
+        };
+        info!(
+            threshold = %row.threshold,
+            th_mul = row.th_mul,
+            format = %format,
+            rects_per_frame = row.rects_per_frame,
+            bytes = row.bytes,
+            convert_ms = row.convert_ms,
+            write_ms = row.write_ms,
+            "{:<10} {:>6.2}  {:<10} {:>9.1} {:>10} {:>9.0} {:>9.2} {:>9.1}",
+            row.threshold.to_string(),
+            row.th_mul,
+            format,
+            row.rects_per_frame,
+            row.bytes,
+            row.bytes as f64 / frames,
+            row.convert_ms,
+            row.write_ms
+        );
+    }
+    Ok(())
+}
+
+fn lint(dir: &Path, size: Option<(u32, u32)>) -> Result<()> {
+    let report = lint_frames(dir, size)?;
+
+    info!(
+        dir = %dir.display(),
+        frames = report.frames,
+        "📂 {}: {} frames",
+        dir.display(),
+        report.frames
+    );
+    if let Some((first, last)) = report.range {
+        info!(first, last, "🔢 Numbered {first}-{last}");
+    }
+    if let Some((width, height)) = report.size {
+        info!(width, height, "📐 {width}×{height}");
+    }
+    for issue in &report.issues {
+        error!(issue = %issue, "{issue}");
+    }
+    if !report.issues.is_empty() {
+        anyhow::bail!("{} problem(s) in {}", report.issues.len(), dir.display());
+    }
+
+    info!("✅ No problems found");
+    Ok(())
+}
+
+fn preview(input: &Path, out: &Path, opts: &ContactSheetOpts) -> Result<()> {
+    let payload = Payload::from_file(input)?;
+    let tiles = export_contact_sheet_file(&payload, out, opts)?;
+    let step = match tiles.as_slice() {
+        [a, b, ..] => b - a,
+        _ => 1,
+    };
+    info!(
+        out = %out.display(),
+        tiles = tiles.len(),
+        step,
+        "✅ Contact sheet: {} frames (every {step}) -> {}",
+        tiles.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+fn rescale(input: &Path, width: u32, height: u32, out: Option<PathBuf>) -> Result<()> {
+    let out = out.unwrap_or_else(|| sized_path(input, width, height));
+    let payload = Payload::from_file(input)?;
+    let (from_w, from_h) = (payload.width, payload.height);
+
+    let payload = edit::rescale(payload, width, height);
+    write_payload_json(&payload, &out)?;
+    info!(
+        out = %out.display(),
+        width,
+        height,
+        "✅ {from_w}×{from_h} -> {width}×{height} payload written: {}",
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn trim(input: &Path, from: f64, to: Option<f64>, out: &Path) -> Result<()> {
+    let payload = Payload::from_file(input)?;
+    let total = payload.frames_count;
+
+    let payload = edit::trim(payload, from, to)?;
+    write_payload_json(&payload, out)?;
+    info!(
+        out = %out.display(),
+        frames_count = payload.frames_count,
+        "✅ {} of {} frames ({:.2}s) written: {}",
+        payload.frames_count,
+        total,
+        payload.frames_count as f64 / payload.fps as f64,
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn concat(inputs: &[PathBuf], out: &Path) -> Result<()> {
+    let payloads = inputs
+        .iter()
+        .map(|p| Payload::from_file(p).map_err(anyhow::Error::from))
+        .collect::<Result<Vec<_>>>()?;
+
+    let payload = edit::concat(payloads)?;
+    write_payload_json(&payload, out)?;
+    info!(
+        out = %out.display(),
+        frames_count = payload.frames_count,
+        "✅ {} payloads, {} frames ({:.2}s) written: {}",
+        inputs.len(),
+        payload.frames_count,
+        payload.frames_count as f64 / payload.fps as f64,
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn repack(input: &Path, output: &Path, to: Option<Packing>, crc32: bool) -> Result<()> {
+    let to = match to.or_else(|| Packing::from_path(output)) {
+        Some(to) => to,
+        None => anyhow::bail!(
+            "Can't tell the container from {}, pass --to (json, bin, msgpack, +.gz)",
+            output.display()
+        ),
+    };
+    if crc32 && to.container != Container::Binary {
+        anyhow::bail!("--crc32 only applies to bin payloads, not {to}");
+    }
+    let in_bytes = fs::metadata(input)?.len();
+    let (payload, from) = read_packed(input)?;
+    if to.container == Container::Binary
+        && (payload.frame_thresholds.is_some()
+            || !payload.preprocess.is_empty()
+            || payload.interpolated.is_some())
+    {
+        warn!("bin keeps the rects and basic header only, the optional fields are dropped");
+    }
+
+    let out_bytes = write_packed(&payload, output, to, &BinaryOpts { crc32 })?;
+    info!(
+        out = %output.display(),
+        from = %from,
+        to = %to,
+        in_bytes,
+        out_bytes,
+        "✅ {from} ({:.1} KiB) -> {to} ({:.1} KiB): {}",
+        in_bytes as f64 / 1024.0,
+        out_bytes as f64 / 1024.0,
+        output.display()
+    );
+
+    Ok(())
+}
+
+fn verify(input: &Path, golden: Option<&Path>, opts: &GoldenOpts) -> Result<()> {
+    let bytes = fs::read(input)?;
+    let v = container::verify(&bytes)
+        .map_err(|e| e.context(format!("{} is damaged", input.display())))?;
+    info!(
+        file = %input.display(),
+        packing = %v.packing,
+        frames_count = v.frames_count,
+        crc32 = v.crc32,
+        "✅ {} ({}): {} frames{}",
+        input.display(),
+        v.packing,
+        v.frames_count,
+        if v.crc32 { ", all CRC32s match" } else { "" }
+    );
+
+    let Some(golden) = golden else {
+        return Ok(());
+    };
+    let (reference, _) = read_packed(golden)?;
+    let (payload, _) = read_packed(input)?;
+    let report = compare_golden(&reference, &payload, opts);
+    for failure in &report.failures {
+        error!(golden = %golden.display(), "{failure}");
+    }
+    if !report.passed() {
+        anyhow::bail!(
+            "{} differs from {} ({} problems)",
+            input.display(),
+            golden.display(),
+            report.failures.len()
+        );
+    }
+    let worst = report
+        .worst
+        .map(|w| format!(", worst frame {} at {:.2}%", w.frame, w.accuracy * 100.0))
+        .unwrap_or_default();
+    info!(
+        golden = %golden.display(),
+        changed_frames = report.changed_frames,
+        threshold_drift = report.threshold_drift,
+        rect_drift = report.rect_drift,
+        "✅ Matches {}: {}/{} frames changed{}, threshold drift {:.1}, rects {:+.2}%",
+        golden.display(),
+        report.changed_frames,
+        report.frames,
+        worst,
+        report.threshold_drift,
+        report.rect_drift * 100.0
+    );
+
+    Ok(())
+}
+
+impl ExportTarget {
+    fn default_out(&self) -> &'static str {
+        match self {
+            ExportTarget::CHeader { .. } => "out/bad_apple_frames.h",
+            ExportTarget::RustSource { .. } => "out/bad_apple_frames.rs",
+            ExportTarget::Progmem { .. } => "out/bad_apple_progmem.h",
+            ExportTarget::Svg { .. } => "out/bad_apple.svg",
+            ExportTarget::Html { .. } => "out/bad_apple.html",
+            ExportTarget::Lottie { .. } => "out/bad_apple.lottie.json",
+            ExportTarget::Css { .. } => "out/bad_apple.css.html",
+            ExportTarget::Epaper { .. } => "out/bad_apple.epaper.json",
+            ExportTarget::Minecraft { .. } => "out/bad_apple_datapack",
+            ExportTarget::Spritesheet { .. } => "out/bad_apple_sprites.json",
+            ExportTarget::Pdf { .. } => "out/bad_apple_flipbook.pdf",
+            ExportTarget::Asciicast { .. } => "out/bad_apple.cast",
+            ExportTarget::Desmos { .. } => "out/bad_apple.desmos.json",
+        }
+    }
+}
+
+fn export(input: &Path, out: Option<PathBuf>, target: ExportTarget) -> Result<()> {
+    let out = out.unwrap_or_else(|| PathBuf::from(target.default_out()));
+    let payload = Payload::from_file(input)?;
+
+    match target {
+        ExportTarget::CHeader { name } => {
+            export_c_header_file(&payload, &out, &name)?;
+            info!(out = %out.display(), "✅ C header written: {}", out.display());
+        }
+        ExportTarget::RustSource { bytes_file } => {
+            export_rust_source_file(&payload, &out, bytes_file)?;
+            info!(out = %out.display(), "✅ Rust source written: {}", out.display());
+        }
+        ExportTarget::Progmem {
+            size,
+            display,
+            fit,
+            step,
+            chunk_bytes,
+            name,
+        } => {
+            let (width, height) = match display {
+                Some(d) => display_preset(&d)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown display preset: {d}"))?,
+                None => size,
+            };
+            let opts = ProgmemOpts {
+                width,
+                height,
+                fit,
+                step,
+                chunk_bytes,
+                name: &name,
+            };
+            export_progmem_file(&payload, &out, &opts)?;
+            info!(
+                out = %out.display(),
+                "✅ PROGMEM header written: {} ({}×{})",
+                out.display(),
+                width,
+                height
+            );
+        }
+        ExportTarget::Svg { step, fg, bg } => {
+            let opts = SvgOpts {
+                step,
+                fg: &fg,
+                bg: &bg,
+            };
+            export_svg_file(&payload, &out, &opts)?;
+            info!(out = %out.display(), "✅ SVG written: {}", out.display());
+        }
+        ExportTarget::Html { data, subs, title } => {
+            let tracks = subs
+                .iter()
+                .map(|p| SubtitleTrack::from_file(p))
+                .collect::<Result<Vec<_>>>()?;
+            let opts = HtmlOpts {
+                title: &title,
+                data,
+                tracks: &tracks,
+            };
+            export_html_file(&payload, &out, &opts)?;
+            info!(out = %out.display(), "✅ HTML player written: {}", out.display());
+        }
+        ExportTarget::Lottie { step, fg, bg } => {
+            export_lottie_file(&payload, &out, &LottieOpts { step, fg, bg })?;
+            info!(out = %out.display(), "✅ Lottie written: {}", out.display());
+        }
+        ExportTarget::Css {
+            step,
+            scale,
+            fg,
+            bg,
+        } => {
+            let opts = CssOpts {
+                step,
+                scale,
+                fg: &fg,
+                bg: &bg,
+            };
+            export_css_player_file(&payload, &out, &opts)?;
+            info!(out = %out.display(), "✅ CSS player written: {}", out.display());
+        }
+        ExportTarget::Desmos { step, max_frames } => {
+            export_desmos_file(&payload, &out, &DesmosOpts { step, max_frames })?;
+            info!(
+                out = %out.display(),
+                "✅ Desmos state written: {} — paste into Calc.setState(…) in the browser console",
+                out.display()
+            );
+        }
+        ExportTarget::Asciicast {
+            renderer,
+            cols,
+            rows,
+            invert,
+            title,
+        } => {
+            let opts = AsciicastOpts {
+                renderer,
+                cols,
+                rows,
+                invert,
+                title: &title,
+            };
+            export_asciicast_file(&payload, &out, &opts)?;
+            info!(out = %out.display(), "✅ asciinema cast written: {}", out.display());
+        }
+        ExportTarget::Pdf {
+            step,
+            page,
+            margin,
+            binding,
+            no_numbers,
+        } => {
+            let page_mm = match page_preset(&page) {
+                Some(size) => size,
+                None => {
+                    let (w, h) = parse_size(&page).map_err(|e| anyhow::anyhow!(e))?;
+                    (w as f32, h as f32)
+                }
+            };
+            let opts = PdfOpts {
+                step,
+                page_mm,
+                margin_mm: margin,
+                binding_mm: binding,
+                numbers: !no_numbers,
+            };
+            export_pdf_file(&payload, &out, &opts)?;
+            info!(
+                out = %out.display(),
+                "✅ Flipbook written: {} ({} pages)",
+                out.display(),
+                payload.rect_frames.len().div_ceil(step.max(1))
+            );
+        }
+        ExportTarget::Spritesheet {
+            frame_size,
+            fit,
+            columns,
+            sheet_size,
+            step,
+        } => {
+            let opts = SpritesheetOpts {
+                frame_size,
+                fit,
+                columns,
+                max_sheet: sheet_size,
+                step,
+            };
+            let atlas = export_spritesheet_files(&payload, &out, &opts)?;
+            info!(
+                out = %out.display(),
+                "✅ Spritesheets written: {} ({} frames on {} sheets)",
+                out.display(),
+                atlas.frames.len(),
+                atlas.sheets.len()
+            );
+        }
+        ExportTarget::Minecraft {
+            namespace,
+            origin,
+            downscale,
+            on_block,
+            off_block,
+            pack_format,
+        } => {
+            let opts = MinecraftOpts {
+                namespace: &namespace,
+                origin,
+                downscale,
+                on_block: &on_block,
+                off_block: &off_block,
+                pack_format,
+            };
+            let files = export_datapack_dir(&payload, &out, &opts)?;
+            info!(
+                out = %out.display(),
+                "✅ Datapack written: {} ({} files) — copy it to <world>/datapacks, then /reload and /function {}:play",
+                out.display(),
+                files,
+                namespace
+            );
+        }
+        ExportTarget::Epaper {
+            size,
+            fit,
+            fps,
+            diff,
+        } => {
+            let (width, height) = size.unwrap_or((payload.width, payload.height));
+            let opts = EpaperOpts {
+                width,
+                height,
+                fit,
+                fps,
+                diff_threshold: diff,
+            };
+            let export = export_epaper_file(&payload, &out, &opts)?;
+            info!(
+                out = %out.display(),
+                "✅ E-paper frames written: {} ({} of {} frames kept)",
+                out.display(),
+                export.frames.len(),
+                payload.rect_frames.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn render(
+    input: &Path,
+    format: RenderFormat,
+    frame: usize,
+    out: Option<PathBuf>,
+    opts: RenderVideoOpts,
+) -> Result<()> {
+    let payload = Payload::from_file(input)?;
+    let ext = match format {
+        RenderFormat::Sixel => None,
+        RenderFormat::Mp4 => Some("mp4"),
+        RenderFormat::Webm => Some("webm"),
+    };
+    if let Some(ext) = ext {
+        let out = out.unwrap_or_else(|| PathBuf::from(format!("out/bad_apple.{ext}")));
+        if !out.extension().is_some_and(|x| x.eq_ignore_ascii_case(ext)) {
+            anyhow::bail!("--format {ext} needs a .{ext} output, got {}", out.display());
+        }
+        let bytes = render_video(&payload, &out, &opts)?;
+        info!(
+            out = %out.display(),
+            frames = payload.rect_frames.len(),
+            bytes,
+            "✅ Video written: {} ({} frames, {:.1} MiB)",
+            out.display(),
+            payload.rect_frames.len(),
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+        return Ok(());
+    }
+    if opts.audio.is_some() {
+        anyhow::bail!("--audio only applies to video formats");
+    }
+    if frame >= payload.rect_frames.len() {
+        anyhow::bail!(
+            "Frame {} out of range (payload has {})",
+            frame,
+            payload.rect_frames.len()
+        );
+    }
+
+    let (w, h) = (payload.width as usize, payload.height as usize);
+    let scale = opts.scale.max(1) as usize;
+    let mut px = resample(
+        &payload.rasterize_frame(frame),
+        w,
+        h,
+        w * scale,
+        h * scale,
+        Fit::Stretch,
+    );
+    if opts.invert {
+        px.iter_mut().for_each(|v| *v ^= 1);
+    }
+
+    let bytes = match format {
+        RenderFormat::Sixel => sixel_encode(&px, w * scale, h * scale).into_bytes(),
+        RenderFormat::Mp4 | RenderFormat::Webm => unreachable!("rendered as video above"),
+    };
+
+    match out {
+        Some(path) => {
+            fs::write(&path, bytes)?;
+            info!(
+                out = %path.display(),
+                frame,
+                "✅ Frame {} rendered: {}",
+                frame,
+                path.display()
+            );
+        }
+        None => {
+            let mut stdout = io::stdout();
+            stdout.write_all(&bytes)?;
+            stdout.write_all(b"\n")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn stream(input: &Path, mut opts: LedOpts, target: StreamTarget) -> Result<()> {
+    let payload = Payload::from_file(input)?;
+
+    match target {
+        StreamTarget::Wled { host, serpentine } => {
+            opts.serpentine = serpentine;
+            let mut sender = DdpSender::connect(&host)?;
+            info!(
+                "💡 Streaming {} to {} ({}×{} LEDs, {}fps)",
+                input.display(),
+                host,
+                opts.width,
+                opts.height,
+                payload.fps
+            );
+            led::play(&payload, &opts, &mut sender)?;
+        }
+        #[cfg(feature = "rpi-matrix")]
+        StreamTarget::RgbMatrix {
+            rows,
+            cols,
+            chain,
+            parallel,
+            hardware_mapping,
+            gpio_slowdown,
+        } => {
+            let matrix_opts = RgbMatrixOpts {
+                rows,
+                cols,
+                chain,
+                parallel,
+                hardware_mapping: &hardware_mapping,
+                gpio_slowdown,
+            };
+            (opts.width, opts.height) = matrix_opts.size();
+            let mut matrix = RgbMatrix::open(&matrix_opts)?;
+            info!(
+                "💡 Playing {} on {}×{} HUB75 panels ({}fps)",
+                input.display(),
+                opts.width,
+                opts.height,
+                payload.fps
+            );
+            led::play(&payload, &opts, &mut matrix)?;
+        }
+    }
+
+    info!("✅ Done");
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli: Cli = parse_with_config(&[
+        (Some("convert"), Settings::Convert),
+        (Some("serve"), Settings::Serve),
+    ])?;
+    match &cli.command {
+        // fetch prints the path on stdout for scripts, its logs go to stderr
+        #[cfg(feature = "http")]
+        Command::Fetch { .. } => init_console_stderr(),
+        Command::Convert(args) => cli.log.init(args.writes_stdout()),
+        #[cfg(feature = "server")]
+        Command::Serve(args) => args.log.init(false),
+        _ => cli.log.init(false),
+    }
+
+    match cli.command {
+        Command::Convert(args) => convert::run(args, &cli.log),
+        Command::Batch(args) => batch::run(args, &cli.log),
+        Command::Subs {
+            action: Some(SubsAction::Export { input, out }),
+            ..
+        } => subs_export(&input, out),
+        Command::Subs {
+            action:
+                Some(SubsAction::Bundle {
+                    inputs,
+                    ruby,
+                    out,
+                    pretty,
+                }),
+            ..
+        } => subs_bundle(&inputs, &out, ruby, pretty),
+        Command::Subs {
+            action: None,
+            input,
+            format,
+            offset,
+            scale,
+            overlaps,
+            max_cue_secs,
+            max_cue_chars,
+            encoding,
+            tags,
+            keep_karaoke,
+            ruby,
+            out,
+            pretty,
+        } => subs(
+            &input.expect("required unless exporting"),
+            out,
+            &SubsOpts {
+                format,
+                keep_karaoke,
+                pretty,
+                encoding,
+                tags,
+                offset,
+                scale,
+                overlaps,
+                max_cue_secs,
+                max_cue_chars,
+                ruby,
+            },
+        ),
+        #[cfg(feature = "server")]
+        Command::Serve(args) => Ok(serve::run(args)?),
+        Command::Play(args) => play::run(args),
+        Command::Tune(args) => tune::run(args),
+        Command::Stats { file } => stats(&file),
+        Command::Extract {
+            video,
+            fps,
+            size: (width, height),
+            out,
+            no_sharpen,
+            force,
+            ffmpeg,
+        } => {
+            let opts = ExtractOpts {
+                fps,
+                width,
+                height,
+                sharpen: !no_sharpen,
+                force,
+                ffmpeg,
+            };
+            extract(&video, &out, &opts)
+        }
+        Command::Audio { video, out, ffmpeg } => audio(&video, &out, &ffmpeg),
+        Command::Bench {
+            dir,
+            samples,
+            from,
+            th_mul,
+            threshold,
+            invert,
+        } => {
+            let opts = BenchOpts {
+                samples,
+                from,
+                th_muls: th_mul,
+                thresholds: threshold,
+                invert: invert == 1,
+                ..BenchOpts::default()
+            };
+            bench(&dir, &opts)
+        }
+        Command::LintFrames { dir, size } => lint(&dir, size),
+        Command::Export { input, out, target } => export(&input, out, target),
+        Command::Preview {
+            input,
+            step,
+            tiles,
+            columns,
+            tile_width,
+            out,
+        } => {
+            let opts = ContactSheetOpts {
+                step,
+                tiles,
+                columns,
+                tile_width,
+                ..ContactSheetOpts::default()
+            };
+            preview(&input, &out, &opts)
+        }
+        Command::Rescale {
+            input,
+            size: (width, height),
+            out,
+        } => rescale(&input, width, height, out),
+        Command::Trim {
+            input,
+            from,
+            to,
+            out,
+        } => trim(&input, from, to, &out),
+        Command::Concat { inputs, out } => concat(&inputs, &out),
+        Command::Repack {
+            input,
+            output,
+            to,
+            crc32,
+        } => repack(&input, &output, to, crc32),
+        Command::Verify {
+            input,
+            golden,
+            max_threshold_drift,
+            min_accuracy,
+            max_rect_drift,
+        } => verify(
+            &input,
+            golden.as_deref(),
+            &GoldenOpts {
+                max_threshold_drift,
+                min_accuracy,
+                max_rect_drift,
+            },
+        ),
+        Command::Render {
+            input,
+            format,
+            frame,
+            scale,
+            invert,
+            out,
+            audio,
+            ffmpeg,
+        } => render(
+            &input,
+            format,
+            frame,
+            out,
+            RenderVideoOpts {
+                scale,
+                invert,
+                audio,
+                ffmpeg,
+            },
+        ),
+        Command::Stream {
+            input,
+            size: (width, height),
+            fit,
+            brightness,
+            gamma,
+            invert,
+            looping,
+            target,
+        } => {
+            let opts = LedOpts {
+                width,
+                height,
+                fit,
+                serpentine: false,
+                brightness,
+                gamma,
+                invert,
+                looping,
+            };
+            stream(&input, opts, target)
+        }
+        Command::Schema { kind, out } => schema(kind, out),
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "bad-apple", &mut io::stdout());
+            Ok(())
+        }
+        Command::Man { out } => man(out),
+        #[cfg(feature = "http")]
+        Command::Fetch {
+            url,
+            cache_dir,
+            retries,
+        } => {
+            let path = bad_apple::remote::RemoteSource::new(&url, &cache_dir)
+                .retries(retries)
+                .fetch()?;
+            println!("{}", path.display());
+            Ok(())
+        }
+    }
+}
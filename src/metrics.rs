@@ -0,0 +1,119 @@
+// src/lib/metrics.rs
+// Compares encoded rect frames against the binarized source frames.
+//
+// Every frame is re-decoded with the same settings used for conversion,
+// rasterized from its rects, and scored by:
+//   - pixel accuracy: matching pixels / total pixels
+//   - IoU: |on ∩ on'| / |on ∪ on'| (1.0 when both frames are empty)
+
+use anyhow::Result;
+
+use crate::rectframes::{binarize_png, ConvertRectframesOpts, FrameParams, Payload};
+use crate::source::PngFrames;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FrameQuality {
+    pub frame: usize,
+    pub accuracy: f64,
+    pub iou: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QualityReport {
+    pub frames: Vec<FrameQuality>,
+    pub avg_accuracy: f64,
+    pub avg_iou: f64,
+    pub worst_accuracy: FrameQuality,
+    pub worst_iou: FrameQuality,
+}
+
+/// Scores one rasterized frame against its reference (both 1/0 buffers of equal length).
+pub fn compare_frames(expected: &[u8], actual: &[u8]) -> (f64, f64) {
+    let mut matching = 0usize;
+    let mut inter = 0usize;
+    let mut union = 0usize;
+
+    for (&a, &b) in expected.iter().zip(actual) {
+        let (a, b) = (a != 0, b != 0);
+        if a == b {
+            matching += 1;
+        }
+        if a && b {
+            inter += 1;
+        }
+        if a || b {
+            union += 1;
+        }
+    }
+
+    let accuracy = if expected.is_empty() {
+        1.0
+    } else {
+        matching as f64 / expected.len() as f64
+    };
+    let iou = if union == 0 {
+        1.0
+    } else {
+        inter as f64 / union as f64
+    };
+
+    (accuracy, iou)
+}
+
+pub fn evaluate_quality(
+    payload: &Payload,
+    opts: &ConvertRectframesOpts<'_>,
+) -> Result<QualityReport> {
+    let files = PngFrames::open(opts.in_dir)?;
+    // Interpolated payloads are scored on the frames that have a source
+    let step = if payload.interpolated.is_some() { 2 } else { 1 };
+
+    if files.len() * step != payload.frames_count {
+        anyhow::bail!(
+            "Frame count mismatch: payload has {}, {} has {}",
+            payload.frames_count,
+            opts.in_dir.display(),
+            files.len()
+        );
+    }
+
+    let mut frames = Vec::with_capacity(payload.frames_count);
+    // Compared against the plain binarization, so temporal smoothing counts as error
+    let params = FrameParams {
+        temporal: None,
+        ..opts.prepare()?
+    };
+
+    for (i, png) in files.enumerate() {
+        let (fp, bytes) = png?;
+        let (expected, _) = binarize_png(&fp, &bytes, &params)?;
+        let actual = payload.rasterize_frame(i * step);
+        let (accuracy, iou) = compare_frames(&expected, &actual);
+        frames.push(FrameQuality {
+            frame: i,
+            accuracy,
+            iou,
+        });
+    }
+
+    let n = frames.len() as f64;
+    let avg_accuracy = frames.iter().map(|f| f.accuracy).sum::<f64>() / n;
+    let avg_iou = frames.iter().map(|f| f.iou).sum::<f64>() / n;
+
+    let worst_accuracy = *frames
+        .iter()
+        .min_by(|a, b| a.accuracy.total_cmp(&b.accuracy))
+        .expect("at least one frame");
+    let worst_iou = *frames
+        .iter()
+        .min_by(|a, b| a.iou.total_cmp(&b.iou))
+        .expect("at least one frame");
+
+    Ok(QualityReport {
+        frames,
+        avg_accuracy,
+        avg_iou,
+        worst_accuracy,
+        worst_iou,
+    })
+}
@@ -0,0 +1,214 @@
+// src/lib/node.rs
+// Node.js addon (feature "node", napi-rs) so JS build pipelines can call the
+// converter and SRT parser directly instead of shelling out to the binaries:
+//
+//   const ba = require("./bad-apple.node");
+//   const p = await ba.convertRectframes({ inDir: "frames", w: 256, h: 192 });
+//   p.save("out/rectFrames.json");
+//   const cues = ba.parseSrtToCues(fs.readFileSync("lyrics/transcript_en.srt", "utf8"));
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::{fs, path::PathBuf};
+
+use crate::binary::{decode_payload, MAGIC};
+use crate::error::BadAppleError;
+use crate::rectframes::{self, ConvertRectframesOpts, Payload, Rect};
+use crate::subs;
+
+/// Options for `convertRectframes`, unset fields take the `ConvertRectframesOpts::builder` defaults.
+#[napi(object)]
+pub struct ConvertOptions {
+    pub in_dir: String,
+    pub w: Option<u32>,
+    pub h: Option<u32>,
+    pub fps: Option<u32>,
+    pub invert: Option<bool>,
+    pub th_mul: Option<f64>,
+}
+
+#[napi(object, js_name = "Cue")]
+pub struct JsCue {
+    /// Start, seconds
+    pub s: f64,
+    /// End, seconds
+    pub e: f64,
+    pub t: String,
+}
+
+#[napi(object, js_name = "Rect")]
+pub struct JsRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    pub v: u8,
+}
+
+#[napi(js_name = "Payload")]
+pub struct JsPayload(Payload);
+
+#[napi]
+impl JsPayload {
+    #[napi(getter)]
+    pub fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    #[napi(getter)]
+    pub fn height(&self) -> u32 {
+        self.0.height
+    }
+
+    #[napi(getter)]
+    pub fn fps(&self) -> u32 {
+        self.0.fps
+    }
+
+    #[napi(getter)]
+    pub fn threshold(&self) -> u32 {
+        self.0.threshold
+    }
+
+    #[napi(getter)]
+    pub fn invert(&self) -> bool {
+        self.0.invert
+    }
+
+    #[napi(getter)]
+    pub fn frames_count(&self) -> u32 {
+        self.0.rect_frames.len() as u32
+    }
+
+    /// Per-frame thresholds, or undefined unless converted with `--frame-stats`.
+    #[napi(getter)]
+    pub fn frame_thresholds(&self) -> Option<Vec<f64>> {
+        let ths = self.0.frame_thresholds.as_ref()?;
+        Some(ths.iter().map(|&th| th as f64).collect())
+    }
+
+    #[napi(getter)]
+    pub fn frame_rect_counts(&self) -> Option<Vec<u32>> {
+        let counts = self.0.frame_rect_counts.as_ref()?;
+        Some(counts.iter().map(|&n| n as u32).collect())
+    }
+
+    /// Rects of frame `i`.
+    #[napi]
+    pub fn rects(&self, i: u32) -> Result<Vec<JsRect>> {
+        let rects = self.frame(i)?;
+        Ok(rects
+            .iter()
+            .map(|r| JsRect {
+                x: r.x,
+                y: r.y,
+                w: r.w,
+                h: r.h,
+                v: r.v,
+            })
+            .collect())
+    }
+
+    /// Frame `i` as `width * height` bytes, 1 = black/on.
+    #[napi]
+    pub fn rasterize(&self, i: u32) -> Result<Buffer> {
+        self.frame(i)?;
+        Ok(self.0.rasterize_frame(i as usize).into())
+    }
+
+    /// The payload as rectFrames.json text (same as the converter writes).
+    #[napi]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.0).map_err(anyhow::Error::from)?)
+    }
+
+    #[napi]
+    pub fn save(&self, path: String) -> Result<()> {
+        Ok(rectframes::write_payload_json(&self.0, PathBuf::from(path).as_path())?)
+    }
+}
+
+impl JsPayload {
+    fn frame(&self, i: u32) -> Result<&[Rect]> {
+        self.0
+            .rect_frames
+            .get(i as usize)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::new(Status::InvalidArg, format!("frame {i} out of range")))
+    }
+}
+
+impl From<BadAppleError> for Error {
+    fn from(e: BadAppleError) -> Self {
+        let status = match e {
+            BadAppleError::InvalidOpts(_) => Status::InvalidArg,
+            _ => Status::GenericFailure,
+        };
+        Error::new(status, format!("{:#}", anyhow::Error::from(e)))
+    }
+}
+
+pub struct ConvertTask(ConvertOptions);
+
+impl Task for ConvertTask {
+    type Output = Payload;
+    type JsValue = JsPayload;
+
+    fn compute(&mut self) -> Result<Payload> {
+        let o = &self.0;
+        let in_dir = PathBuf::from(&o.in_dir);
+        let mut builder = ConvertRectframesOpts::builder(&in_dir);
+        if let Some(w) = o.w {
+            builder = builder.w(w);
+        }
+        if let Some(h) = o.h {
+            builder = builder.h(h);
+        }
+        if let Some(fps) = o.fps {
+            builder = builder.fps(fps);
+        }
+        if let Some(invert) = o.invert {
+            builder = builder.invert(invert);
+        }
+        if let Some(th_mul) = o.th_mul {
+            builder = builder.th_mul(th_mul as f32);
+        }
+        let opts = builder.build()?;
+        Ok(rectframes::convert_rectframes(opts)?)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Payload) -> Result<JsPayload> {
+        Ok(JsPayload(output))
+    }
+}
+
+/// Converts a directory of PNG frames on the libuv thread pool, resolving to a Payload.
+#[napi(ts_return_type = "Promise<Payload>")]
+pub fn convert_rectframes(opts: ConvertOptions) -> AsyncTask<ConvertTask> {
+    AsyncTask::new(ConvertTask(opts))
+}
+
+/// Loads a rectFrames.json or binary ("BAPL") payload.
+#[napi]
+pub fn load_payload(path: String) -> Result<JsPayload> {
+    let path = PathBuf::from(path);
+    let bytes = fs::read(&path).map_err(anyhow::Error::from)?;
+    let payload = if bytes.starts_with(MAGIC) {
+        decode_payload(&bytes)?
+    } else {
+        Payload::from_file(&path)?
+    };
+    Ok(JsPayload(payload))
+}
+
+#[napi]
+pub fn parse_srt_to_cues(srt_text: String) -> Vec<JsCue> {
+    subs::parse_srt_to_cues(&srt_text)
+        .into_iter()
+        .map(|c| JsCue {
+            s: c.s as f64,
+            e: c.e as f64,
+            t: c.t,
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+// src/lib/pipeline.rs
+// Two-stage worker pipeline for conversions: a decoder pool (PNG decode +
+// binarize) feeds an encoder pool (rect merging) through bounded channels, so
+// both stages run at once on different cores and at most a few frames per
+// worker are in flight. Results are handed to the sink in input order.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::cancel::CancelToken;
+use crate::error::Result;
+
+/// Frames buffered per worker between stages.
+const QUEUE_PER_WORKER: usize = 2;
+
+/// Worker count for `threads == 0`: one per core.
+pub(crate) fn auto_threads(threads: usize) -> usize {
+    match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Splits `threads` between the stages; decoding is the heavier one.
+fn split(threads: usize) -> (usize, usize) {
+    let encoders = (threads / 3).max(1);
+    let decoders = threads.saturating_sub(encoders).max(1);
+    (decoders, encoders)
+}
+
+/// Runs `decode` then `encode` over `items` on `threads` workers (plus the
+/// reader and a collector), calling `sink` with each result in input order. The first error
+/// (or a cancellation) stops every stage and is returned.
+pub(crate) fn run<T, U, V, I, D, E, S>(
+    items: I,
+    threads: usize,
+    cancel: Option<&CancelToken>,
+    decode: D,
+    encode: E,
+    mut sink: S,
+) -> Result<()>
+where
+    T: Send,
+    U: Send,
+    V: Send,
+    I: Iterator<Item = Result<T>>,
+    D: Fn(T) -> Result<U> + Sync,
+    E: Fn(U) -> V + Sync,
+    S: FnMut(V) + Send,
+{
+    let threads = threads.max(2);
+    let (decoders, encoders) = split(threads);
+    let (job_tx, job_rx) = mpsc::sync_channel::<(usize, T)>(decoders * QUEUE_PER_WORKER);
+    let (mid_tx, mid_rx) = mpsc::sync_channel::<(usize, U)>(encoders * QUEUE_PER_WORKER);
+    let (out_tx, out_rx) = mpsc::sync_channel::<(usize, Result<V>)>(threads * QUEUE_PER_WORKER);
+    let (permit_tx, permit_rx) = mpsc::sync_channel::<()>(threads * QUEUE_PER_WORKER * 2);
+    // Each pool owns its receiver, so it closes (failing upstream sends) as
+    // soon as the pool's last worker exits
+    let (job_rx, mid_rx) = (Arc::new(Mutex::new(job_rx)), Arc::new(Mutex::new(mid_rx)));
+
+    thread::scope(|scope| {
+        for _ in 0..decoders {
+            let (jobs, next, results) = (job_rx.clone(), mid_tx.clone(), out_tx.clone());
+            let decode = &decode;
+            scope.spawn(move || {
+                while let Some((i, item)) = recv(&jobs) {
+                    let sent = match decode(item) {
+                        Ok(u) => next.send((i, u)).is_ok(),
+                        Err(e) => results.send((i, Err(e))).is_ok(),
+                    };
+                    if !sent {
+                        break;
+                    }
+                }
+            });
+        }
+        drop((job_rx, mid_tx));
+
+        for _ in 0..encoders {
+            let (frames, results) = (mid_rx.clone(), out_tx.clone());
+            let encode = &encode;
+            scope.spawn(move || {
+                while let Some((i, u)) = recv(&frames) {
+                    if results.send((i, Ok(encode(u)))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(mid_rx);
+
+        let collector = scope.spawn(move || collect(out_rx, permit_rx, &mut sink));
+        // Reading stays on the calling thread, so `items` needn't be Send (stdin locks aren't)
+        read(items, cancel, permit_tx, job_tx, out_tx);
+        collector.join().expect("pipeline collector panicked")
+    })
+}
+
+/// Feeds the decoders; each frame first takes a permit, which the collector
+/// hands back once it passed the frame on, bounding the frames in flight
+/// (including ones waiting for reordering).
+fn read<T, V, I>(
+    items: I,
+    cancel: Option<&CancelToken>,
+    permits: SyncSender<()>,
+    jobs: SyncSender<(usize, T)>,
+    results: SyncSender<(usize, Result<V>)>,
+) where
+    I: Iterator<Item = Result<T>>,
+{
+    for (i, item) in items.enumerate() {
+        if permits.send(()).is_err() {
+            break;
+        }
+        let item = cancel.map_or(Ok(()), CancelToken::check).and(item);
+        let sent = match item {
+            Ok(t) => jobs.send((i, t)).is_ok(),
+            Err(e) => {
+                let _ = results.send((i, Err(e)));
+                false
+            }
+        };
+        if !sent {
+            break;
+        }
+    }
+}
+
+/// Next item from a receiver shared by a pool; None once the sender is gone.
+fn recv<T>(rx: &Mutex<Receiver<T>>) -> Option<T> {
+    rx.lock().ok()?.recv().ok()
+}
+
+/// Reorders results by index; returns the first error, dropping the receivers
+/// so the other stages' sends fail and every thread winds down.
+fn collect<V, S: FnMut(V)>(
+    results: Receiver<(usize, Result<V>)>,
+    permits: Receiver<()>,
+    sink: &mut S,
+) -> Result<()> {
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+
+    for (i, result) in results {
+        pending.insert(i, result?);
+        while let Some(v) = pending.remove(&next) {
+            sink(v);
+            next += 1;
+            let _ = permits.try_recv();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+// src/lib/playback.rs
+// Wall-clock playback position shared by the players.
+//
+// The clock tracks time, not frames: the current frame is derived from the
+// position, so a slow renderer drops frames instead of drifting. With an
+// audio track attached (feature "audio") the audio position is the master
+// clock and frames are dropped/repeated to stay locked to it. Audio only
+// plays at 1× (no pitch-shifted chipmunk Bad Apple); at other speeds it is
+// paused and re-seeked to the video position when going back to 1×.
+
+use std::time::Instant;
+
+#[cfg(feature = "audio")]
+use crate::audio::AudioTrack;
+
+/// Playback speeds the players cycle through.
+pub const SPEEDS: [f64; 3] = [0.5, 1.0, 2.0];
+
+/// Formats seconds as `mm:ss.s`.
+pub fn format_time(secs: f64) -> String {
+    let secs = secs.max(0.0);
+    format!("{:02}:{:04.1}", (secs / 60.0) as u64, secs % 60.0)
+}
+
+#[derive(Debug)]
+pub struct PlaybackClock {
+    fps: f64,
+    frames: usize,
+    position: f64,
+    playing: bool,
+    speed: f64,
+    looping: bool,
+    last_tick: Instant,
+    #[cfg(feature = "audio")]
+    audio: Option<AudioTrack>,
+}
+
+impl PlaybackClock {
+    pub fn new(fps: u32, frames: usize) -> Self {
+        Self {
+            fps: fps.max(1) as f64,
+            frames,
+            position: 0.0,
+            playing: true,
+            speed: 1.0,
+            looping: false,
+            last_tick: Instant::now(),
+            #[cfg(feature = "audio")]
+            audio: None,
+        }
+    }
+
+    /// Slaves the clock to an already playing audio track (position restarts at 0).
+    #[cfg(feature = "audio")]
+    pub fn with_audio(mut self, track: AudioTrack) -> Self {
+        self.position = 0.0;
+        self.last_tick = Instant::now();
+        self.audio = Some(track);
+        self
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frames as f64 / self.fps
+    }
+
+    /// Advances the position by the wall time since the last tick (if playing),
+    /// wrapping around at the end when looping.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        if self.playing {
+            self.position += (now - self.last_tick).as_secs_f64() * self.speed;
+        }
+        self.last_tick = now;
+
+        #[cfg(feature = "audio")]
+        if self.audio_active()
+            && let Some(pos) = self.audio.as_ref().and_then(|a| a.position())
+        {
+            self.position = pos;
+        }
+
+        let duration = self.duration();
+        if self.looping && duration > 0.0 && self.position >= duration {
+            self.position %= duration;
+            self.sync_audio(true);
+        }
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.duration()
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.tick();
+        let was_active = self.audio_active();
+        self.speed = speed.max(0.01);
+        // Re-seek when audio resumes so it picks up where the video got to
+        self.sync_audio(!was_active);
+    }
+
+    /// Moves to the previous/next entry of `SPEEDS` (`dir` < 0 is slower).
+    pub fn cycle_speed(&mut self, dir: i32) {
+        let i = SPEEDS
+            .iter()
+            .position(|&s| s >= self.speed)
+            .unwrap_or(SPEEDS.len() - 1) as i32;
+        let next = (i + dir.signum()).clamp(0, SPEEDS.len() as i32 - 1);
+        self.set_speed(SPEEDS[next as usize]);
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn toggle_loop(&mut self) {
+        self.looping = !self.looping;
+    }
+
+    /// Current frame index, clamped to the last frame.
+    pub fn frame(&self) -> usize {
+        ((self.position * self.fps) as usize).min(self.frames.saturating_sub(1))
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.tick();
+        self.set_playing(!self.playing);
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+        self.sync_audio(false);
+    }
+
+    /// Whether the audio track (if any) should currently be audible.
+    fn audio_active(&self) -> bool {
+        self.playing && self.speed == 1.0
+    }
+
+    /// Plays or pauses the audio to match the clock, optionally seeking it to `position`.
+    #[cfg_attr(not(feature = "audio"), allow(unused_variables))]
+    fn sync_audio(&self, seek: bool) {
+        #[cfg(feature = "audio")]
+        if let Some(audio) = &self.audio {
+            if seek {
+                audio.seek(self.position);
+            }
+            if self.audio_active() {
+                audio.play();
+            } else {
+                audio.pause();
+            }
+        }
+    }
+
+    pub fn seek_to(&mut self, secs: f64) {
+        self.tick();
+        self.position = secs.clamp(0.0, self.duration());
+        self.sync_audio(true);
+    }
+
+    pub fn seek_by(&mut self, secs: f64) {
+        self.seek_to(self.position + secs);
+    }
+
+    /// Pauses and moves `n` frames forward/backward, landing on the frame start.
+    pub fn step(&mut self, n: i64) {
+        self.tick();
+        self.set_playing(false);
+        let target = (self.frame() as i64 + n).clamp(0, self.frames.saturating_sub(1) as i64);
+        self.seek_to(target as f64 / self.fps);
+    }
+}
@@ -0,0 +1,284 @@
+// src/lib/preprocess.rs
+// Luma adjustments applied before thresholding, so low-contrast or badly
+// exposed sources don't need an extra ffmpeg filter pass. Each step is listed
+// in `Payload::preprocess` to keep the payload reproducible.
+
+use std::{fmt, str::FromStr};
+
+/// Exposure tweaks, same meaning as ffmpeg's `eq` filter: contrast scales
+/// around mid-gray, brightness is added (-1..1 of the full range), then gamma
+/// brightens (> 1) or darkens (< 1) the midtones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjust {
+    pub gamma: f32,
+    pub brightness: f32,
+    pub contrast: f32,
+}
+
+impl Default for Adjust {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            brightness: 0.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl Adjust {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if !(self.gamma.is_finite() && self.gamma > 0.0) {
+            return Err(format!(
+                "gamma must be a positive number, got {}",
+                self.gamma
+            ));
+        }
+        if !(-1.0..=1.0).contains(&self.brightness) {
+            return Err(format!(
+                "brightness must be within -1..1, got {}",
+                self.brightness
+            ));
+        }
+        if !(self.contrast.is_finite() && self.contrast >= 0.0) {
+            return Err(format!(
+                "contrast must be a non-negative number, got {}",
+                self.contrast
+            ));
+        }
+        Ok(())
+    }
+
+    /// Maps every luma value once, so per pixel it is a table lookup.
+    pub fn lut(&self) -> [u8; 256] {
+        std::array::from_fn(|v| {
+            let x = (v as f32 / 255.0 - 0.5) * self.contrast + 0.5 + self.brightness;
+            let x = x.clamp(0.0, 1.0).powf(1.0 / self.gamma);
+            (x * 255.0).round() as u8
+        })
+    }
+
+    pub fn apply(&self, buf: &[u8]) -> Vec<u8> {
+        let lut = self.lut();
+        buf.iter().map(|&v| lut[v as usize]).collect()
+    }
+
+    /// Non-default settings for `Payload::preprocess`.
+    pub(crate) fn steps(&self) -> Vec<String> {
+        let d = Self::default();
+        [
+            ("contrast", self.contrast, d.contrast),
+            ("brightness", self.brightness, d.brightness),
+            ("gamma", self.gamma, d.gamma),
+        ]
+        .into_iter()
+        .filter(|(_, v, default)| v != default)
+        .map(|(name, v, _)| format!("{name}:{v}"))
+        .collect()
+    }
+}
+
+/// Histogram equalization before thresholding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Equalize {
+    /// One histogram for the whole frame
+    Global,
+    /// Contrast-limited adaptive equalization over a `tiles`×`tiles` grid,
+    /// histogram bins clipped at `clip` times their average height
+    Clahe { tiles: u32, clip: f32 },
+}
+
+impl Equalize {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if let Equalize::Clahe { tiles, clip } = *self
+            && (tiles == 0 || !(clip.is_finite() && clip >= 1.0))
+        {
+            return Err(format!("CLAHE needs tiles >= 1 and clip >= 1, got {self}"));
+        }
+        Ok(())
+    }
+
+    /// Equalizes a `w`-wide luma buffer.
+    pub fn apply(&self, buf: &[u8], w: usize) -> Vec<u8> {
+        match *self {
+            Equalize::Global => {
+                let lut = equalize_lut(&histogram(buf.iter().copied()), buf.len(), None);
+                buf.iter().map(|&v| lut[v as usize]).collect()
+            }
+            Equalize::Clahe { tiles, clip } => clahe(buf, w, tiles as usize, clip),
+        }
+    }
+}
+
+impl fmt::Display for Equalize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Equalize::Global => write!(f, "global"),
+            Equalize::Clahe { tiles, clip } => write!(f, "clahe:{tiles},{clip}"),
+        }
+    }
+}
+
+/// Parses `global`, `clahe` (8×8 tiles, clip 2) or `clahe:TILES[,CLIP]`.
+impl FromStr for Equalize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut tiles, mut clip) = (8, 2.0);
+        let eq = match s.split_once(':') {
+            None if s == "global" => Equalize::Global,
+            None if s == "clahe" => Equalize::Clahe { tiles, clip },
+            Some(("clahe", args)) => {
+                let (t, c) = args.split_once(',').unwrap_or((args, ""));
+                tiles = t
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("bad CLAHE tile count {t:?}: {e}"))?;
+                if !c.is_empty() {
+                    clip = c
+                        .trim()
+                        .parse()
+                        .map_err(|e| format!("bad CLAHE clip limit {c:?}: {e}"))?;
+                }
+                Equalize::Clahe { tiles, clip }
+            }
+            _ => {
+                return Err(format!(
+                    "expected global or clahe[:TILES[,CLIP]], got {s:?}"
+                ))
+            }
+        };
+        eq.validate()?;
+        Ok(eq)
+    }
+}
+
+fn histogram(values: impl Iterator<Item = u8>) -> [u32; 256] {
+    let mut hist = [0u32; 256];
+    for v in values {
+        hist[v as usize] += 1;
+    }
+    hist
+}
+
+/// Cumulative-histogram mapping for `n` pixels. With `clip`, bins above the
+/// limit are cut and the excess spread evenly over all bins (CLAHE).
+fn equalize_lut(hist: &[u32; 256], n: usize, clip: Option<f32>) -> [u8; 256] {
+    let mut hist = *hist;
+    if let Some(clip) = clip {
+        let limit = ((clip * n as f32 / 256.0) as u32).max(1);
+        let excess: u32 = hist.iter().map(|&c| c.saturating_sub(limit)).sum();
+        let (share, rest) = (excess / 256, (excess % 256) as usize);
+        for (i, c) in hist.iter_mut().enumerate() {
+            *c = (*c).min(limit) + share + (i < rest) as u32;
+        }
+    }
+
+    let mut lut = [0u8; 256];
+    let mut cdf = 0u64;
+    // Global equalization stretches from the darkest present level; CLAHE
+    // maps straight from the CDF so flat tiles don't blow up to full range
+    let first = match clip {
+        None => hist.iter().find(|&&c| c > 0).copied().unwrap_or(0) as u64,
+        Some(_) => 0,
+    };
+    let span = (n as u64).saturating_sub(first).max(1);
+    for (v, &c) in lut.iter_mut().zip(&hist) {
+        cdf += c as u64;
+        *v = ((cdf.saturating_sub(first) * 255 + span / 2) / span).min(255) as u8;
+    }
+    lut
+}
+
+/// CLAHE: one clipped LUT per tile, each pixel mapped through the four
+/// nearest tiles' LUTs and blended bilinearly to hide tile seams.
+fn clahe(buf: &[u8], w: usize, tiles: usize, clip: f32) -> Vec<u8> {
+    let h = buf.len().checked_div(w).unwrap_or(0);
+    if h == 0 {
+        return buf.to_vec();
+    }
+    let (tx, ty) = (tiles.min(w), tiles.min(h));
+    let (tw, th) = (w.div_ceil(tx), h.div_ceil(ty));
+    let (tx, ty) = (w.div_ceil(tw), h.div_ceil(th));
+
+    let mut luts = Vec::with_capacity(tx * ty);
+    for t in 0..tx * ty {
+        let (x0, y0) = ((t % tx) * tw, (t / tx) * th);
+        let (x1, y1) = ((x0 + tw).min(w), (y0 + th).min(h));
+        let values = (y0..y1).flat_map(|y| buf[y * w + x0..y * w + x1].iter().copied());
+        let n = (x1 - x0) * (y1 - y0);
+        luts.push(equalize_lut(&histogram(values), n, Some(clip)));
+    }
+
+    let grid = |p: usize, size: usize, n: usize| {
+        let g = ((p as f32 + 0.5) / size as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+        let i = g as usize;
+        (i, (i + 1).min(n - 1), g - i as f32)
+    };
+    let cols: Vec<_> = (0..w).map(|x| grid(x, tw, tx)).collect();
+
+    let mut out = Vec::with_capacity(buf.len());
+    for y in 0..h {
+        let (r0, r1, fy) = grid(y, th, ty);
+        for (x, &(c0, c1, fx)) in cols.iter().enumerate() {
+            let v = buf[y * w + x] as usize;
+            let at = |r: usize, c: usize| luts[r * tx + c][v] as f32;
+            let top = at(r0, c0) * (1.0 - fx) + at(r0, c1) * fx;
+            let bottom = at(r1, c0) * (1.0 - fx) + at(r1, c1) * fx;
+            out.push((top * (1.0 - fy) + bottom * fy).round() as u8);
+        }
+    }
+    out
+}
+
+/// Gaussian blur with standard deviation `sigma` pixels (kernel cut at 3σ,
+/// edges clamped), as two separable passes. Smooths out compression noise
+/// and dithering that would otherwise binarize into speckle.
+pub fn gaussian_blur(buf: &[u8], w: usize, sigma: f32) -> Vec<u8> {
+    let h = buf.len().checked_div(w).unwrap_or(0);
+    let r = (3.0 * sigma).ceil() as isize;
+    let mut kernel: Vec<f32> = (-r..=r)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|k| *k /= total);
+
+    let ru = r as usize;
+    let clamp = |i: isize, n: usize| i.clamp(0, n as isize - 1) as usize;
+
+    // Horizontal: each row padded with its edge values, then a plain dot product
+    let mut rows = vec![0f32; buf.len()];
+    let mut padded = vec![0f32; w + 2 * ru];
+    for (src, dst) in buf.chunks_exact(w).zip(rows.chunks_exact_mut(w)) {
+        for (i, p) in padded.iter_mut().enumerate() {
+            *p = src[clamp(i as isize - r, w)] as f32;
+        }
+        for (x, d) in dst.iter_mut().enumerate() {
+            *d = padded[x..x + kernel.len()]
+                .iter()
+                .zip(&kernel)
+                .map(|(v, k)| v * k)
+                .sum();
+        }
+    }
+
+    // Vertical: whole rows scaled and added, so the inner loop runs along x
+    let mut out = vec![0u8; buf.len()];
+    let mut acc = vec![0f32; w];
+    for (y, dst) in out.chunks_exact_mut(w).enumerate() {
+        acc.fill(0.0);
+        for (k, &wt) in (-r..=r).zip(&kernel) {
+            let src = &rows[clamp(y as isize + k, h) * w..][..w];
+            for (a, &v) in acc.iter_mut().zip(src) {
+                *a += v * wt;
+            }
+        }
+        for (d, &a) in dst.iter_mut().zip(&acc) {
+            *d = a.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
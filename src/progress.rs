@@ -0,0 +1,38 @@
+// src/lib/progress.rs
+// Per-frame progress reports for long conversions: a CLI progress bar or a
+// GUI registers a callback, the conversion calls it after each merged frame
+// (in frame order, on the thread collecting the frames).
+
+use std::{fmt, sync::Arc};
+
+/// State after one more frame was merged.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// Frames merged so far, including this one
+    pub done: usize,
+    /// Frames in the input, None for streamed sources
+    pub total: Option<usize>,
+    /// Threshold this frame was binarized with
+    pub threshold: f32,
+    /// Rects this frame merged into
+    pub rects: usize,
+}
+
+#[derive(Clone)]
+pub struct ProgressFn(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressFn {
+    pub fn new(f: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn report(&self, progress: &Progress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressFn")
+    }
+}
@@ -0,0 +1,162 @@
+// src/lib/python.rs
+// Python module (feature "python"), built with maturin:
+//
+//   import bad_apple
+//   p = bad_apple.convert_rectframes("frames", w=256, h=192)
+//   p.save("out/rectFrames.json")
+//   p = bad_apple.load_payload("out/rectFrames.json")
+//   pixels = p.rasterize(1500)          # bytes, width*height, 1 = black
+//   cues = bad_apple.parse_srt_to_cues(open("lyrics/transcript_en.srt").read())
+
+use pyo3::exceptions::PyIndexError;
+use pyo3::prelude::*;
+use std::{fs, path::PathBuf};
+
+use crate::binary::{decode_payload, MAGIC};
+use crate::rectframes::{self, ConvertRectframesOpts};
+use crate::subs;
+
+/// Rect as seen from Python: `(x, y, w, h, v)`.
+type RectTuple = (u32, u32, u32, u32, u8);
+
+#[pyclass(name = "Payload", module = "bad_apple", frozen)]
+pub struct PyPayload(rectframes::Payload);
+
+#[pymethods]
+impl PyPayload {
+    #[getter]
+    fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.0.height
+    }
+
+    #[getter]
+    fn fps(&self) -> u32 {
+        self.0.fps
+    }
+
+    #[getter]
+    fn threshold(&self) -> u32 {
+        self.0.threshold
+    }
+
+    #[getter]
+    fn th_mul(&self) -> f32 {
+        self.0.th_mul
+    }
+
+    #[getter]
+    fn invert(&self) -> bool {
+        self.0.invert
+    }
+
+    #[getter]
+    fn frames_count(&self) -> usize {
+        self.0.rect_frames.len()
+    }
+
+    /// Per-frame thresholds, or None unless converted with `--frame-stats`.
+    #[getter]
+    fn frame_thresholds(&self) -> Option<Vec<f32>> {
+        self.0.frame_thresholds.clone()
+    }
+
+    #[getter]
+    fn frame_rect_counts(&self) -> Option<Vec<usize>> {
+        self.0.frame_rect_counts.clone()
+    }
+
+    /// Every frame as a list of `(x, y, w, h, v)` tuples.
+    #[getter]
+    fn rect_frames(&self) -> Vec<Vec<RectTuple>> {
+        self.0
+            .rect_frames
+            .iter()
+            .map(|rects| rects.iter().map(|r| (r.x, r.y, r.w, r.h, r.v)).collect())
+            .collect()
+    }
+
+    /// Frame `i` as `width * height` bytes, 1 = black/on.
+    fn rasterize(&self, i: usize) -> PyResult<Vec<u8>> {
+        if i >= self.0.rect_frames.len() {
+            return Err(PyIndexError::new_err(format!("frame {i} out of range")));
+        }
+        Ok(self.0.rasterize_frame(i))
+    }
+
+    /// Writes the payload as rectFrames.json.
+    fn save(&self, path: PathBuf) -> anyhow::Result<()> {
+        Ok(rectframes::write_payload_json(&self.0, &path)?)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.rect_frames.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Payload({}x{} @ {}fps, {} frames)",
+            self.0.width,
+            self.0.height,
+            self.0.fps,
+            self.0.rect_frames.len()
+        )
+    }
+}
+
+/// Converts a directory of PNG frames, like the convert_rectframes binary.
+#[pyfunction]
+#[pyo3(signature = (in_dir, w = 192, h = 144, fps = 30, invert = false, th_mul = 0.95))]
+fn convert_rectframes(
+    py: Python<'_>,
+    in_dir: PathBuf,
+    w: u32,
+    h: u32,
+    fps: u32,
+    invert: bool,
+    th_mul: f32,
+) -> anyhow::Result<PyPayload> {
+    let opts = ConvertRectframesOpts::builder(&in_dir)
+        .size(w, h)
+        .fps(fps)
+        .invert(invert)
+        .th_mul(th_mul)
+        .build()?;
+    // Multi-minute for the full video, let other Python threads run meanwhile
+    let payload = py.detach(|| rectframes::convert_rectframes(opts))?;
+    Ok(PyPayload(payload))
+}
+
+/// Loads a rectFrames.json or binary ("BAPL") payload.
+#[pyfunction]
+fn load_payload(path: PathBuf) -> anyhow::Result<PyPayload> {
+    let bytes = fs::read(&path)?;
+    let payload = if bytes.starts_with(MAGIC) {
+        decode_payload(&bytes)?
+    } else {
+        rectframes::Payload::from_file(&path)?
+    };
+    Ok(PyPayload(payload))
+}
+
+/// Parses SRT text into `(start, end, text)` tuples (seconds).
+#[pyfunction]
+fn parse_srt_to_cues(srt_text: &str) -> Vec<(f32, f32, String)> {
+    subs::parse_srt_to_cues(srt_text)
+        .into_iter()
+        .map(|c| (c.s, c.e, c.t))
+        .collect()
+}
+
+#[pymodule]
+fn bad_apple(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPayload>()?;
+    m.add_function(wrap_pyfunction!(convert_rectframes, m)?)?;
+    m.add_function(wrap_pyfunction!(load_payload, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_srt_to_cues, m)?)?;
+    Ok(())
+}
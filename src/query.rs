@@ -0,0 +1,104 @@
+// src/lib/query.rs
+// Time and point lookups on a payload, for interactive players that need to
+// know which rect (if any) sits under the cursor at the current time.
+
+use crate::rectframes::{Payload, Rect};
+
+impl Payload {
+    /// Index of the frame shown at `secs` seconds, None before the start or
+    /// past the end.
+    pub fn frame_at(&self, secs: f64) -> Option<usize> {
+        if secs.is_nan() || secs < 0.0 || self.fps == 0 {
+            return None;
+        }
+        let i = (secs * self.fps as f64).floor() as usize;
+        (i < self.rect_frames.len()).then_some(i)
+    }
+
+    /// First rect of `frame` covering pixel (`x`, `y`), None if the pixel is
+    /// uncovered (off, unless the payload has `off_rects`) or out of range.
+    /// A linear scan; for many lookups on one frame build a `FrameIndex`.
+    pub fn rect_at(&self, frame: usize, x: u32, y: u32) -> Option<&Rect> {
+        let rects = self.rect_frames.get(frame)?;
+        // Sorted by (y, x): nothing past the first rect starting below y can hit
+        let end = rects.partition_point(|r| r.y <= y);
+        rects[..end].iter().find(|r| contains(r, x, y))
+    }
+
+    /// Spatial index over one frame's rects, see `FrameIndex`.
+    pub fn frame_index(&self, frame: usize, cell: u32) -> Option<FrameIndex> {
+        let rects = self.rect_frames.get(frame)?;
+        Some(FrameIndex::new(rects, self.width, self.height, cell))
+    }
+}
+
+fn contains(r: &Rect, x: u32, y: u32) -> bool {
+    x >= r.x && x - r.x < r.w && y >= r.y && y - r.y < r.h
+}
+
+/// Uniform grid over a frame: each `cell`×`cell` square lists the rects
+/// overlapping it, so a lookup only tests those.
+#[derive(Clone, Debug)]
+pub struct FrameIndex {
+    cell: u32,
+    cols: u32,
+    rows: u32,
+    /// `ids[offsets[c]..offsets[c + 1]]` are the rects overlapping cell c
+    offsets: Vec<u32>,
+    ids: Vec<u32>,
+}
+
+impl FrameIndex {
+    /// Indexes `rects` of a `width`×`height` frame; `cell` is clamped to >= 1.
+    pub fn new(rects: &[Rect], width: u32, height: u32, cell: u32) -> Self {
+        let cell = cell.max(1);
+        let (cols, rows) = (width.div_ceil(cell), height.div_ceil(cell));
+        let cells_of = |r: &Rect| {
+            let x1 = (r.x.saturating_add(r.w).min(width)).div_ceil(cell);
+            let y1 = (r.y.saturating_add(r.h).min(height)).div_ceil(cell);
+            let (x0, y0) = (r.x / cell, r.y / cell);
+            (y0..y1).flat_map(move |cy| (x0..x1).map(move |cx| (cy * cols + cx) as usize))
+        };
+
+        // Two passes (count, fill) into one flat array instead of a Vec per cell
+        let mut offsets = vec![0u32; (cols * rows) as usize + 1];
+        for r in rects {
+            for c in cells_of(r) {
+                offsets[c + 1] += 1;
+            }
+        }
+        for c in 1..offsets.len() {
+            offsets[c] += offsets[c - 1];
+        }
+        let mut fill = offsets.clone();
+        let mut ids = vec![0u32; *offsets.last().unwrap_or(&0) as usize];
+        for (i, r) in rects.iter().enumerate() {
+            for c in cells_of(r) {
+                ids[fill[c] as usize] = i as u32;
+                fill[c] += 1;
+            }
+        }
+
+        Self {
+            cell,
+            cols,
+            rows,
+            offsets,
+            ids,
+        }
+    }
+
+    /// Index (into the frame's rects) of the first rect covering (`x`, `y`),
+    /// same answer as `Payload::rect_at`.
+    pub fn rect_at(&self, rects: &[Rect], x: u32, y: u32) -> Option<usize> {
+        let (cx, cy) = (x / self.cell, y / self.cell);
+        if cx >= self.cols || cy >= self.rows {
+            return None;
+        }
+        let c = (cy * self.cols + cx) as usize;
+        self.ids[self.offsets[c] as usize..self.offsets[c + 1] as usize]
+            .iter()
+            .map(|&i| i as usize)
+            .find(|&i| rects.get(i).is_some_and(|r| contains(r, x, y)))
+    }
+}
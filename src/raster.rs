@@ -0,0 +1,137 @@
+// src/lib/raster.rs
+// Helpers for 1/0 pixel buffers (as produced by `Payload::rasterize_frame`).
+
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Fit {
+    /// Keep aspect ratio, center and letterbox (off pixels)
+    #[default]
+    Contain,
+    /// Stretch to fill the target, ignoring aspect ratio
+    Stretch,
+}
+
+impl FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(Fit::Contain),
+            "stretch" => Ok(Fit::Stretch),
+            _ => Err(format!("unknown fit {s:?} (expected contain|stretch)")),
+        }
+    }
+}
+
+/// Nearest-neighbour resample of a `sw`×`sh` 1/0 buffer into `dw`×`dh`.
+pub fn resample(src: &[u8], sw: usize, sh: usize, dw: usize, dh: usize, fit: Fit) -> Vec<u8> {
+    let mut dst = vec![0u8; dw * dh];
+    if sw == 0 || sh == 0 || dw == 0 || dh == 0 {
+        return dst;
+    }
+
+    let (tw, th) = match fit {
+        Fit::Stretch => (dw, dh),
+        Fit::Contain => {
+            // Largest box with the source aspect ratio that fits the target
+            if sw * dh > dw * sh {
+                (dw, (sh * dw / sw).max(1))
+            } else {
+                ((sw * dh / sh).max(1), dh)
+            }
+        }
+    };
+    let ox = (dw - tw) / 2;
+    let oy = (dh - th) / 2;
+
+    for y in 0..th {
+        let sy = y * sh / th;
+        for x in 0..tw {
+            let sx = x * sw / tw;
+            dst[(oy + y) * dw + ox + x] = src[sy * sw + sx];
+        }
+    }
+
+    dst
+}
+
+/// Box-filter downscale of a `sw`×`sh` 1/0 buffer into `dw`×`dh`: a pixel
+/// is on when at least half of the source pixels it covers are, so thin
+/// strokes survive better than with `resample`. Upscaled axes repeat pixels.
+pub fn downscale(src: &[u8], sw: usize, sh: usize, dw: usize, dh: usize) -> Vec<u8> {
+    // Source span [a, b) of each target column / row
+    let spans = |s: usize, d: usize| -> Vec<(usize, usize)> {
+        (0..d)
+            .map(|i| (i * s / d, ((i + 1) * s / d).max(i * s / d + 1)))
+            .collect()
+    };
+    let (cols, rows) = (spans(sw, dw), spans(sh, dh));
+
+    let mut dst = vec![0u8; dw * dh];
+    let mut counts = vec![0usize; dw];
+    for (y, &(y0, y1)) in rows.iter().enumerate() {
+        counts.fill(0);
+        for row in src[y0 * sw..y1 * sw].chunks_exact(sw) {
+            for (c, &(x0, x1)) in counts.iter_mut().zip(&cols) {
+                *c += row[x0..x1].iter().filter(|&&v| v != 0).count();
+            }
+        }
+        for ((px, &c), &(x0, x1)) in dst[y * dw..][..dw].iter_mut().zip(&counts).zip(&cols) {
+            *px = (2 * c >= (x1 - x0) * (y1 - y0)) as u8;
+        }
+    }
+    dst
+}
+
+/// Bytes per packed row (rows are padded to a whole byte).
+pub fn row_bytes(w: usize) -> usize {
+    w.div_ceil(8)
+}
+
+/// Packs a 1/0 buffer at 1 bit per pixel, MSB first, each row padded to a byte.
+pub fn pack_1bpp(frame: &[u8], w: usize, h: usize) -> Vec<u8> {
+    let rb = row_bytes(w);
+    let mut out = vec![0u8; rb * h];
+    for y in 0..h {
+        for x in 0..w {
+            if frame[y * w + x] != 0 {
+                out[y * rb + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+    out
+}
+
+/// Inverse of [`pack_1bpp`].
+pub fn unpack_1bpp(packed: &[u8], w: usize, h: usize) -> Vec<u8> {
+    let rb = row_bytes(w);
+    let mut out = vec![0u8; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            if packed[y * rb + x / 8] & (0x80 >> (x % 8)) != 0 {
+                out[y * w + x] = 1;
+            }
+        }
+    }
+    out
+}
+
+/// Parses `"WxH"` (e.g. `"128x64"`).
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s
+        .split_once(['x', 'X', '×'])
+        .ok_or_else(|| format!("invalid size {s:?} (expected WxH)"))?;
+    let w: u32 = w
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid width in {s:?}"))?;
+    let h: u32 = h
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid height in {s:?}"))?;
+    if w == 0 || h == 0 {
+        return Err(format!("size must be non-zero: {s:?}"));
+    }
+    Ok((w, h))
+}
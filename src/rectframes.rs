@@ -1,36 +1,368 @@
-use anyhow::{Context, Result};
-use serde::Serialize;
-use std::{collections::HashMap, fs, path::Path};
+use image::DynamicImage;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, instrument};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use crate::binarize::{
+    binarize_each, binarize_hysteresis, mark_near, tile_thresholds, Background, BackgroundModel,
+    Hysteresis, Temporal, TemporalFilter, ThresholdMode,
+};
+use crate::cancel::CancelToken;
+pub use crate::decode::{rasterize_rects, Rect};
+use crate::error::{BadAppleError, Result};
+use crate::interpolate::{double_frames, Interpolate};
+use crate::pipeline;
+use crate::preprocess::{gaussian_blur, Adjust, Equalize};
+use crate::progress::{Progress, ProgressFn};
+use crate::raster::downscale;
+use crate::source::{frame_order, PngFrames};
+
+/// Conversion settings. Build with `ConvertRectframesOpts::builder`, new
+/// fields get a default there instead of breaking every caller.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct ConvertRectframesOpts<'a> {
     pub w: u32,
     pub h: u32,
     pub fps: u32,
     pub invert: bool,
     pub th_mul: f32,
+    /// PNG directory, or a .zip / .tar / .tar.gz of PNGs (feature "archive")
     pub in_dir: &'a Path,
+    /// Checked between frames, see `CancelToken`
+    pub cancel: Option<CancelToken>,
+    /// Called after every merged frame, see `Progress`; replaces the
+    /// periodic "✔ n/total" log lines
+    pub progress: Option<ProgressFn>,
+    /// Worker threads for decoding + merging (0 = one per core, 1 = no extra threads)
+    pub threads: usize,
+    /// Binarize colour frames with wgpu (feature "gpu"; CPU if off or no adapter)
+    pub gpu: bool,
+    /// Also record each frame's threshold and rect count in the payload
+    pub frame_stats: bool,
+    /// Dual-threshold binarization instead of the single `th_mul` cut
+    pub hysteresis: Option<Hysteresis>,
+    /// Hold pixels near the threshold across frames to stop flicker
+    pub temporal: Option<Temporal>,
+    /// Global cut or per-tile means, see `ThresholdMode`
+    pub threshold: ThresholdMode,
+    /// Histogram equalization before thresholding
+    pub equalize: Option<Equalize>,
+    /// Gamma / brightness / contrast, applied before `equalize`
+    pub adjust: Adjust,
+    /// Gaussian blur sigma in pixels, applied first of all preprocessing
+    pub blur: Option<f32>,
+    /// Binarize the difference to an estimated static background instead
+    pub background: Option<Background>,
+    /// PNG of the frame size: white areas are encoded, black ones forced off
+    pub mask: Option<PathBuf>,
+    /// Also emit the off regions, merged the same way, as `v: 0` rects
+    pub off_rects: bool,
+    /// Double the frame rate with in-between frames
+    pub interpolate: Option<Interpolate>,
+    /// Extra payload sizes (width, height), downscaled from the binarized
+    /// frames; only `convert_rectframes_sizes` produces them
+    pub sizes: Vec<(u32, u32)>,
+    /// Indented JSON from the `_to_file` functions (compact by default)
+    pub pretty: bool,
 }
 
-#[derive(Serialize, Clone, Debug)]
-pub struct Rect {
-    pub x: u32,
-    pub y: u32,
-    pub w: u32,
-    pub h: u32,
-    pub v: u8, // 1 = black/on
+impl<'a> ConvertRectframesOpts<'a> {
+    /// Starts from the defaults (192×144 @ 30fps, th_mul 0.95, no invert).
+    pub fn builder(in_dir: &'a Path) -> ConvertRectframesOptsBuilder<'a> {
+        ConvertRectframesOptsBuilder {
+            opts: ConvertRectframesOpts {
+                w: 192,
+                h: 144,
+                fps: 30,
+                invert: false,
+                th_mul: 0.95,
+                in_dir,
+                cancel: None,
+                progress: None,
+                threads: 0,
+                gpu: false,
+                frame_stats: false,
+                hysteresis: None,
+                temporal: None,
+                threshold: ThresholdMode::Global,
+                equalize: None,
+                adjust: Adjust::default(),
+                blur: None,
+                background: None,
+                mask: None,
+                off_rects: false,
+                interpolate: None,
+                sizes: Vec::new(),
+                pretty: false,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConvertRectframesOptsBuilder<'a> {
+    opts: ConvertRectframesOpts<'a>,
+}
+
+impl<'a> ConvertRectframesOptsBuilder<'a> {
+    /// Frame width the PNGs must have.
+    pub fn w(mut self, w: u32) -> Self {
+        self.opts.w = w;
+        self
+    }
+
+    /// Frame height the PNGs must have.
+    pub fn h(mut self, h: u32) -> Self {
+        self.opts.h = h;
+        self
+    }
+
+    pub fn size(self, w: u32, h: u32) -> Self {
+        self.w(w).h(h)
+    }
+
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.opts.fps = fps;
+        self
+    }
+
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.opts.invert = invert;
+        self
+    }
+
+    /// Multiplier applied to each frame's mean luma to get its threshold.
+    pub fn th_mul(mut self, th_mul: f32) -> Self {
+        self.opts.th_mul = th_mul;
+        self
+    }
+
+    /// Lets another thread abort the conversion with `token.cancel()`.
+    pub fn cancel(mut self, token: CancelToken) -> Self {
+        self.opts.cancel = Some(token);
+        self
+    }
+
+    /// Calls `f` after every merged frame, e.g. to drive a progress bar.
+    pub fn on_progress(mut self, f: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.opts.progress = Some(ProgressFn::new(f));
+        self
+    }
+
+    /// Worker threads, 0 (the default) for one per core.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.opts.threads = threads;
+        self
+    }
+
+    /// Binarize on the GPU where possible (needs feature "gpu").
+    pub fn gpu(mut self, gpu: bool) -> Self {
+        self.opts.gpu = gpu;
+        self
+    }
+
+    /// Keep per-frame thresholds and rect counts (`Payload::frame_thresholds`).
+    pub fn frame_stats(mut self, frame_stats: bool) -> Self {
+        self.opts.frame_stats = frame_stats;
+        self
+    }
+
+    /// Binarize with low/high cuts (multipliers of the mean luma, `th_mul`
+    /// is then unused), keeping in-between pixels only next to strong ones.
+    pub fn hysteresis(mut self, hysteresis: Hysteresis) -> Self {
+        self.opts.hysteresis = Some(hysteresis);
+        self
+    }
+
+    /// Anti-flicker: pixels only change state when clearly past the threshold
+    /// or after staying across it for a few frames.
+    pub fn temporal(mut self, temporal: Temporal) -> Self {
+        self.opts.temporal = Some(temporal);
+        self
+    }
+
+    /// Per-pixel threshold source (default: one global cut per frame).
+    pub fn threshold(mut self, threshold: ThresholdMode) -> Self {
+        self.opts.threshold = threshold;
+        self
+    }
+
+    /// Equalize each frame's histogram first, for low-contrast sources.
+    pub fn equalize(mut self, equalize: Equalize) -> Self {
+        self.opts.equalize = Some(equalize);
+        self
+    }
+
+    /// Gamma correction of the luma, > 1 brightens midtones (default 1).
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.opts.adjust.gamma = gamma;
+        self
+    }
+
+    /// Added to the luma, -1..1 of the full range (default 0).
+    pub fn brightness(mut self, brightness: f32) -> Self {
+        self.opts.adjust.brightness = brightness;
+        self
+    }
+
+    /// Luma contrast around mid-gray (default 1).
+    pub fn contrast(mut self, contrast: f32) -> Self {
+        self.opts.adjust.contrast = contrast;
+        self
+    }
+
+    /// Blur with this sigma (pixels) before binarizing, against noisy sources.
+    pub fn blur(mut self, sigma: f32) -> Self {
+        self.opts.blur = Some(sigma);
+        self
+    }
+
+    /// Encode only what differs from the input's static background (needs
+    /// `in_dir`, the background is estimated from it before converting).
+    pub fn background(mut self, background: Background) -> Self {
+        self.opts.background = Some(background);
+        self
+    }
+
+    /// Only encode the white areas of this PNG (watermarks, timestamps and
+    /// letterbox bars go black); masked-off pixels don't count towards the mean.
+    pub fn mask(mut self, mask: impl Into<PathBuf>) -> Self {
+        self.opts.mask = Some(mask.into());
+        self
+    }
+
+    /// Emit `v: 0` rects for the off regions too (`Payload::off_rects`).
+    pub fn off_rects(mut self, off_rects: bool) -> Self {
+        self.opts.off_rects = off_rects;
+        self
+    }
+
+    /// Insert an in-between frame after every frame, doubling `fps`.
+    pub fn interpolate(mut self, mode: Interpolate) -> Self {
+        self.opts.interpolate = Some(mode);
+        self
+    }
+
+    /// Also produce payloads at these sizes (each at most `w`×`h`) from the
+    /// same decode pass, see `convert_rectframes_sizes`.
+    pub fn sizes(mut self, sizes: Vec<(u32, u32)>) -> Self {
+        self.opts.sizes = sizes;
+        self
+    }
+
+    /// Write indented JSON in `convert_rectframes_to_file`, for debugging.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.opts.pretty = pretty;
+        self
+    }
+
+    pub fn build(self) -> Result<ConvertRectframesOpts<'a>> {
+        let o = &self.opts;
+        let invalid = |msg: String| Err(BadAppleError::InvalidOpts(msg));
+        if o.w == 0 || o.h == 0 {
+            return invalid(format!("frame size must be non-zero, got {}×{}", o.w, o.h));
+        }
+        if o.fps == 0 {
+            return invalid("fps must be non-zero".into());
+        }
+        if !(o.th_mul.is_finite() && o.th_mul > 0.0) {
+            return invalid(format!("th_mul must be a positive number, got {}", o.th_mul));
+        }
+        if let Some(h) = &o.hysteresis {
+            h.validate().map_err(BadAppleError::InvalidOpts)?;
+        }
+        if o.hysteresis.is_some() && o.threshold != ThresholdMode::Global {
+            return invalid(
+                "hysteresis uses the global mean, it can't be combined with tiles".into(),
+            );
+        }
+        if let Some(sigma) = o.blur
+            && !(sigma.is_finite() && sigma > 0.0)
+        {
+            return invalid(format!("blur must be a positive number, got {sigma}"));
+        }
+        o.adjust.validate().map_err(BadAppleError::InvalidOpts)?;
+        if let Some(eq) = &o.equalize {
+            eq.validate().map_err(BadAppleError::InvalidOpts)?;
+        }
+        if let Some(bg) = &o.background {
+            bg.validate().map_err(BadAppleError::InvalidOpts)?;
+            if o.hysteresis.is_some() || o.threshold != ThresholdMode::Global {
+                return invalid(
+                    "background subtraction has its own threshold, drop hysteresis / tiles".into(),
+                );
+            }
+        }
+        if let Some(t) = &o.temporal {
+            t.validate().map_err(BadAppleError::InvalidOpts)?;
+        }
+        if let Some(&(w, h)) = o
+            .sizes
+            .iter()
+            .find(|&&(w, h)| w == 0 || h == 0 || w > o.w || h > o.h)
+        {
+            return invalid(format!(
+                "sizes are downscaled from {}×{}, got {w}×{h}",
+                o.w, o.h
+            ));
+        }
+        Ok(self.opts)
+    }
 }
 
-#[derive(Serialize)]
+/// Current `Payload::version`. Bump it whenever the JSON layout changes and
+/// teach `Payload::migrate` to upgrade from the previous version.
+pub const PAYLOAD_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct Payload {
+    /// Format version, see `PAYLOAD_VERSION` (missing = 0, written before versioning)
+    #[serde(default)]
+    pub version: u32,
     pub width: u32,
     pub height: u32,
     pub fps: u32,
+    /// Average binarization threshold over all frames (0-255)
     pub threshold: u32,
+    /// Multiplier applied to each frame's mean luma to get its threshold
+    #[serde(serialize_with = "fixed_decimals")]
     pub th_mul: f32,
     pub invert: bool,
     pub frames_count: usize,
+    /// Rects per frame, in frame order, each frame sorted by (y, x)
     pub rect_frames: Vec<Vec<Rect>>,
+    /// Threshold each frame was binarized with (mean luma × `th_mul`), when
+    /// converted with `frame_stats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_thresholds: Option<Vec<f32>>,
+    /// `rect_frames[i].len()` for each frame, alongside `frame_thresholds`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_rect_counts: Option<Vec<usize>>,
+    /// Luma preprocessing applied before thresholding, in order (e.g.
+    /// `"equalize:clahe:8,2"`); empty for plain conversions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preprocess: Vec<String>,
+    /// `rect_frames` also cover the off pixels, as `v: 0` rects, so a renderer
+    /// can paint both colours instead of clearing each frame
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub off_rects: bool,
+    /// Every other frame was interpolated (`fps` is twice the source rate)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpolated: Option<Interpolate>,
+}
+
+/// Writes an f32 rounded to 4 decimals, so values that went through f64 (the
+/// Python/Node bindings, config arithmetic) serialize the same as CLI input.
+fn fixed_decimals<S: serde::Serializer>(v: &f32, s: S) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_f64((*v as f64 * 1e4).round() / 1e4)
 }
 
 #[inline]
@@ -38,26 +370,45 @@ fn idx(x: usize, y: usize, w: usize) -> usize {
     y * w + x
 }
 
+/// Pixels per chunk when summing luma: independent u32 lanes instead of one
+/// u64 accumulator, which LLVM turns into SIMD adds (SSE2/AVX2/NEON).
+const LANES: usize = 32;
+
 fn adaptive_threshold(gray: &[u8]) -> f32 {
-    let sum: u64 = gray.iter().map(|&v| v as u64).sum();
+    // u32 lanes can't overflow below ~16.8M chunks (537M pixels)
+    let mut lanes = [0u32; LANES];
+    let chunks = gray.chunks_exact(LANES);
+    let tail: u64 = chunks.remainder().iter().map(|&v| v as u64).sum();
+    for chunk in chunks {
+        for (lane, &v) in lanes.iter_mut().zip(chunk) {
+            *lane += v as u32;
+        }
+    }
+
+    let sum = lanes.iter().map(|&l| l as u64).sum::<u64>() + tail;
     (sum as f32) / (gray.len() as f32)
 }
 
 /*
 Strategy:
-1) For each row, convert 1/0 pixels into horizontal runs: (x_start, run_width)
-2) Merge vertical rectangles only when the run key (x_start, run_width) matches exactly
+1) For each row, convert 1/0 pixels into horizontal runs: (x_start, run_width, value),
+   keeping the 0 runs only with `off_rects`
+2) Merge vertical rectangles only when the run key (x_start, run_width, value) matches exactly
 */
-fn merge_frame_to_rects(frame: &[u8], w: usize, h: usize) -> Vec<Rect> {
-    let mut runs_by_row: Vec<Vec<(usize, usize)>> = vec![Vec::new(); h];
+pub(crate) fn merge_frame_to_rects(frame: &[u8], w: usize, h: usize, off_rects: bool) -> Vec<Rect> {
+    let mut runs_by_row: Vec<Vec<(usize, usize, u8)>> = vec![Vec::new(); h];
 
     for (y, row_runs) in runs_by_row.iter_mut().enumerate() {
         let mut x = 0usize;
 
         while x < w {
+            let start = x;
             while x < w && frame[idx(x, y, w)] == 0 {
                 x += 1;
             }
+            if off_rects && x > start {
+                row_runs.push((start, x - start, 0));
+            }
             if x >= w {
                 break;
             }
@@ -68,18 +419,18 @@ fn merge_frame_to_rects(frame: &[u8], w: usize, h: usize) -> Vec<Rect> {
             }
 
             let run_w = x - start;
-            row_runs.push((start, run_w));
+            row_runs.push((start, run_w, 1));
         }
     }
 
     let mut rects: Vec<Rect> = Vec::new();
-    let mut active: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut active: HashMap<(usize, usize, u8), usize> = HashMap::new();
 
     for (y, runs) in runs_by_row.iter().enumerate() {
-        let mut next_active: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut next_active: HashMap<(usize, usize, u8), usize> = HashMap::new();
 
-        for &(x, run_w) in runs {
-            let key = (x, run_w);
+        for &(x, run_w, v) in runs {
+            let key = (x, run_w, v);
 
             if let Some(&rect_idx) = active.get(&key) {
                 rects[rect_idx].h += 1;
@@ -91,7 +442,7 @@ fn merge_frame_to_rects(frame: &[u8], w: usize, h: usize) -> Vec<Rect> {
                     y: y as u32,
                     w: run_w as u32,
                     h: 1,
-                    v: 1,
+                    v,
                 });
                 next_active.insert(key, rect_idx);
             }
@@ -103,95 +454,925 @@ fn merge_frame_to_rects(frame: &[u8], w: usize, h: usize) -> Vec<Rect> {
     rects
 }
 
-pub fn convert_rectframes(opts: ConvertRectframesOpts<'_>) -> Result<Payload> {
-    if !opts.in_dir.exists() {
-        anyhow::bail!("Input directory not found: {}", opts.in_dir.display());
+pub(crate) fn list_frames(in_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !in_dir.exists() {
+        return Err(BadAppleError::MissingInput {
+            what: "Input directory",
+            path: in_dir.to_path_buf(),
+        });
     }
 
-    let mut files: Vec<_> = fs::read_dir(opts.in_dir)?
+    let entries = fs::read_dir(in_dir)
+        .map_err(BadAppleError::io("reading", in_dir))?
         .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| p.extension().map(|x| x == "png").unwrap_or(false))
-        .collect();
+        .map(|e| e.path());
+
+    sorted_pngs(in_dir, entries.collect())
+}
 
-    files.sort();
+/// Keeps the .png paths in `frame_order`, erroring if there are none.
+fn sorted_pngs(in_dir: &Path, mut files: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    files.retain(|p| p.extension().map(|x| x == "png").unwrap_or(false));
+    files.sort_by(|a, b| frame_order(a, b));
 
     if files.is_empty() {
-        anyhow::bail!("No PNG frames found in {}", opts.in_dir.display());
+        return Err(BadAppleError::MissingInput {
+            what: "PNG frames",
+            path: in_dir.to_path_buf(),
+        });
     }
 
-    println!("🎞️  Frames: {}", files.len());
-    println!("📐 {}×{} @ {}fps", opts.w, opts.h, opts.fps);
-    println!("🔁 Invert: {}", opts.invert);
-    println!("🎚️  Threshold multiplier: {}", opts.th_mul);
+    Ok(files)
+}
 
-    let mut rect_frames: Vec<Vec<Rect>> = Vec::with_capacity(files.len());
-    let mut th_sum: f64 = 0.0;
+/// The per-frame part of the options, cheap to clone into worker tasks (the
+/// background buffer is shared).
+#[derive(Clone, Debug)]
+pub(crate) struct FrameParams {
+    pub w: u32,
+    pub h: u32,
+    pub invert: bool,
+    pub th_mul: f32,
+    pub hysteresis: Option<Hysteresis>,
+    /// Only the margin matters per frame; the filter itself runs in order
+    pub temporal: Option<Temporal>,
+    pub threshold: ThresholdMode,
+    pub equalize: Option<Equalize>,
+    pub adjust: Adjust,
+    pub blur: Option<f32>,
+    /// Estimated by `ConvertRectframesOpts::prepare`
+    pub background: Option<BackgroundModel>,
+    /// 1 = encode, 0 = forced off; loaded by `ConvertRectframesOpts::prepare`
+    pub mask: Option<Arc<[u8]>>,
+}
 
-    for (i, fp) in files.iter().enumerate() {
-        let img = image::open(fp).with_context(|| format!("Failed to open {}", fp.display()))?;
+impl FrameParams {
+    /// Modes the GPU path doesn't implement.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn cpu_only(&self) -> bool {
+        self.hysteresis.is_some()
+            || self.temporal.is_some()
+            || self.threshold != ThresholdMode::Global
+            || self.equalize.is_some()
+            || !self.adjust.is_identity()
+            || self.blur.is_some()
+            || self.background.is_some()
+            || self.mask.is_some()
+    }
 
+    /// Mean luma of the pixels the mask keeps (all of them without a mask).
+    fn mean(&self, buf: &[u8]) -> f32 {
+        let Some(mask) = &self.mask else {
+            return adaptive_threshold(buf);
+        };
+        let (sum, n) = buf
+            .iter()
+            .zip(mask.iter())
+            .filter(|&(_, &keep)| keep == 1)
+            .fold((0u64, 0u64), |(sum, n), (&v, _)| (sum + v as u64, n + 1));
+        if n == 0 {
+            return adaptive_threshold(buf);
+        }
+        sum as f32 / n as f32
+    }
+
+    /// Blur, exposure and equalization, in that order; borrows `buf` untouched
+    /// when none is set.
+    fn preprocess<'b>(&self, buf: &'b [u8]) -> Cow<'b, [u8]> {
+        let w = self.w as usize;
+        let mut buf = Cow::Borrowed(buf);
+        if let Some(sigma) = self.blur {
+            buf = Cow::Owned(gaussian_blur(&buf, w, sigma));
+        }
+        if !self.adjust.is_identity() {
+            buf = Cow::Owned(self.adjust.apply(&buf));
+        }
+        if let Some(eq) = &self.equalize {
+            buf = Cow::Owned(eq.apply(&buf, w));
+        }
+        buf
+    }
+}
+
+impl ConvertRectframesOpts<'_> {
+    /// The same options without the `in_dir` borrow, to move into a task
+    /// and point at an owned path there.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    fn detached(&self) -> ConvertRectframesOpts<'static> {
+        ConvertRectframesOpts {
+            w: self.w,
+            h: self.h,
+            fps: self.fps,
+            invert: self.invert,
+            th_mul: self.th_mul,
+            in_dir: Path::new(""),
+            cancel: self.cancel.clone(),
+            threads: self.threads,
+            gpu: self.gpu,
+            frame_stats: self.frame_stats,
+            hysteresis: self.hysteresis,
+            temporal: self.temporal,
+            threshold: self.threshold,
+            equalize: self.equalize,
+            adjust: self.adjust,
+            blur: self.blur,
+            background: self.background,
+            mask: self.mask.clone(),
+            off_rects: self.off_rects,
+            interpolate: self.interpolate,
+            sizes: self.sizes.clone(),
+            progress: self.progress.clone(),
+            pretty: self.pretty,
+        }
+    }
+
+    /// `frame_params` without the background model; see `prepare`.
+    pub(crate) fn frame_params(&self) -> FrameParams {
+        FrameParams {
+            w: self.w,
+            h: self.h,
+            invert: self.invert,
+            th_mul: self.th_mul,
+            hysteresis: self.hysteresis,
+            temporal: self.temporal,
+            threshold: self.threshold,
+            equalize: self.equalize,
+            adjust: self.adjust,
+            blur: self.blur,
+            background: None,
+            mask: None,
+        }
+    }
+
+    /// `frame_params` plus the buffers it leaves out: the mask, and the
+    /// background estimated from `in_dir` when background subtraction is on.
+    pub(crate) fn prepare(&self) -> Result<FrameParams> {
+        let mut params = self.frame_params();
+        if let Some(mask) = &self.mask {
+            params.mask = Some(load_mask(mask, &params)?);
+        }
+        if let Some(bg) = self.background {
+            params.background = Some(estimate_background(self.in_dir, &params, bg)?);
+        }
+        Ok(params)
+    }
+
+    /// Preprocessing steps as recorded in `Payload::preprocess`.
+    fn preprocess_steps(&self) -> Vec<String> {
+        let mut steps: Vec<String> = self.blur.iter().map(|s| format!("blur:{s}")).collect();
+        steps.extend(self.adjust.steps());
+        if let Some(eq) = &self.equalize {
+            steps.push(format!("equalize:{eq}"));
+        }
+        if let Some(mask) = self.mask.as_deref().and_then(Path::file_name) {
+            steps.push(format!("mask:{}", mask.to_string_lossy()));
+        }
+        steps
+    }
+}
+
+/// Decodes one PNG frame and binarizes it (1 = on), returning the frame and the threshold used.
+pub(crate) fn binarize_png(
+    fp: &Path,
+    bytes: &[u8],
+    params: &FrameParams,
+) -> Result<(Vec<u8>, f32)> {
+    let img =
+        image::load_from_memory(bytes).map_err(BadAppleError::parse(fp.display().to_string()))?;
+    binarize_image(&img, fp, params)
+}
+
+pub(crate) fn check_size(fp: &Path, (iw, ih): (u32, u32), params: &FrameParams) -> Result<()> {
+    if iw != params.w || ih != params.h {
+        return Err(BadAppleError::SizeMismatch {
+            frame: fp.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            got: (iw, ih),
+            expected: (params.w, params.h),
+        });
+    }
+    Ok(())
+}
+
+/// Binarizes an already decoded frame (`fp` is only used in errors).
+pub(crate) fn binarize_image(
+    img: &DynamicImage,
+    fp: &Path,
+    params: &FrameParams,
+) -> Result<(Vec<u8>, f32)> {
+    let gray = img.to_luma8();
+    check_size(fp, gray.dimensions(), params)?;
+
+    Ok(binarize_gray(gray.as_raw(), params))
+}
+
+/// Median of `bg.samples` frames spread evenly over the input (after
+/// preprocessing, like the frames it is compared with).
+fn estimate_background(
+    in_dir: &Path,
+    params: &FrameParams,
+    bg: Background,
+) -> Result<BackgroundModel> {
+    let frames = PngFrames::open(in_dir)?;
+    let step = (frames.len() / bg.samples as usize).max(1);
+
+    let mut samples = Vec::with_capacity(bg.samples as usize);
+    for png in frames.step_by(step).take(bg.samples as usize) {
+        let (fp, bytes) = png?;
+        let img = image::load_from_memory(&bytes)
+            .map_err(BadAppleError::parse(fp.display().to_string()))?;
         let gray = img.to_luma8();
-        let (iw, ih) = gray.dimensions();
-
-        if iw != opts.w || ih != opts.h {
-            anyhow::bail!(
-                "❌ Frame size mismatch in {}: got {}×{}, expected {}×{}",
-                fp.file_name().unwrap_or_default().to_string_lossy(),
-                iw,
-                ih,
-                opts.w,
-                opts.h
-            );
+        check_size(&fp, gray.dimensions(), params)?;
+        samples.push(params.preprocess(gray.as_raw()).into_owned());
+    }
+    info!("🧍 Background: median of {} frames", samples.len());
+
+    Ok(BackgroundModel::median(&samples, bg.threshold))
+}
+
+/// Reads a mask PNG: white (luma >= 128) = encode, black = forced off.
+fn load_mask(path: &Path, params: &FrameParams) -> Result<Arc<[u8]>> {
+    let bytes = fs::read(path).map_err(BadAppleError::io("reading mask", path))?;
+    let img = image::load_from_memory(&bytes)
+        .map_err(BadAppleError::parse(path.display().to_string()))?;
+    let gray = img.to_luma8();
+    check_size(path, gray.dimensions(), params)?;
+
+    let mask: Arc<[u8]> = gray.as_raw().iter().map(|&v| (v >= 128) as u8).collect();
+    let kept = mask.iter().filter(|&&keep| keep == 1).count();
+    info!(
+        "🎭 Mask: {} ({:.1}% encoded)",
+        path.display(),
+        kept as f64 * 100.0 / mask.len() as f64
+    );
+    Ok(mask)
+}
+
+/// Binarizes a luma frame with the mode `params` selects, then applies the
+/// mask. With `temporal` the frame also carries `binarize::NEAR` flags for
+/// the `TemporalFilter`.
+pub(crate) fn binarize_gray(buf: &[u8], params: &FrameParams) -> (Vec<u8>, f32) {
+    let (mut frame, th) = threshold_gray(buf, params);
+    if let Some(mask) = &params.mask {
+        for (px, &keep) in frame.iter_mut().zip(mask.iter()) {
+            *px *= keep;
         }
+    }
+    (frame, th)
+}
+
+fn threshold_gray(buf: &[u8], params: &FrameParams) -> (Vec<u8>, f32) {
+    let w = params.w as usize;
+    let pre = params.preprocess(buf);
+    let buf = &pre[..];
+
+    if let Some(bg) = &params.background {
+        // Foreground = differs from the background; `invert` flips as usual
+        let diff = bg.diff(buf);
+        let flip = params.invert as u8;
+        let mut frame: Vec<u8> = diff
+            .iter()
+            .map(|&d| (d > bg.threshold) as u8 ^ flip)
+            .collect();
+        let th = bg.threshold as f32;
+        if let Some(t) = params.temporal {
+            mark_near(&mut frame, &diff, |_| th, t.margin);
+        }
+        return (frame, th);
+    }
+    if let ThresholdMode::Tiles(tile) = params.threshold {
+        let ths = tile_thresholds(buf, w, tile as usize, params.th_mul);
+        let mut frame = binarize_each(buf, &ths, params.invert);
+        if let Some(t) = params.temporal {
+            mark_near(&mut frame, buf, |i| ths[i], t.margin);
+        }
+        return (frame, params.mean(buf) * params.th_mul);
+    }
+
+    let (mut frame, th) = match params.hysteresis {
+        Some(hyst) => binarize_hysteresis(buf, w, params.mean(buf), params.invert, hyst),
+        None => {
+            let th = params.mean(buf) * params.th_mul;
+            (binarize_cut(buf, th, params.invert), th)
+        }
+    };
+    if let Some(t) = params.temporal {
+        mark_near(&mut frame, buf, |_| th, t.margin);
+    }
+    (frame, th)
+}
+
+/// Binarizes a luma buffer against `mean * th_mul` (1 = on, darker than the
+/// threshold unless `invert`), returning the frame and the threshold used.
+/// This is the shared tail of every frame source.
+pub fn binarize_luma(buf: &[u8], invert: bool, th_mul: f32) -> (Vec<u8>, f32) {
+    let th = adaptive_threshold(buf) * th_mul;
+    (binarize_cut(buf, th, invert), th)
+}
+
+/// `v < th` per pixel (flipped by `invert`) for a float threshold.
+fn binarize_cut(buf: &[u8], th: f32, invert: bool) -> Vec<u8> {
+    // `v as f32 < th` for integer v is `v < ceil(th)`, so compare bytes
+    // against an integer cut instead of converting every pixel to float
+    let cut = th.ceil().clamp(0.0, 256.0) as u16;
+    let flip = invert as u8;
+    let mut frame = vec![0u8; buf.len()];
+
+    if cut > u8::MAX as u16 {
+        frame.fill(1 ^ flip);
+        return frame;
+    }
 
-        let buf = gray.as_raw();
-        let th = adaptive_threshold(buf) * opts.th_mul;
-        th_sum += th as f64;
+    // Branch-free byte compare, vectorized by LLVM (16-32 pixels per instruction)
+    let cut = cut as u8;
+    for (o, &v) in frame.iter_mut().zip(buf) {
+        *o = (v < cut) as u8 ^ flip;
+    }
 
-        let mut frame = vec![0u8; buf.len()];
-        for (pi, &v) in buf.iter().enumerate() {
-            let mut on = (v as f32) < th;
-            if opts.invert {
-                on = !on;
+    frame
+}
+
+impl Payload {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let json =
+            fs::read_to_string(path).map_err(BadAppleError::io("reading payload", path))?;
+        let payload: Payload =
+            serde_json::from_str(&json).map_err(BadAppleError::parse(path.display().to_string()))?;
+        payload.migrate()
+    }
+
+    /// Parses rectFrames.json text, upgrading older versions (see `migrate`).
+    pub fn from_json(json: &str) -> Result<Self> {
+        let payload: Payload =
+            serde_json::from_str(json).map_err(BadAppleError::parse("payload JSON"))?;
+        payload.migrate()
+    }
+
+    /// Upgrades the payload to `PAYLOAD_VERSION`, one version at a time.
+    /// Payloads newer than this build are rejected instead of misread.
+    pub fn migrate(mut self) -> Result<Self> {
+        if self.version > PAYLOAD_VERSION {
+            return Err(BadAppleError::UnsupportedVersion {
+                found: self.version,
+                supported: PAYLOAD_VERSION,
+            });
+        }
+
+        while self.version < PAYLOAD_VERSION {
+            match self.version {
+                // Unversioned: same layout, but frames_count was never checked
+                0 => self.frames_count = self.rect_frames.len(),
+                v => unreachable!("no migration from payload version {v}"),
             }
-            frame[pi] = if on { 1 } else { 0 };
+            self.version += 1;
         }
 
-        let rects = merge_frame_to_rects(&frame, opts.w as usize, opts.h as usize);
-        rect_frames.push(rects);
+        Ok(self)
+    }
+
+    /// Rasterizes frame `i` into a 1/0 pixel buffer (`width`×`height`).
+    pub fn rasterize_frame(&self, i: usize) -> Vec<u8> {
+        rasterize_rects(
+            &self.rect_frames[i],
+            self.width as usize,
+            self.height as usize,
+        )
+    }
+}
+
+pub fn convert_rectframes(opts: ConvertRectframesOpts<'_>) -> Result<Payload> {
+    let opts = ConvertRectframesOpts {
+        sizes: Vec::new(),
+        ..opts
+    };
+    Ok(convert_rectframes_sizes(opts)?.swap_remove(0))
+}
 
-        if i % 200 == 0 {
-            println!("  ✔ {}/{}", i, files.len());
+/// `convert_rectframes` at `opts.w`×`opts.h` and at every `opts.sizes`, in
+/// that order, from one decode pass: the smaller payloads are box-downscaled
+/// from the binarized (and temporally filtered) frames before merging.
+#[instrument(skip_all, fields(in_dir = %opts.in_dir.display()))]
+pub fn convert_rectframes_sizes(opts: ConvertRectframesOpts<'_>) -> Result<Vec<Payload>> {
+    let frames = PngFrames::open(opts.in_dir)?;
+    let (params, mut acc) = Accumulator::prepare(&opts, Some(frames.len()))?;
+    acc.sizes = opts.sizes.clone();
+
+    #[cfg(feature = "gpu")]
+    let gpu = opts.gpu.then(crate::gpu::GpuBinarizer::new).flatten();
+
+    convert_frames(
+        frames,
+        &opts,
+        |(fp, bytes)| {
+            #[cfg(feature = "gpu")]
+            if let Some(gpu) = &gpu {
+                return gpu.binarize_png(&fp, &bytes, &params);
+            }
+            binarize_png(&fp, &bytes, &params)
+        },
+        &mut acc,
+    )?;
+    Ok(acc.finish_sizes(&opts))
+}
+
+/// Binarizes (`decode`) and merges every frame into `acc`, in order: inline
+/// for `threads == 1`, otherwise through the decoder/encoder `pipeline`.
+fn convert_frames<T, D>(
+    frames: impl Iterator<Item = Result<T>>,
+    opts: &ConvertRectframesOpts<'_>,
+    decode: D,
+    acc: &mut Accumulator,
+) -> Result<()>
+where
+    T: Send,
+    D: Fn(T) -> Result<(Vec<u8>, f32)> + Sync,
+{
+    let (w, h, off_rects) = (opts.w as usize, opts.h as usize, opts.off_rects);
+    let threads = pipeline::auto_threads(opts.threads);
+    let sizes = acc.sizes.clone();
+    let merge = |frame: &[u8]| {
+        let scaled = sizes
+            .iter()
+            .map(|&(sw, sh)| {
+                let (sw, sh) = (sw as usize, sh as usize);
+                merge_frame_to_rects(&downscale(frame, w, h, sw, sh), sw, sh, off_rects)
+            })
+            .collect();
+        (merge_frame_to_rects(frame, w, h, off_rects), scaled)
+    };
+
+    if threads == 1 {
+        for item in frames {
+            if let Some(token) = &opts.cancel {
+                token.check()?;
+            }
+            let (mut frame, th) = decode(item?)?;
+            acc.filter(&mut frame);
+            let (rects, scaled) = merge(&frame);
+            acc.push(rects, scaled, th);
         }
+        return Ok(());
     }
 
-    let avg_th = (th_sum / files.len() as f64).round().clamp(0.0, 255.0) as u32;
+    if acc.temporal.is_some() {
+        // The filter needs frames in order, so it and merging move to the sink
+        return pipeline::run(
+            frames,
+            threads,
+            opts.cancel.as_ref(),
+            decode,
+            |decoded| decoded,
+            |(mut frame, th)| {
+                acc.filter(&mut frame);
+                let (rects, scaled) = merge(&frame);
+                acc.push(rects, scaled, th)
+            },
+        );
+    }
 
-    Ok(Payload {
-        width: opts.w,
-        height: opts.h,
-        fps: opts.fps,
-        threshold: avg_th,
-        th_mul: opts.th_mul,
-        invert: opts.invert,
-        frames_count: rect_frames.len(),
-        rect_frames,
-    })
+    pipeline::run(
+        frames,
+        threads,
+        opts.cancel.as_ref(),
+        decode,
+        |(frame, th): (Vec<u8>, f32)| (merge(&frame), th),
+        |((rects, scaled), th)| acc.push(rects, scaled, th),
+    )
 }
 
-pub fn convert_rectframes_to_file(opts: ConvertRectframesOpts<'_>, out_file: &Path) -> Result<()> {
-    let payload = convert_rectframes(opts)?;
+/// Converts frames that are already decoded to luma (one byte per pixel,
+/// `opts.w * opts.h` bytes each), e.g. `source::RawFrames` over ffmpeg's
+/// rawvideo output. `opts.in_dir` is not read.
+#[instrument(skip_all)]
+pub fn convert_luma_frames<I>(frames: I, opts: &ConvertRectframesOpts<'_>) -> Result<Payload>
+where
+    I: IntoIterator<Item = Result<Vec<u8>>>,
+{
+    if opts.background.is_some() {
+        return Err(BadAppleError::InvalidOpts(
+            "background subtraction needs a frames directory or archive, not a stream".into(),
+        ));
+    }
+    let (params, mut acc) = Accumulator::prepare(opts, None)?;
+    let expected = opts.w as usize * opts.h as usize;
+
+    convert_frames(
+        frames
+            .into_iter()
+            .enumerate()
+            .map(|(i, luma)| luma.map(|l| (i, l))),
+        opts,
+        |(i, luma): (usize, Vec<u8>)| {
+            if luma.len() != expected {
+                return Err(BadAppleError::ParseError {
+                    what: format!("luma frame {i}"),
+                    source: format!("{} bytes, expected {expected}", luma.len()).into(),
+                });
+            }
+            Ok(binarize_gray(&luma, &params))
+        },
+        &mut acc,
+    )?;
+    Ok(acc.finish(opts))
+}
 
+/// Re-binarizes the frames at `indices` (in `frame_order`) of `opts.in_dir`
+/// and splices them into `payload`, a `frame_stats` conversion of the same
+/// directory with the same `opts`, so a few edited frames don't cost a full
+/// conversion (`convert --watch`). `threshold` stays the average of the
+/// updated `frame_thresholds`. Frames that depend on other frames
+/// (`temporal`, `interpolate`, `background`) can only be redone by
+/// converting again, and so can a directory that gained or lost frames.
+#[instrument(skip_all, fields(in_dir = %opts.in_dir.display(), frames = indices.len()))]
+pub fn reconvert_frames(
+    payload: &mut Payload,
+    opts: &ConvertRectframesOpts<'_>,
+    indices: &[usize],
+) -> Result<()> {
+    if opts.temporal.is_some() || opts.interpolate.is_some() || opts.background.is_some() {
+        return Err(BadAppleError::InvalidOpts(
+            "temporal, interpolate and background frames depend on other frames, \
+             convert them all again"
+                .into(),
+        ));
+    }
+    let files = list_frames(opts.in_dir)?;
+    let n = payload.rect_frames.len();
+    if files.len() != n {
+        return Err(BadAppleError::InvalidOpts(format!(
+            "{} frames in {}, the payload has {n}",
+            files.len(),
+            opts.in_dir.display()
+        )));
+    }
+    if let Some(&i) = indices.iter().find(|&&i| i >= n) {
+        return Err(BadAppleError::InvalidOpts(format!("no frame {i}, the payload has {n}")));
+    }
+    let Some(thresholds) = payload.frame_thresholds.as_mut().filter(|t| t.len() == n) else {
+        return Err(BadAppleError::InvalidOpts(
+            "the payload has no frame_thresholds (convert with frame_stats)".into(),
+        ));
+    };
+
+    let params = opts.prepare()?;
+    let (w, h) = (opts.w as usize, opts.h as usize);
+    for &i in indices {
+        let fp = &files[i];
+        let bytes = fs::read(fp).map_err(BadAppleError::io("reading", fp))?;
+        let (frame, th) = binarize_png(fp, &bytes, &params)?;
+        let mut rects = merge_frame_to_rects(&frame, w, h, opts.off_rects);
+        rects.sort_by_key(|r| (r.y, r.x));
+        debug!(frame = i, threshold = th, rects = rects.len(), "frame reconverted");
+
+        thresholds[i] = th;
+        if let Some(count) = payload.frame_rect_counts.as_mut().and_then(|c| c.get_mut(i)) {
+            *count = rects.len();
+        }
+        payload.rect_frames[i] = rects;
+    }
+    payload.threshold = average_threshold(thresholds);
+
+    Ok(())
+}
+
+/// `Payload::threshold`: the mean of the per-frame thresholds, rounded.
+fn average_threshold(thresholds: &[f32]) -> u32 {
+    let n = thresholds.len().max(1);
+    let sum: f64 = thresholds.iter().map(|&th| th as f64).sum();
+    (sum / n as f64).round().clamp(0.0, 255.0) as u32
+}
+
+/// Collects merged frames and their thresholds, logging progress.
+struct Accumulator {
+    rect_frames: Vec<Vec<Rect>>,
+    /// Extra output sizes and, per size, their frames so far
+    sizes: Vec<(u32, u32)>,
+    scaled: Vec<Vec<Vec<Rect>>>,
+    thresholds: Vec<f32>,
+    /// None for streamed sources
+    total: Option<usize>,
+    temporal: Option<TemporalFilter>,
+    progress: Option<ProgressFn>,
+}
+
+impl Accumulator {
+    /// The frame params (`ConvertRectframesOpts::prepare`) and an accumulator
+    /// for `total` frames: the setup every converter shares, sync or async,
+    /// so their options can't drift apart.
+    fn prepare(
+        opts: &ConvertRectframesOpts<'_>,
+        total: Option<usize>,
+    ) -> Result<(FrameParams, Self)> {
+        let params = opts.prepare()?;
+        Ok((params, Self::start(opts, total)))
+    }
+
+    fn start(opts: &ConvertRectframesOpts<'_>, total: Option<usize>) -> Self {
+        match total {
+            Some(n) => info!("🎞️  Frames: {}", n),
+            None => info!("🎞️  Frames: streaming"),
+        }
+        info!("📐 {}×{} @ {}fps", opts.w, opts.h, opts.fps);
+        info!("🔁 Invert: {}", opts.invert);
+        info!("🎚️  Threshold multiplier: {}", opts.th_mul);
+
+        Self {
+            rect_frames: Vec::with_capacity(total.unwrap_or(0)),
+            sizes: Vec::new(),
+            scaled: Vec::new(),
+            thresholds: Vec::with_capacity(total.unwrap_or(0)),
+            total,
+            temporal: opts.temporal.map(TemporalFilter::new),
+            progress: opts.progress.clone(),
+        }
+    }
+
+    /// Runs the temporal filter, if any, over the next frame in order.
+    fn filter(&mut self, frame: &mut [u8]) {
+        if let Some(t) = &mut self.temporal {
+            t.apply(frame);
+        }
+    }
+
+    fn push(&mut self, mut rects: Vec<Rect>, scaled: Vec<Vec<Rect>>, th: f32) {
+        // Canonical order whatever the merge strategy (already true for runs)
+        rects.sort_by_key(|r| (r.y, r.x));
+        let i = self.rect_frames.len();
+        debug!(frame = i, threshold = th, rects = rects.len(), "frame merged");
+        if let Some(progress) = &self.progress {
+            progress.report(&Progress {
+                done: i + 1,
+                total: self.total,
+                threshold: th,
+                rects: rects.len(),
+            });
+        } else if i.is_multiple_of(200) {
+            match self.total {
+                Some(n) => info!("  ✔ {}/{}", i, n),
+                None => info!("  ✔ {}", i),
+            }
+        }
+
+        self.thresholds.push(th);
+        self.rect_frames.push(rects);
+        self.scaled.resize_with(scaled.len(), Vec::new);
+        for (frames, mut rects) in self.scaled.iter_mut().zip(scaled) {
+            rects.sort_by_key(|r| (r.y, r.x));
+            frames.push(rects);
+        }
+    }
+
+    fn finish(self, opts: &ConvertRectframesOpts<'_>) -> Payload {
+        self.finish_sizes(opts).swap_remove(0)
+    }
+
+    /// The base payload, then one per extra size.
+    fn finish_sizes(self, opts: &ConvertRectframesOpts<'_>) -> Vec<Payload> {
+        let avg_th = average_threshold(&self.thresholds);
+        let thresholds = opts.frame_stats.then_some(self.thresholds);
+
+        let mut scaled = self.scaled;
+        scaled.resize_with(self.sizes.len(), Vec::new);
+        let outputs = std::iter::once(((opts.w, opts.h), self.rect_frames))
+            .chain(self.sizes.into_iter().zip(scaled));
+
+        outputs
+            .map(|((width, height), rect_frames)| {
+                let frame_rect_counts = opts
+                    .frame_stats
+                    .then(|| rect_frames.iter().map(Vec::len).collect());
+                let payload = Payload {
+                    version: PAYLOAD_VERSION,
+                    width,
+                    height,
+                    fps: opts.fps,
+                    threshold: avg_th,
+                    th_mul: opts.th_mul,
+                    invert: opts.invert,
+                    frames_count: rect_frames.len(),
+                    rect_frames,
+                    frame_thresholds: thresholds.clone(),
+                    frame_rect_counts,
+                    preprocess: opts.preprocess_steps(),
+                    off_rects: opts.off_rects,
+                    interpolated: None,
+                };
+                match opts.interpolate {
+                    Some(mode) => double_frames(payload, mode),
+                    None => payload,
+                }
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn part_path(out_file: &Path) -> PathBuf {
+    let mut part = out_file.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Compact JSON, or indented with `pretty`.
+pub(crate) fn to_json<T: Serialize + ?Sized>(
+    value: &T,
+    pretty: bool,
+) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+pub fn write_payload_json(payload: &Payload, out_file: &Path) -> Result<()> {
+    write_payload_json_with(payload, out_file, false)
+}
+
+/// `write_payload_json`, indented when `pretty`.
+#[instrument(skip_all, fields(path = %out_file.display()))]
+pub fn write_payload_json_with(payload: &Payload, out_file: &Path, pretty: bool) -> Result<()> {
     if let Some(parent) = out_file.parent() {
-        fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent).map_err(BadAppleError::io("creating dir", parent))?;
     }
 
-    fs::write(out_file, serde_json::to_string(&payload)?)?;
+    // Write next to the target and rename, so an interrupted write never
+    // leaves a truncated rectFrames.json behind
+    let part = part_path(out_file);
 
-    println!("✅ rectFrames.json written: {}", out_file.display());
-    println!("🧮 frames_count: {}", payload.frames_count);
-    println!("🎚️ avg threshold: {}", payload.threshold);
+    let written = to_json(payload, pretty)
+        .map_err(std::io::Error::from)
+        .and_then(|json| fs::write(&part, json))
+        .and_then(|_| fs::rename(&part, out_file));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&part);
+        return Err(BadAppleError::io("writing", out_file)(e));
+    }
+    debug!("payload written");
 
     Ok(())
 }
+
+pub fn convert_rectframes_to_file(
+    opts: ConvertRectframesOpts<'_>,
+    out_file: &Path,
+) -> Result<Payload> {
+    let (cancel, pretty) = (opts.cancel.clone(), opts.pretty);
+    let payload = convert_rectframes(opts)?;
+
+    if let Some(token) = &cancel {
+        token.check()?;
+    }
+    write_payload_json_with(&payload, out_file, pretty)?;
+
+    log_written(&payload, out_file);
+    Ok(payload)
+}
+
+fn log_written(payload: &Payload, out_file: &Path) {
+    info!(
+        out = %out_file.display(),
+        "✅ rectFrames.json written: {}",
+        out_file.display()
+    );
+    info!(
+        frames_count = payload.frames_count,
+        "🧮 frames_count: {}",
+        payload.frames_count
+    );
+    info!(
+        threshold = payload.threshold,
+        "🎚️ avg threshold: {}",
+        payload.threshold
+    );
+}
+
+/// `convert_rectframes` for async callers (feature "async", tokio): directory
+/// listing and PNG reads use `tokio::fs`, decoding and rect merging run on the
+/// blocking pool, so the executor never stalls on a multi-minute conversion.
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+#[instrument(skip_all, fields(in_dir = %opts.in_dir.display()))]
+pub async fn convert_rectframes_async(opts: ConvertRectframesOpts<'_>) -> Result<Payload> {
+    #[cfg(feature = "archive")]
+    if opts.in_dir.is_file() && crate::source::ArchiveKind::from_path(opts.in_dir).is_some() {
+        // zip/tar readers are blocking, so the whole archive converts on the blocking pool
+        let in_dir = opts.in_dir.to_path_buf();
+        let name = in_dir.clone();
+        let detached = opts.detached();
+        return tokio::task::spawn_blocking(move || {
+            convert_rectframes(ConvertRectframesOpts {
+                in_dir: &in_dir,
+                ..detached
+            })
+        })
+        .await
+        .map_err(|e| BadAppleError::io("decoding", name)(std::io::Error::other(e)))?;
+    }
+
+    let files = list_frames_async(opts.in_dir).await?;
+    // Reads the mask and, for background subtraction, a pass over sample
+    // frames: blocking like the rest of the decoding
+    let total = files.len();
+    let detached = opts.detached();
+    let in_dir = opts.in_dir.to_path_buf();
+    let (params, mut acc) = tokio::task::spawn_blocking(move || {
+        let opts = ConvertRectframesOpts {
+            in_dir: &in_dir,
+            ..detached
+        };
+        Accumulator::prepare(&opts, Some(total))
+    })
+    .await
+    .map_err(|e| BadAppleError::io("decoding", opts.in_dir)(std::io::Error::other(e)))??;
+
+    for fp in files {
+        if let Some(token) = &opts.cancel {
+            token.check()?;
+        }
+
+        let bytes = tokio::fs::read(&fp)
+            .await
+            .map_err(BadAppleError::io("reading", &fp))?;
+
+        let name = fp.clone();
+        let params = params.clone();
+        // The filter carries state between frames, so it travels with each task
+        let mut temporal = acc.temporal.take();
+        let (rects, th, temporal) = tokio::task::spawn_blocking(move || {
+            let (mut frame, th) = binarize_png(&fp, &bytes, &params)?;
+            if let Some(t) = &mut temporal {
+                t.apply(&mut frame);
+            }
+            Ok::<_, BadAppleError>((
+                merge_frame_to_rects(&frame, params.w as usize, params.h as usize, opts.off_rects),
+                th,
+                temporal,
+            ))
+        })
+        .await
+        .map_err(|e| BadAppleError::io("decoding", name)(std::io::Error::other(e)))??;
+
+        acc.temporal = temporal;
+        acc.push(rects, Vec::new(), th);
+    }
+
+    Ok(acc.finish(&opts))
+}
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+async fn list_frames_async(in_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dir = match tokio::fs::read_dir(in_dir).await {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(BadAppleError::MissingInput {
+                what: "Input directory",
+                path: in_dir.to_path_buf(),
+            });
+        }
+        Err(e) => return Err(BadAppleError::io("reading", in_dir)(e)),
+    };
+
+    let mut files = Vec::new();
+    while let Some(entry) = dir
+        .next_entry()
+        .await
+        .map_err(BadAppleError::io("reading", in_dir))?
+    {
+        files.push(entry.path());
+    }
+
+    sorted_pngs(in_dir, files)
+}
+
+/// Async `convert_rectframes_to_file`, writing the JSON with `tokio::fs`.
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub async fn convert_rectframes_to_file_async(
+    opts: ConvertRectframesOpts<'_>,
+    out_file: &Path,
+) -> Result<Payload> {
+    let (cancel, pretty) = (opts.cancel.clone(), opts.pretty);
+    let payload = convert_rectframes_async(opts).await?;
+
+    if let Some(token) = &cancel {
+        token.check()?;
+    }
+
+    if let Some(parent) = out_file.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(BadAppleError::io("creating dir", parent))?;
+    }
+
+    let part = part_path(out_file);
+    let written = async {
+        let json = to_json(&payload, pretty)?;
+        tokio::fs::write(&part, json).await?;
+        tokio::fs::rename(&part, out_file).await
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(BadAppleError::io("writing", out_file)(e));
+    }
+
+    log_written(&payload, out_file);
+    Ok(payload)
+}
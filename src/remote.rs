@@ -0,0 +1,235 @@
+// src/lib/remote.rs
+// HTTP(S) frame source (feature "http"). Downloads a frames archive, a video or
+// a numbered PNG sequence into a local cache directory and returns the local
+// path, which the converters then read like any other input. Cached downloads
+// are reused, so CI runs and render farms only fetch once.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::error::{BadAppleError, Result};
+use crate::rectframes::part_path;
+
+/// True for `http://` and `https://` inputs.
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoteSource<'a> {
+    /// File URL, or a frame sequence with a printf counter (`.../frame_%05d.png`)
+    pub url: &'a str,
+    pub cache_dir: &'a Path,
+    /// Extra attempts after a network error or a 5xx/429 response
+    pub retries: u32,
+}
+
+impl<'a> RemoteSource<'a> {
+    /// 3 retries by default.
+    pub fn new(url: &'a str, cache_dir: &'a Path) -> Self {
+        Self {
+            url,
+            cache_dir,
+            retries: 3,
+        }
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Downloads the input unless it is already cached, returning its local
+    /// path: a file for plain URLs, a directory of PNGs for sequences.
+    pub fn fetch(&self) -> Result<PathBuf> {
+        fs::create_dir_all(self.cache_dir)
+            .map_err(BadAppleError::io("creating dir", self.cache_dir))?;
+
+        match Counter::find(self.url)? {
+            Some(counter) => self.fetch_sequence(&counter),
+            None => self.fetch_file(),
+        }
+    }
+
+    fn fetch_file(&self) -> Result<PathBuf> {
+        let name = self
+            .url
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .unwrap_or("download");
+        // Strip the query string, keep the extension (archive detection uses it)
+        let name = name.split(['?', '#']).next().unwrap_or(name);
+        let dest = self
+            .cache_dir
+            .join(format!("{}-{}", cache_key(self.url), name));
+
+        if dest.is_file() {
+            info!("📦 Cached {}", dest.display());
+            return Ok(dest);
+        }
+
+        info!("🌐 Downloading {}", self.url);
+        if !self.download(self.url, &dest)? {
+            return Err(BadAppleError::MissingInput {
+                what: "Remote file",
+                path: PathBuf::from(self.url),
+            });
+        }
+        info!("📦 Saved {}", dest.display());
+        Ok(dest)
+    }
+
+    /// Fetches frame 1, 2, … until the server answers 404. The directory is
+    /// only marked complete at the end, so an interrupted run resumes.
+    fn fetch_sequence(&self, counter: &Counter) -> Result<PathBuf> {
+        let dir = self.cache_dir.join(cache_key(self.url));
+        let complete = dir.join(".complete");
+
+        if complete.is_file() {
+            info!("📦 Cached {}", dir.display());
+            return Ok(dir);
+        }
+
+        fs::create_dir_all(&dir).map_err(BadAppleError::io("creating dir", &dir))?;
+        info!("🌐 Downloading frames from {}", self.url);
+
+        let mut n = 1;
+        loop {
+            let dest = dir.join(format!("frame_{n:05}.png"));
+            if !dest.is_file() && !self.download(&counter.url(self.url, n), &dest)? {
+                break;
+            }
+            if n.is_multiple_of(200) {
+                info!("  ✔ {}", n);
+            }
+            n += 1;
+        }
+
+        if n == 1 {
+            return Err(BadAppleError::MissingInput {
+                what: "Remote frames",
+                path: PathBuf::from(counter.url(self.url, 1)),
+            });
+        }
+
+        fs::write(&complete, b"").map_err(BadAppleError::io("writing", &complete))?;
+        info!("📦 Saved {} frames to {}", n - 1, dir.display());
+        Ok(dir)
+    }
+
+    /// GETs `url` into `dest` (through a .part file), retrying transient
+    /// failures with exponential backoff. `Ok(false)` means 404.
+    fn download(&self, url: &str, dest: &Path) -> Result<bool> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let err = match self.try_download(url, dest) {
+                Ok(found) => return Ok(found),
+                Err(e) => e,
+            };
+
+            if !is_transient(&err) || attempt > self.retries {
+                return Err(BadAppleError::Download {
+                    url: url.to_string(),
+                    attempts: attempt,
+                    source: err.into(),
+                });
+            }
+
+            let delay = Duration::from_millis(500 << (attempt - 1).min(6));
+            warn!("{} failed ({}), retrying in {:?}", url, err, delay);
+            thread::sleep(delay);
+        }
+    }
+
+    fn try_download(&self, url: &str, dest: &Path) -> std::result::Result<bool, ureq::Error> {
+        let response = match ureq::get(url).call() {
+            Ok(r) => r,
+            Err(ureq::Error::StatusCode(404)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        let part = part_path(dest);
+        let written = fs::File::create(&part)
+            .and_then(|mut file| {
+                io::copy(&mut response.into_body().into_reader(), &mut file)?;
+                file.flush()
+            })
+            .and_then(|_| fs::rename(&part, dest));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&part);
+            return Err(e.into());
+        }
+
+        Ok(true)
+    }
+}
+
+fn is_transient(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::StatusCode(code) => *code == 429 || *code >= 500,
+        ureq::Error::Io(_)
+        | ureq::Error::Timeout(_)
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::HostNotFound => true,
+        _ => false,
+    }
+}
+
+/// FNV-1a of the URL: stable across runs and Rust versions (unlike `DefaultHasher`).
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// A `%d` / `%05d` frame counter inside a URL.
+struct Counter {
+    start: usize,
+    end: usize,
+    width: usize,
+}
+
+impl Counter {
+    /// The last `%d` / `%0Nd` in `url`, None for a plain file URL. Every `%`
+    /// is looked at, since percent-escapes (`%20`) can come before the counter;
+    /// a `%` that is neither is an error.
+    fn find(url: &str) -> Result<Option<Self>> {
+        let mut counter = None;
+        for (start, _) in url.match_indices('%') {
+            let rest = &url[start + 1..];
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let padded = digits == 0 || rest.starts_with('0');
+            if padded && rest.as_bytes().get(digits) == Some(&b'd') {
+                counter = Some(Self {
+                    start,
+                    end: start + 1 + digits + 1,
+                    width: rest[..digits].parse().unwrap_or(0),
+                });
+            } else if rest.bytes().take(2).filter(u8::is_ascii_hexdigit).count() != 2 {
+                return Err(BadAppleError::InvalidOpts(format!(
+                    "{url}: `%` at {start} is neither a frame counter (%d, %05d) nor an escape"
+                )));
+            }
+        }
+        Ok(counter)
+    }
+
+    fn url(&self, url: &str, n: usize) -> String {
+        format!(
+            "{}{:0width$}{}",
+            &url[..self.start],
+            n,
+            &url[self.end..],
+            width = self.width
+        )
+    }
+}
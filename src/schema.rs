@@ -0,0 +1,90 @@
+// src/lib/schema.rs
+// JSON Schemas (draft 2020-12, via schemars) for the JSON this crate emits,
+// so front ends can validate and codegen types against the exact format.
+
+use anyhow::{Context, Result};
+use schemars::{schema_for, Schema};
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use crate::rectframes::{Payload, Rect};
+use crate::subs::{Cue, SubsBundle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// rectFrames.json
+    Payload,
+    /// One merged rect of a frame
+    Rect,
+    /// One subtitle cue of transcript_*.json (the file is an array of these)
+    Cue,
+    /// subtitles.json, every language's cues in one file
+    Bundle,
+}
+
+impl SchemaKind {
+    pub const ALL: [SchemaKind; 4] = [
+        SchemaKind::Payload,
+        SchemaKind::Rect,
+        SchemaKind::Cue,
+        SchemaKind::Bundle,
+    ];
+
+    pub fn schema(self) -> Schema {
+        match self {
+            SchemaKind::Payload => schema_for!(Payload),
+            SchemaKind::Rect => schema_for!(Rect),
+            SchemaKind::Cue => schema_for!(Cue),
+            SchemaKind::Bundle => schema_for!(SubsBundle),
+        }
+    }
+
+    /// File name used when writing every schema into a directory.
+    pub fn file_name(self) -> String {
+        format!("{self}.schema.json")
+    }
+}
+
+impl FromStr for SchemaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "payload" => Ok(SchemaKind::Payload),
+            "rect" => Ok(SchemaKind::Rect),
+            "cue" => Ok(SchemaKind::Cue),
+            "bundle" => Ok(SchemaKind::Bundle),
+            _ => Err(format!("unknown schema {s:?} (expected payload|rect|cue|bundle)")),
+        }
+    }
+}
+
+impl fmt::Display for SchemaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SchemaKind::Payload => "payload",
+            SchemaKind::Rect => "rect",
+            SchemaKind::Cue => "cue",
+            SchemaKind::Bundle => "bundle",
+        })
+    }
+}
+
+pub fn schema_json(kind: SchemaKind) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&kind.schema())?)
+}
+
+/// Writes `<kind>.schema.json` for every kind into `dir`, returning the paths.
+pub fn write_schema_dir(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed creating schema dir: {}", dir.display()))?;
+
+    SchemaKind::ALL
+        .iter()
+        .map(|&kind| {
+            let path = dir.join(kind.file_name());
+            fs::write(&path, schema_json(kind)?)
+                .with_context(|| format!("Failed writing schema: {}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+}
@@ -0,0 +1,324 @@
+// src/lib/source.rs
+// Where frames come from: PNGs in a directory or an archive (`PngFrames`,
+// behind `convert_rectframes`), or raw luma on a stream (`RawFrames`, fed to
+// `convert_luma_frames`).
+
+use std::{
+    cmp::Ordering,
+    fs,
+    io::{ErrorKind, Read},
+    path::{Path, PathBuf},
+    vec,
+};
+
+use crate::error::{BadAppleError, Result};
+use crate::rectframes::list_frames;
+
+/// Archive layouts accepted in place of a frames directory (feature "archive").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Picks the layout from the file name (.zip, .tar, .tar.gz / .tgz).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Frame order for every source: file names compared with digit runs as
+/// numbers (so unpadded `frame_9.png` comes before `frame_10.png`), ties broken
+/// on the raw bytes. Independent of locale, filesystem and archive entry order.
+pub(crate) fn frame_order(a: &Path, b: &Path) -> Ordering {
+    let (x, y) = (
+        a.as_os_str().as_encoded_bytes(),
+        b.as_os_str().as_encoded_bytes(),
+    );
+    let (mut i, mut j) = (0, 0);
+
+    while i < x.len() && j < y.len() {
+        if x[i].is_ascii_digit() && y[j].is_ascii_digit() {
+            let (di, dj) = (digit_run(&x[i..]), digit_run(&y[j..]));
+            let (ni, nj) = (trim_zeros(&x[i..i + di]), trim_zeros(&y[j..j + dj]));
+            let ord = ni.len().cmp(&nj.len()).then_with(|| ni.cmp(nj));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            i += di;
+            j += dj;
+        } else {
+            if x[i] != y[j] {
+                return x[i].cmp(&y[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    (x.len() - i).cmp(&(y.len() - j)).then_with(|| x.cmp(y))
+}
+
+fn digit_run(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&b| b == b'0').count();
+    &digits[zeros..]
+}
+
+/// Encoded PNG frames in `frame_order`, yielded as `(name, bytes)`.
+pub(crate) struct PngFrames {
+    inner: PngFramesInner,
+    len: usize,
+}
+
+enum PngFramesInner {
+    Dir(vec::IntoIter<PathBuf>),
+    #[cfg(feature = "archive")]
+    Zip {
+        archive: zip::ZipArchive<fs::File>,
+        entries: vec::IntoIter<(PathBuf, usize)>,
+        path: PathBuf,
+    },
+    // tar can only be read front to back, so its (small) PNGs are buffered
+    #[cfg(feature = "archive")]
+    Tar(vec::IntoIter<(PathBuf, Vec<u8>)>),
+}
+
+impl PngFrames {
+    /// Opens `in_dir`, which may also be a .zip / .tar(.gz) with feature "archive".
+    pub(crate) fn open(in_dir: &Path) -> Result<Self> {
+        #[cfg(feature = "archive")]
+        if in_dir.is_file()
+            && let Some(kind) = ArchiveKind::from_path(in_dir)
+        {
+            return Self::open_archive(in_dir, kind);
+        }
+
+        let files = list_frames(in_dir)?;
+        Ok(Self {
+            len: files.len(),
+            inner: PngFramesInner::Dir(files.into_iter()),
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    #[cfg(feature = "archive")]
+    fn open_archive(path: &Path, kind: ArchiveKind) -> Result<Self> {
+        let file = fs::File::open(path).map_err(BadAppleError::io("opening", path))?;
+
+        let inner = match kind {
+            ArchiveKind::Zip => {
+                let archive = zip::ZipArchive::new(file)
+                    .map_err(BadAppleError::parse(path.display().to_string()))?;
+                let mut entries: Vec<(PathBuf, usize)> = archive
+                    .file_names()
+                    .enumerate()
+                    .filter_map(|(i, name)| Some((PathBuf::from(name.ok()?.as_ref()), i)))
+                    .filter(|(name, _)| is_png(name))
+                    .collect();
+                entries.sort_by(|a, b| frame_order(&a.0, &b.0));
+                PngFramesInner::Zip {
+                    archive,
+                    entries: entries.into_iter(),
+                    path: path.to_path_buf(),
+                }
+            }
+            ArchiveKind::Tar => PngFramesInner::Tar(read_tar(file, path)?.into_iter()),
+            ArchiveKind::TarGz => {
+                let gz = flate2::read::GzDecoder::new(file);
+                PngFramesInner::Tar(read_tar(gz, path)?.into_iter())
+            }
+        };
+
+        let len = match &inner {
+            PngFramesInner::Zip { entries, .. } => entries.len(),
+            PngFramesInner::Tar(entries) => entries.len(),
+            PngFramesInner::Dir(files) => files.len(),
+        };
+        if len == 0 {
+            return Err(BadAppleError::MissingInput {
+                what: "PNG frames",
+                path: path.to_path_buf(),
+            });
+        }
+
+        Ok(Self { inner, len })
+    }
+}
+
+#[cfg(feature = "archive")]
+fn is_png(name: &Path) -> bool {
+    name.extension().map(|x| x == "png").unwrap_or(false)
+}
+
+/// Most an archive entry's header can make us reserve up front: a corrupt or
+/// crafted size would otherwise abort on allocation. Past it `read_to_end`
+/// grows the buffer as the data actually arrives.
+#[cfg(feature = "archive")]
+const MAX_PREALLOC: u64 = 64 << 20;
+
+#[cfg(feature = "archive")]
+fn entry_buf(size: u64) -> Vec<u8> {
+    Vec::with_capacity(size.min(MAX_PREALLOC) as usize)
+}
+
+#[cfg(feature = "archive")]
+fn read_tar<R: Read>(reader: R, path: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut frames = Vec::new();
+
+    for entry in archive
+        .entries()
+        .map_err(BadAppleError::io("reading", path))?
+    {
+        let mut entry = entry.map_err(BadAppleError::io("reading", path))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .map_err(BadAppleError::io("reading", path))?
+            .into_owned();
+        if !is_png(&name) {
+            continue;
+        }
+
+        let mut bytes = entry_buf(entry.size());
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(BadAppleError::io("reading", path.join(&name)))?;
+        frames.push((name, bytes));
+    }
+
+    frames.sort_by(|a, b| frame_order(&a.0, &b.0));
+    Ok(frames)
+}
+
+impl Iterator for PngFrames {
+    type Item = Result<(PathBuf, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            PngFramesInner::Dir(files) => {
+                let fp = files.next()?;
+                Some(
+                    fs::read(&fp)
+                        .map(|bytes| (fp.clone(), bytes))
+                        .map_err(BadAppleError::io("reading", &fp)),
+                )
+            }
+            #[cfg(feature = "archive")]
+            PngFramesInner::Zip {
+                archive,
+                entries,
+                path,
+            } => {
+                let (name, i) = entries.next()?;
+                let read = archive
+                    .by_index(i)
+                    .map_err(std::io::Error::from)
+                    .and_then(|mut f| {
+                        let mut bytes = entry_buf(f.size());
+                        f.read_to_end(&mut bytes).map(|_| bytes)
+                    });
+                Some(
+                    read.map(|bytes| (name.clone(), bytes))
+                        .map_err(BadAppleError::io("reading", path.join(&name))),
+                )
+            }
+            #[cfg(feature = "archive")]
+            PngFramesInner::Tar(entries) => entries.next().map(Ok),
+        }
+    }
+}
+
+/// Raw 8-bit grayscale frames back to back, as written by
+/// `ffmpeg -i video.mp4 -vf scale=256:192 -pix_fmt gray -f rawvideo -`.
+///
+/// Yields `w * h` bytes per frame and stops at EOF; a trailing partial frame
+/// is an error (usually a wrong `--w`/`--h`).
+pub struct RawFrames<R> {
+    reader: R,
+    frame_len: usize,
+    index: usize,
+    done: bool,
+}
+
+impl<R: Read> RawFrames<R> {
+    pub fn new(reader: R, w: u32, h: u32) -> Self {
+        Self {
+            reader,
+            frame_len: w as usize * h as usize,
+            index: 0,
+            done: false,
+        }
+    }
+
+    /// Fills `buf` like `read_exact`, but returns how much was read on EOF.
+    fn fill(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+}
+
+impl<R: Read> Iterator for RawFrames<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut buf = vec![0u8; self.frame_len];
+        let read = match self.fill(&mut buf) {
+            Ok(n) => n,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(BadAppleError::io("reading", "raw frame stream")(e)));
+            }
+        };
+
+        if read == self.frame_len {
+            self.index += 1;
+            return Some(Ok(buf));
+        }
+
+        self.done = true;
+        if read == 0 {
+            return None;
+        }
+        Some(Err(BadAppleError::ParseError {
+            what: format!("raw frame {}", self.index),
+            source: format!(
+                "stream ended after {read} of {} bytes, check the frame size",
+                self.frame_len
+            )
+            .into(),
+        }))
+    }
+}
@@ -0,0 +1,114 @@
+// src/lib/stats.rs
+// Summary numbers for an encoded payload, used to tune playback targets.
+
+use anyhow::Result;
+
+use crate::rectframes::Payload;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distribution {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+impl Distribution {
+    pub fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self {
+                min: 0.0,
+                avg: 0.0,
+                max: 0.0,
+                p50: 0.0,
+                p90: 0.0,
+                p99: 0.0,
+            };
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        // Nearest-rank percentile
+        let pct = |p: f64| {
+            let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Self {
+            min: sorted[0],
+            avg: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            max: sorted[sorted.len() - 1],
+            p50: pct(50.0),
+            p90: pct(90.0),
+            p99: pct(99.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PayloadStats {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub frames_count: usize,
+    pub duration_secs: f64,
+    pub total_rects: usize,
+    pub rects_per_frame: Distribution,
+    pub bytes_per_frame: Distribution,
+    /// Average fraction of the canvas covered by "on" rects.
+    pub coverage_ratio: f64,
+    /// Per-frame thresholds, if the payload was converted with `frame_stats`.
+    pub thresholds: Option<Distribution>,
+}
+
+pub fn payload_stats(payload: &Payload) -> Result<PayloadStats> {
+    let area = (payload.width as f64) * (payload.height as f64);
+
+    let mut rect_counts = Vec::with_capacity(payload.rect_frames.len());
+    let mut frame_bytes = Vec::with_capacity(payload.rect_frames.len());
+    let mut coverage_sum = 0.0f64;
+
+    for rects in &payload.rect_frames {
+        rect_counts.push(rects.len() as f64);
+        frame_bytes.push(serde_json::to_vec(rects)?.len() as f64);
+
+        let on: u64 = rects
+            .iter()
+            .filter(|r| r.v == 1)
+            .map(|r| r.w as u64 * r.h as u64)
+            .sum();
+        if area > 0.0 {
+            coverage_sum += on as f64 / area;
+        }
+    }
+
+    let frames = payload.rect_frames.len();
+    let duration_secs = if payload.fps > 0 {
+        frames as f64 / payload.fps as f64
+    } else {
+        0.0
+    };
+
+    Ok(PayloadStats {
+        width: payload.width,
+        height: payload.height,
+        fps: payload.fps,
+        frames_count: frames,
+        duration_secs,
+        total_rects: payload.rect_frames.iter().map(|f| f.len()).sum(),
+        rects_per_frame: Distribution::from_values(&rect_counts),
+        bytes_per_frame: Distribution::from_values(&frame_bytes),
+        coverage_ratio: if frames > 0 {
+            coverage_sum / frames as f64
+        } else {
+            0.0
+        },
+        thresholds: payload.frame_thresholds.as_ref().map(|ths| {
+            let values: Vec<f64> = ths.iter().map(|&th| th as f64).collect();
+            Distribution::from_values(&values)
+        }),
+    })
+}
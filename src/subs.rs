@@ -1,21 +1,358 @@
 // src/lib/subs.rs
-// Reads .srt (UTF-8) and writes compact JSON cues for the userscript.
+// Reads subtitles (UTF-8 .srt, WebVTT .vtt as YouTube exports them, the
+// ASS/SSA karaoke subs Bad Apple is usually timed in, .lrc synced lyrics, or
+// TTML/DFXP broadcast captions) and writes compact JSON cues for the
+// userscript (indented with `srt_to_json_file_with(.., true)`).
 //
 // Output schema (compact):
 //   [{ "s": 12.345, "e": 14.200, "t": "line1\nline2" }, ...]
 //
+// Files don't have to be UTF-8: a BOM is honoured, and Shift-JIS, UTF-16
+// without BOM, etc. are guessed from the bytes (`decode_subs`), unless
+// `SubsOpts::encoding` names the encoding.
+//
+// SRT text often carries `<i>`, `<b>`, `<font color>` and `{\an8}` tags;
+// `TagPolicy` strips them (the default), keeps them verbatim, or turns them
+// into the cue's `f` style:
+//   { "s": 1.0, "e": 2.5, "t": "text", "f": { "i": true, "top": true } }
+//
+// ASS karaoke timing (`{\k20}` etc.) and enhanced LRC word timestamps fill
+// the cue's `w` word list:
+//   { "s": 1.0, "e": 2.5, "t": "Bad Apple",
+//     "w": [{ "t": 0.0, "x": "Bad" }, { "t": 0.6, "x": "Apple" }] }
+// With `keep_karaoke` the text also keeps them, as ASS `{\kNN}` tags.
+//
+// Furigana written into the text (`影(かげ)`, `影|かげ`, `｜お前《おまえ》`)
+// move to the cue's `r` list with `SubsOpts::ruby` (`extract_ruby`):
+//   { "s": 3.1, "e": 5.4, "t": "影絵", "r": [{ "i": 0, "b": "影", "r": "かげ" }, ...] }
+//
+// Several languages can go into one file (`write_subs_bundle`), keyed by
+// language code with a label and writing direction for each:
+//   { "en": { "label": "English", "dir": "ltr", "cues": [...] }, "jp": { ... } }
+//
+// The cues can also be written back out as WebVTT (`cues_to_vtt_file`), for
+// players using a native `<track>` instead of the JSON, or as SRT
+// (`cues_to_srt_file`), e.g. after editing the JSON by hand.
+//
 // Usage example:
 //   srt_to_json_file("out/transcript_jp.srt", "out/transcript_jp.json")?;
+//   subs_to_json_file_with("lyrics/karaoke.ass", "out/karaoke.json", &SubsOpts::default())?;
 
-use anyhow::{anyhow, Context, Result};
-use serde::Serialize;
-use std::{fs, path::Path};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tracing::debug;
 
-#[derive(Debug, Clone, Serialize)]
+use crate::error::{BadAppleError, Result};
+use crate::rectframes::to_json;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Cue {
-    pub s: f32, // start seconds
-    pub e: f32, // end seconds
+    /// Start, seconds
+    pub s: f32,
+    /// End, seconds
+    pub e: f32,
+    /// Text, lines joined with `\n`
     pub t: String,
+    /// Styling from the source's tags (`TagPolicy::Style`), for the whole cue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub f: Option<CueStyle>,
+    /// Per-word timing, from ASS `\k` tags or enhanced LRC, for sing-along
+    /// highlighting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub w: Option<Vec<Word>>,
+    /// Furigana over parts of `t` (`SubsOpts::ruby`), in text order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r: Option<Vec<Ruby>>,
+}
+
+/// One sung word (or syllable) of a cue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Word {
+    /// Start, seconds after the cue's start
+    pub t: f32,
+    /// Text
+    pub x: String,
+}
+
+/// A reading (furigana) annotating part of a cue's text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Ruby {
+    /// Where the base starts in the cue text, in characters
+    pub i: usize,
+    /// Base text, e.g. kanji
+    pub b: String,
+    /// Reading shown over the base, e.g. kana
+    pub r: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CueStyle {
+    /// Italic
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub i: bool,
+    /// Bold
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub b: bool,
+    /// Underlined
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub u: bool,
+    /// Text colour as given by `<font color>` (`#rrggbb` or a name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c: Option<String>,
+    /// At the top of the screen (`{\an7}` to `{\an9}`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub top: bool,
+}
+
+/// What to do with cues whose time ranges overlap, which the userscript
+/// would draw on top of each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// Leave them overlapping
+    #[default]
+    Keep,
+    /// Join each run of overlapping cues into one, text in start order
+    Merge,
+    /// End each cue where the next one starts
+    Clamp,
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(OverlapPolicy::Keep),
+            "merge" => Ok(OverlapPolicy::Merge),
+            "clamp" => Ok(OverlapPolicy::Clamp),
+            _ => Err(format!("unknown overlap policy {s:?} (expected keep|merge|clamp)")),
+        }
+    }
+}
+
+impl fmt::Display for OverlapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OverlapPolicy::Keep => "keep",
+            OverlapPolicy::Merge => "merge",
+            OverlapPolicy::Clamp => "clamp",
+        })
+    }
+}
+
+/// What to do with formatting tags in SRT text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TagPolicy {
+    /// Remove them
+    #[default]
+    Strip,
+    /// Leave them in the text
+    Keep,
+    /// Remove them, recording what they did in `Cue::f`
+    Style,
+}
+
+impl FromStr for TagPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strip" => Ok(TagPolicy::Strip),
+            "keep" => Ok(TagPolicy::Keep),
+            "style" => Ok(TagPolicy::Style),
+            _ => Err(format!("unknown tag policy {s:?} (expected strip|keep|style)")),
+        }
+    }
+}
+
+impl fmt::Display for TagPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TagPolicy::Strip => "strip",
+            TagPolicy::Keep => "keep",
+            TagPolicy::Style => "style",
+        })
+    }
+}
+
+/// Subtitle file formats `subs_to_json_file_with` reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SubsFormat {
+    /// SubRip (.srt)
+    #[default]
+    Srt,
+    /// WebVTT (.vtt)
+    Vtt,
+    /// Advanced SubStation Alpha (.ass) and its predecessor SSA (.ssa)
+    Ass,
+    /// LRC synced lyrics (.lrc), including enhanced LRC word timestamps
+    Lrc,
+    /// TTML captions (.ttml), and DFXP (.dfxp), its older name
+    Ttml,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SubsOpts {
+    /// Input format, None for the one the extension names (SRT when unknown)
+    pub format: Option<SubsFormat>,
+    /// Keep ASS karaoke tags (`{\k20}`, `{\kf}`, `{\ko}`) in the cue text,
+    /// and turn enhanced LRC word timestamps into `{\k}` tags
+    pub keep_karaoke: bool,
+    /// Indented JSON
+    pub pretty: bool,
+    /// Text encoding of the input, None to detect it (`decode_subs`)
+    pub encoding: Option<&'static Encoding>,
+    /// Formatting tags in SRT text
+    pub tags: TagPolicy,
+    /// Seconds added to every cue (`shift_cues`), negative to show them earlier
+    pub offset: f32,
+    /// Factor every cue time is multiplied by (`scale_cues`), before `offset`
+    pub scale: f32,
+    /// Overlapping cues (`resolve_overlaps`)
+    pub overlaps: OverlapPolicy,
+    /// Split cues longer than this many seconds (`split_long_cues`)
+    pub max_cue_secs: Option<f32>,
+    /// Split cues with more characters than this (`split_long_cues`)
+    pub max_cue_chars: Option<usize>,
+    /// Move furigana written into the text (`漢字(かんじ)`) to `Cue::r`
+    /// (`extract_ruby`)
+    pub ruby: bool,
+}
+
+impl Default for SubsOpts {
+    fn default() -> Self {
+        Self {
+            format: None,
+            keep_karaoke: false,
+            pretty: false,
+            encoding: None,
+            tags: TagPolicy::Strip,
+            offset: 0.0,
+            scale: 1.0,
+            overlaps: OverlapPolicy::Keep,
+            max_cue_secs: None,
+            max_cue_chars: None,
+            ruby: false,
+        }
+    }
+}
+
+/// Time scale factor, as a number (`0.959`) or a ratio of frame rates
+/// (`23.976/25`: subs timed at 23.976 fps, played at 25).
+pub fn parse_scale(s: &str) -> std::result::Result<f32, String> {
+    let factor = match s.split_once('/') {
+        Some((from, to)) => {
+            let from: f32 = from.trim().parse().map_err(|_| format!("invalid rate in {s:?}"))?;
+            let to: f32 = to.trim().parse().map_err(|_| format!("invalid rate in {s:?}"))?;
+            from / to
+        }
+        None => s.trim().parse().map_err(|_| format!("invalid scale {s:?}"))?,
+    };
+    if !(factor.is_finite() && factor > 0.0) {
+        return Err(format!("scale {s:?} must be positive"));
+    }
+    Ok(factor)
+}
+
+/// Encoding by WHATWG label (`shift_jis`, `utf-16le`, `windows-1252`, ...).
+pub fn parse_encoding(label: &str) -> std::result::Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("unknown encoding {label:?} (e.g. utf-8, shift_jis, utf-16le)"))
+}
+
+/// Guesses the encoding of subtitle bytes: a BOM, else UTF-16 from NUL
+/// bytes in every other position (timestamps and digits are ASCII), else
+/// valid UTF-8, else the byte-frequency guess of `chardetng`.
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let nuls = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count();
+    let half = sample.len() / 2;
+    if half > 0 && nuls(1) * 2 > half {
+        return UTF_16LE;
+    }
+    if half > 0 && nuls(0) * 2 > half {
+        return UTF_16BE;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Subtitle bytes as text, in `encoding` or the detected one; the BOM is
+/// dropped and invalid sequences become U+FFFD.
+pub fn decode_subs(bytes: &[u8], encoding: Option<&'static Encoding>) -> String {
+    let encoding = encoding.unwrap_or_else(|| detect_encoding(bytes));
+    if encoding != UTF_8 {
+        debug!(encoding = encoding.name(), "decoding subtitles");
+    }
+    encoding.decode_with_bom_removal(bytes).0.into_owned()
+}
+
+impl SubsFormat {
+    /// Format of `path` from its extension, None when it isn't a known one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "srt" => Some(SubsFormat::Srt),
+            "vtt" => Some(SubsFormat::Vtt),
+            "ass" | "ssa" => Some(SubsFormat::Ass),
+            "lrc" => Some(SubsFormat::Lrc),
+            "ttml" | "dfxp" => Some(SubsFormat::Ttml),
+            _ => None,
+        }
+    }
+
+    /// Parses `text`; `keep_karaoke` only applies to ASS and LRC.
+    pub fn parse(self, text: &str, keep_karaoke: bool) -> Vec<Cue> {
+        match self {
+            SubsFormat::Srt => parse_srt_to_cues(text),
+            SubsFormat::Vtt => parse_vtt_to_cues(text),
+            SubsFormat::Ass => parse_ass_to_cues(text, keep_karaoke),
+            SubsFormat::Lrc => parse_lrc_to_cues(text, keep_karaoke),
+            SubsFormat::Ttml => parse_ttml_to_cues(text),
+        }
+    }
+}
+
+impl FromStr for SubsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "srt" => Ok(SubsFormat::Srt),
+            "vtt" => Ok(SubsFormat::Vtt),
+            "ass" | "ssa" => Ok(SubsFormat::Ass),
+            "lrc" => Ok(SubsFormat::Lrc),
+            "ttml" | "dfxp" => Ok(SubsFormat::Ttml),
+            _ => Err(format!("unknown subtitle format {s:?} (expected srt|vtt|ass|lrc|ttml)")),
+        }
+    }
+}
+
+impl fmt::Display for SubsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SubsFormat::Srt => "srt",
+            SubsFormat::Vtt => "vtt",
+            SubsFormat::Ass => "ass",
+            SubsFormat::Lrc => "lrc",
+            SubsFormat::Ttml => "ttml",
+        })
+    }
 }
 
 fn parse_ts_to_seconds(ts: &str) -> f32 {
@@ -51,6 +388,378 @@ fn parse_ts_to_seconds(ts: &str) -> f32 {
     (hh as f32) * 3600.0 + (mm as f32) * 60.0 + (ss as f32) + (ms as f32) / 1000.0
 }
 
+/// HTML tags SRT players understand; other `<...>` is left as text.
+const SRT_TAGS: &[&str] = &["i", "b", "u", "s", "em", "strong", "font"];
+
+/// Removes `<i>`/`<b>`/`<u>`/`<font ...>` (and their closing tags) and
+/// `{\...}` override blocks from `t`, returning what they styled.
+fn strip_srt_tags(t: &str) -> (String, CueStyle) {
+    let mut out = String::with_capacity(t.len());
+    let mut style = CueStyle::default();
+    let mut rest = t;
+    while let Some(open) = rest.find(['<', '{']) {
+        out.push_str(&rest[..open]);
+        let close = if rest[open..].starts_with('<') { '>' } else { '}' };
+        let Some(len) = rest[open..].find(close) else {
+            out.push_str(&rest[open..]);
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + len];
+        let known = if close == '}' {
+            let overrides = tag.strip_prefix('\\');
+            for o in overrides.into_iter().flat_map(|o| o.split('\\')) {
+                match o.trim() {
+                    "i1" => style.i = true,
+                    "b1" => style.b = true,
+                    "u1" => style.u = true,
+                    "an7" | "an8" | "an9" => style.top = true,
+                    _ => {}
+                }
+            }
+            overrides.is_some()
+        } else {
+            let name = tag.trim_start_matches('/');
+            let name = name.split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+            if SRT_TAGS.contains(&name.as_str()) && !tag.starts_with('/') {
+                match name.as_str() {
+                    "i" | "em" => style.i = true,
+                    "b" | "strong" => style.b = true,
+                    "u" => style.u = true,
+                    _ => {}
+                }
+                if let Some((_, v)) = tag.split_once("color=") {
+                    let v = v.trim_start_matches(['"', '\'']);
+                    let end = v.find(['"', '\'', ' ']).unwrap_or(v.len());
+                    style.c = Some(v[..end].to_string());
+                }
+            }
+            SRT_TAGS.contains(&name.as_str())
+        };
+        if !known {
+            out.push_str(&rest[open..=open + len]);
+        }
+        rest = &rest[open + len + 1..];
+    }
+    out.push_str(rest);
+    (out, style)
+}
+
+/// Applies `policy` to `cues` overlapping in time. Cues are sorted by start
+/// first; clamping drops a cue when the next one starts at the same time,
+/// merging its text into that one instead.
+pub fn resolve_overlaps(cues: &mut Vec<Cue>, policy: OverlapPolicy) {
+    if policy == OverlapPolicy::Keep {
+        return;
+    }
+    cues.sort_by(|a, b| a.s.partial_cmp(&b.s).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut out: Vec<Cue> = Vec::with_capacity(cues.len());
+    for cue in cues.drain(..) {
+        let Some(prev) = out.last_mut() else {
+            out.push(cue);
+            continue;
+        };
+        if cue.s >= prev.e {
+            out.push(cue);
+            continue;
+        }
+        if policy == OverlapPolicy::Merge || cue.s <= prev.s {
+            prev.e = prev.e.max(cue.e);
+            prev.t.push('\n');
+            prev.t.push_str(&cue.t);
+            if let Some(words) = cue.w {
+                let delay = cue.s - prev.s;
+                prev.w.get_or_insert_with(Vec::new).extend(words.into_iter().map(|w| Word {
+                    t: w.t + delay,
+                    x: w.x,
+                }));
+            }
+        } else {
+            prev.e = cue.s;
+            out.push(cue);
+        }
+    }
+    *cues = out;
+}
+
+/// Character ranges of `t` for pieces of at most `limit` characters, broken
+/// between words. A word is only broken inside when it's CJK (lines without
+/// spaces) or longer than `max` on its own, and never inside a furigana base.
+fn chunk_text(t: &str, limit: usize, max: Option<usize>, ruby: &[Ruby]) -> Vec<Range<usize>> {
+    let chars: Vec<char> = t.chars().collect();
+    let limit = limit.max(1);
+    let in_base = |at: usize| ruby.iter().any(|r| r.i < at && at < r.i + r.b.chars().count());
+    let mut chunks = Vec::new();
+    let mut cur: Option<Range<usize>> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        if cur.as_ref().is_some_and(|c| i - c.start > limit) {
+            chunks.extend(cur.take());
+        }
+        let word = &chars[start..i];
+        let breakable = max.is_some_and(|m| word.len() > m)
+            || word.iter().any(|&c| is_kanji(c) || is_kana(c));
+        let mut from = cur.take().map_or(start, |c| c.start);
+        if breakable {
+            for at in start + 1..i {
+                if at - from >= limit && !in_base(at) {
+                    chunks.push(from..at);
+                    from = at;
+                }
+            }
+        }
+        cur = Some(from..i);
+    }
+    chunks.extend(cur);
+    chunks
+}
+
+/// Splits cues lasting more than `max_secs` or longer than `max_chars` into
+/// sequential cues, each getting a share of the time proportional to its
+/// text, so the overlay stays readable on small canvases. Furigana go with
+/// the piece holding their base.
+pub fn split_long_cues(cues: &mut Vec<Cue>, max_secs: Option<f32>, max_chars: Option<usize>) {
+    let mut out = Vec::with_capacity(cues.len());
+    for cue in cues.drain(..) {
+        let chars = cue.t.chars().filter(|&c| c != '\n').count();
+        let dur = cue.e - cue.s;
+        let by_time = max_secs.map_or(1, |m| (dur / m.max(0.001)).ceil() as usize);
+        let by_chars = max_chars.map_or(1, |m| chars.div_ceil(m.max(1)));
+        let parts = by_time.max(by_chars);
+        if parts <= 1 {
+            out.push(cue);
+            continue;
+        }
+
+        let mut limit = chars.div_ceil(parts);
+        if let Some(m) = max_chars {
+            limit = limit.min(m);
+        }
+        let ruby = cue.r.as_deref().unwrap_or_default();
+        let chunks = chunk_text(&cue.t, limit, max_chars, ruby);
+        let text: Vec<char> = cue.t.chars().collect();
+        let weights: Vec<usize> = chunks
+            .iter()
+            .map(|c| text[c.clone()].iter().filter(|&&c| c != '\n').count().max(1))
+            .collect();
+        let total: usize = weights.iter().sum();
+        let mut s = cue.s;
+        for (i, (range, w)) in chunks.into_iter().zip(&weights).enumerate() {
+            let e = if i + 1 == weights.len() {
+                cue.e
+            } else {
+                s + dur * *w as f32 / total as f32
+            };
+            // Words go to the piece whose time they start in
+            let w = cue.w.as_ref().map(|words| {
+                words
+                    .iter()
+                    .filter(|w| cue.s + w.t >= s && (cue.s + w.t < e || i + 1 == weights.len()))
+                    .map(|w| Word {
+                        t: cue.s + w.t - s,
+                        x: w.x.clone(),
+                    })
+                    .collect()
+            });
+            let r: Vec<Ruby> = ruby
+                .iter()
+                .filter(|r| range.start <= r.i && r.i + r.b.chars().count() <= range.end)
+                .map(|r| Ruby {
+                    i: r.i - range.start,
+                    ..r.clone()
+                })
+                .collect();
+            out.push(Cue {
+                s,
+                e,
+                t: text[range].iter().collect(),
+                f: cue.f.clone(),
+                w,
+                r: (!r.is_empty()).then_some(r),
+            });
+            s = e;
+        }
+    }
+    *cues = out;
+}
+
+/// Multiplies every cue time by `factor`, for subs timed against a source at
+/// another frame rate (drift grows with time, so an offset can't fix it).
+pub fn scale_cues(cues: &mut [Cue], factor: f32) {
+    for cue in cues {
+        cue.s *= factor;
+        cue.e *= factor;
+        for word in cue.w.iter_mut().flatten() {
+            word.t *= factor;
+        }
+    }
+}
+
+/// Moves every cue by `offset` seconds, e.g. to line the lyrics up with the
+/// frame payload. Cues pushed entirely before 0 are dropped, ones straddling
+/// it start at 0.
+pub fn shift_cues(cues: &mut Vec<Cue>, offset: f32) {
+    for cue in cues.iter_mut() {
+        let s = cue.s + offset;
+        cue.s = s.max(0.0);
+        cue.e = (cue.e + offset).max(0.0);
+        // Words stay put when the start is clamped
+        for word in cue.w.iter_mut().flatten() {
+            word.t = (word.t + s - cue.s).max(0.0);
+        }
+    }
+    cues.retain(|cue| cue.e > 0.0);
+}
+
+/// Kanji that furigana annotate (CJK ideographs and the 々 repeat mark).
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+        || matches!(c, '々' | '〆' | 'ヶ')
+}
+
+/// Hiragana, katakana and the long vowel mark.
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309f}' | '\u{30a0}'..='\u{30ff}')
+}
+
+/// Moves furigana written into `t` out of it. Three conventions are read:
+///   - `漢字(かんじ)` / `漢字（かんじ）`: the kanji run before parentheses
+///     holding only kana (so `(Bad Apple!!)` stays text)
+///   - `漢字|かんじ`: the kanji run before `|` and the kana after it, as
+///     Aegisub karaoke templates write furigana in ASS syllables
+///   - `｜漢字《かんじ》`: an explicit base of any characters, for readings of
+///     words that aren't all kanji
+///
+/// Returns the text with only the bases left, and the readings.
+pub fn extract_ruby(t: &str) -> (String, Vec<Ruby>) {
+    let chars: Vec<char> = t.chars().collect();
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut ruby: Vec<Ruby> = Vec::new();
+    // Where the last base ended, so `漢(かん)字(じ)` annotates 字 alone
+    let mut free = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i + 1..];
+
+        if matches!(c, '｜' | '|')
+            && let Some(open) = rest.iter().position(|&c| c == '《')
+            && let Some(close) = rest[open..].iter().position(|&c| c == '》')
+            && open > 0
+            && close > 1
+            && !rest[..open].contains(&'\n')
+        {
+            let b: String = rest[..open].iter().collect();
+            let r: String = rest[open + 1..open + close].iter().collect();
+            ruby.push(Ruby { i: out.len(), b, r });
+            out.extend(&rest[..open]);
+            free = out.len();
+            i += open + close + 2;
+            continue;
+        }
+
+        let reading = match c {
+            '(' | '（' => {
+                let end = if c == '(' { ')' } else { '）' };
+                rest.iter()
+                    .position(|&c| c == end)
+                    .filter(|&n| n > 0 && rest[..n].iter().all(|&c| is_kana(c)))
+                    .map(|n| (&rest[..n], n + 2))
+            }
+            '|' => {
+                let n = rest.iter().take_while(|&&c| is_kana(c)).count();
+                (n > 0).then(|| (&rest[..n], n + 1))
+            }
+            _ => None,
+        };
+        let base = out[free..].iter().rev().take_while(|&&c| is_kanji(c)).count();
+        if let Some((r, len)) = reading
+            && base > 0
+        {
+            let at = out.len() - base;
+            ruby.push(Ruby {
+                i: at,
+                b: out[at..].iter().collect(),
+                r: r.iter().collect(),
+            });
+            free = out.len();
+            i += len;
+            continue;
+        }
+
+        out.push(c);
+        i += 1;
+    }
+    (out.into_iter().collect(), ruby)
+}
+
+/// Cue text with its furigana put back in: plain runs through `text`, each
+/// base and reading through `annotate`.
+fn ruby_text(
+    cue: &Cue,
+    text: impl Fn(&str) -> String,
+    annotate: impl Fn(&str, &str) -> String,
+) -> String {
+    let chars: Vec<char> = cue.t.chars().collect();
+    let plain = |run: &[char]| text(&run.iter().collect::<String>());
+    let mut out = String::new();
+    let mut i = 0;
+    for r in cue.r.iter().flatten() {
+        let n = r.b.chars().count();
+        // Readings that don't match the text (hand-edited JSON) are dropped
+        let base = chars.get(r.i..r.i + n);
+        if r.i < i || base.is_none_or(|b| !b.iter().copied().eq(r.b.chars())) {
+            continue;
+        }
+        out.push_str(&plain(&chars[i..r.i]));
+        out.push_str(&annotate(&text(&r.b), &text(&r.r)));
+        i = r.i + n;
+    }
+    out.push_str(&plain(&chars[i..]));
+    out
+}
+
+/// A reading as `extract_ruby` reads it back: `漢字(かんじ)`, or with an
+/// explicit `｜` base when the base isn't all kanji.
+fn ruby_source(b: &str, r: &str) -> String {
+    if b.chars().all(is_kanji) {
+        format!("{b}({r})")
+    } else {
+        format!("｜{b}《{r}》")
+    }
+}
+
+/// Applies `policy` to the text of `cues` (parsed from SRT, which is where
+/// these tags turn up).
+pub fn apply_tag_policy(cues: &mut Vec<Cue>, policy: TagPolicy) {
+    if policy == TagPolicy::Keep {
+        return;
+    }
+    for cue in cues.iter_mut() {
+        let (t, style) = strip_srt_tags(&cue.t);
+        cue.t = t
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if policy == TagPolicy::Style && style != CueStyle::default() {
+            cue.f = Some(style);
+        }
+    }
+    // Cues that were nothing but tags
+    cues.retain(|cue| !cue.t.is_empty());
+}
+
 pub fn parse_srt_to_cues(srt_text: &str) -> Vec<Cue> {
     let norm = srt_text.replace("\r\n", "\n").replace('\r', "\n");
     let blocks = norm
@@ -114,35 +823,832 @@ pub fn parse_srt_to_cues(srt_text: &str) -> Vec<Cue> {
             continue;
         }
 
-        cues.push(Cue { s, e, t });
+        cues.push(Cue { s, e, t, f: None, w: None, r: None });
+    }
+
+    cues.sort_by(|a, b| a.s.partial_cmp(&b.s).unwrap_or(std::cmp::Ordering::Equal));
+    cues
+}
+
+/// WebVTT timestamp, "HH:MM:SS.mmm" or "MM:SS.mmm" (hours are optional).
+fn parse_vtt_ts(ts: &str) -> Option<f32> {
+    let (hms, ms) = ts.trim().split_once('.')?;
+    let ms: u32 = ms.get(..3)?.parse().ok()?;
+    let mut secs = 0u32;
+    for part in hms.split(':') {
+        secs = secs * 60 + part.parse::<u32>().ok()?;
+    }
+    Some(secs as f32 + ms as f32 / 1000.0)
+}
+
+/// Cue text without WebVTT markup: `<i>`, `<c.color>`, `<v Speaker>`, inline
+/// `<00:00:01.000>` timestamps, and the common character references.
+fn strip_vtt_markup(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&amp;", "&")
+}
+
+/// Parses WebVTT. The header, NOTE, STYLE and REGION blocks are skipped, cue
+/// identifiers and settings (`align:start position:10%`, ...) are ignored.
+pub fn parse_vtt_to_cues(vtt_text: &str) -> Vec<Cue> {
+    let norm = vtt_text
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+
+    let mut cues = Vec::new();
+
+    for block in norm.split("\n\n").map(|b| b.trim()).filter(|b| !b.is_empty()) {
+        let lines: Vec<&str> = block.lines().map(|l| l.trim_end()).collect();
+        // Cue identifier on the first line, if any
+        let Some(time_line_idx) = lines.iter().take(2).position(|l| l.contains("-->")) else {
+            continue;
+        };
+
+        let Some((start_ts, rest)) = lines[time_line_idx].split_once("-->") else {
+            continue;
+        };
+        // Settings follow the end timestamp
+        let end_ts = rest.split_whitespace().next().unwrap_or("");
+        let (Some(s), Some(e)) = (parse_vtt_ts(start_ts), parse_vtt_ts(end_ts)) else {
+            continue;
+        };
+
+        let t = lines[(time_line_idx + 1)..]
+            .iter()
+            .map(|l| strip_vtt_markup(l))
+            .filter(|l| !l.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+
+        if t.is_empty() {
+            continue;
+        }
+
+        cues.push(Cue { s, e, t, f: None, w: None, r: None });
+    }
+
+    cues.sort_by(|a, b| a.s.partial_cmp(&b.s).unwrap_or(std::cmp::Ordering::Equal));
+    cues
+}
+
+/// ASS timestamp, "H:MM:SS.cc" (centiseconds).
+fn parse_ass_ts(ts: &str) -> Option<f32> {
+    let (hms, frac) = ts.trim().split_once('.')?;
+    let frac: f32 = format!("0.{frac}").parse().ok()?;
+    let mut secs = 0u32;
+    for part in hms.split(':') {
+        secs = secs * 60 + part.parse::<u32>().ok()?;
+    }
+    Some(secs as f32 + frac)
+}
+
+/// Dialogue text as plain lines: `\N`/`\n` break lines, `\h` is a hard
+/// space, `{...}` override blocks are dropped (but for their karaoke tags
+/// when `keep_karaoke`), and so is vector drawing text (`{\p1}` to `{\p0}`).
+/// Also returns the syllables the karaoke tags time.
+fn strip_ass_overrides(text: &str, keep_karaoke: bool) -> (String, Vec<Word>) {
+    let mut out = String::with_capacity(text.len());
+    let mut words: Vec<Word> = Vec::new();
+    let mut elapsed = 0.0f32;
+    let mut drawing = false;
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(block) = rest.strip_prefix('{')
+            && let Some((tags, after)) = block.split_once('}')
+        {
+            for tag in tags.split('\\').map(str::trim) {
+                if let Some(scale) = tag.strip_prefix('p')
+                    && let Ok(scale) = scale.parse::<u32>()
+                {
+                    drawing = scale > 0;
+                }
+                let karaoke = ["kf", "ko", "k", "K"]
+                    .iter()
+                    .find_map(|k| tag.strip_prefix(k))
+                    .and_then(|cs| cs.parse::<u32>().ok());
+                if let Some(cs) = karaoke {
+                    words.push(Word {
+                        t: elapsed,
+                        x: String::new(),
+                    });
+                    elapsed += cs as f32 / 100.0;
+                    if keep_karaoke {
+                        out.push_str("{\\");
+                        out.push_str(tag);
+                        out.push('}');
+                    }
+                }
+            }
+            rest = after;
+            continue;
+        }
+
+        let mut chars = rest.chars();
+        let c = chars.next().expect("non-empty");
+        rest = chars.as_str();
+        let c = match (c, rest.chars().next()) {
+            ('\\', Some('N' | 'n')) => {
+                rest = &rest[1..];
+                '\n'
+            }
+            ('\\', Some('h')) => {
+                rest = &rest[1..];
+                '\u{a0}'
+            }
+            _ => c,
+        };
+        if !drawing {
+            out.push(c);
+            if let Some(word) = words.last_mut() {
+                word.x.push(c);
+            }
+        }
+    }
+    // `{\k50}` with nothing after it is a pause
+    words.retain_mut(|w| {
+        w.x = w.x.trim().to_string();
+        !w.x.is_empty()
+    });
+    (out, words)
+}
+
+/// Parses ASS/SSA `Dialogue:` lines of the `[Events]` section, with fields in
+/// the order its `Format:` line gives (the ASS default without one).
+/// `Comment:` lines and other sections are skipped.
+pub fn parse_ass_to_cues(ass_text: &str, keep_karaoke: bool) -> Vec<Cue> {
+    const DEFAULT_FORMAT: &str =
+        "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+    let mut in_events = false;
+    let mut fields: Vec<String> = DEFAULT_FORMAT.split(',').map(|f| f.trim().to_string()).collect();
+    let mut cues = Vec::new();
+
+    for line in ass_text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|f| f.trim().to_string()).collect();
+            continue;
+        }
+        let Some(dialogue) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+
+        // Text is last and may contain commas
+        let values: Vec<&str> = dialogue.trim_start().splitn(fields.len(), ',').collect();
+        let field = |name: &str| {
+            fields
+                .iter()
+                .position(|f| f.eq_ignore_ascii_case(name))
+                .and_then(|i| values.get(i))
+                .copied()
+        };
+        let (Some(s), Some(e)) = (
+            field("start").and_then(parse_ass_ts),
+            field("end").and_then(parse_ass_ts),
+        ) else {
+            continue;
+        };
+
+        let (t, words) = strip_ass_overrides(field("text").unwrap_or(""), keep_karaoke);
+        let w = (!words.is_empty()).then_some(words);
+        let t = t
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if t.is_empty() {
+            continue;
+        }
+
+        cues.push(Cue { s, e, t, f: None, w, r: None });
     }
 
     cues.sort_by(|a, b| a.s.partial_cmp(&b.s).unwrap_or(std::cmp::Ordering::Equal));
     cues
 }
 
+/// How long the last LRC line stays up without a `[length:]` tag.
+const LRC_LAST_LINE_SECS: f32 = 5.0;
+
+/// LRC timestamp, "mm:ss.xx", "mm:ss.xxx" or "mm:ss:xx" (minutes may exceed 59).
+fn parse_lrc_ts(ts: &str) -> Option<f32> {
+    let (mm, rest) = ts.trim().split_once(':')?;
+    let ss = match rest.split_once(':') {
+        Some((ss, frac)) => format!("{ss}.{frac}"),
+        None => rest.to_string(),
+    };
+    let mm: u32 = mm.parse().ok()?;
+    let ss: f32 = ss.parse().ok()?;
+    Some(mm as f32 * 60.0 + ss)
+}
+
+/// Enhanced LRC line text: `<mm:ss.xx>` word timestamps dropped, or turned
+/// into `{\kNN}` durations (centiseconds, like ASS) from `s` to `e` when
+/// `keep_karaoke`. Also returns the timed words, if there were timestamps.
+fn lrc_words(
+    text: &str,
+    s: f32,
+    e: f32,
+    offset: f32,
+    keep_karaoke: bool,
+) -> (String, Option<Vec<Word>>) {
+    // (word start, word), the text before the first timestamp starting at `s`
+    let mut words: Vec<(f32, String)> = vec![(s, String::new())];
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let ts = rest[open + 1..]
+            .split_once('>')
+            .and_then(|(ts, after)| Some((parse_lrc_ts(ts)? - offset, after)));
+        match ts {
+            Some((t, after)) => {
+                words.last_mut().expect("first word").1.push_str(&rest[..open]);
+                words.push((t, String::new()));
+                rest = after;
+            }
+            None => {
+                words.last_mut().expect("first word").1.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    words.last_mut().expect("first word").1.push_str(rest);
+
+    let timed = (words.len() > 1).then(|| {
+        words
+            .iter()
+            .filter(|(_, x)| !x.trim().is_empty())
+            .map(|(t, x)| Word {
+                t: (t - s).max(0.0),
+                x: x.trim().to_string(),
+            })
+            .collect()
+    });
+    if !keep_karaoke {
+        return (words.into_iter().map(|(_, w)| w).collect(), timed);
+    }
+    let mut out = String::new();
+    for (i, (t, word)) in words.iter().enumerate() {
+        // A timestamp after the last word only marks where it ends
+        if word.is_empty() {
+            continue;
+        }
+        let end = words.get(i + 1).map_or(e, |(next, _)| *next);
+        let cs = ((end - t) * 100.0).round().max(0.0) as u32;
+        out.push_str(&format!("{{\\k{cs}}}{word}"));
+    }
+    (out, timed)
+}
+
+/// Parses LRC lyrics: every `[mm:ss.xx]` on a line starts a cue with its
+/// text, which lasts until the next timestamped line (an empty one just ends
+/// the previous line). `[offset:+ms]` shifts all times earlier, `[length:]`
+/// ends the last line; other `[key:value]` tags are skipped.
+pub fn parse_lrc_to_cues(lrc_text: &str, keep_karaoke: bool) -> Vec<Cue> {
+    let mut offset = 0.0f32;
+    let mut length = None;
+    // (start, text), one per timestamp
+    let mut lines: Vec<(f32, &str)> = Vec::new();
+
+    for line in lrc_text.trim_start_matches('\u{feff}').lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+        while let Some((tag, after)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            if let Some(t) = parse_lrc_ts(tag) {
+                times.push(t);
+            } else if let Some((key, value)) = tag.split_once(':') {
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "offset" => offset = value.trim().parse::<f32>().unwrap_or(0.0) / 1000.0,
+                    "length" => length = parse_lrc_ts(value),
+                    _ => {}
+                }
+            } else {
+                // Lyrics starting with e.g. "[Chorus]"
+                break;
+            }
+            rest = after;
+        }
+        lines.extend(times.into_iter().map(|t| (t, rest.trim())));
+    }
+    lines.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut cues = Vec::new();
+    for (i, &(start, text)) in lines.iter().enumerate() {
+        // Lines sharing a timestamp (e.g. a translation) end together
+        let next = lines[i..].iter().map(|&(t, _)| t).find(|&t| t > start);
+        let s = (start - offset).max(0.0);
+        let e = match next {
+            Some(t) => t - offset,
+            None => length.unwrap_or(s + LRC_LAST_LINE_SECS),
+        }
+        .max(s);
+
+        let (t, w) = lrc_words(text, s, e, offset, keep_karaoke);
+        let t = t.trim().to_string();
+        if t.is_empty() {
+            continue;
+        }
+
+        cues.push(Cue { s, e, t, f: None, w, r: None });
+    }
+    cues
+}
+
+/// Document-wide TTML timing parameters (`ttp:frameRate`, `ttp:tickRate`).
+struct TtmlRates {
+    frame_rate: f32,
+    tick_rate: f32,
+}
+
+/// TTML attribute by local name, whatever its namespace prefix (`tts:`,
+/// `ttp:`, or the old DFXP namespaces).
+fn ttml_attr<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes().find(|a| a.name() == name).map(|a| a.value())
+}
+
+/// TTML time expression: clock time ("HH:MM:SS.fff", "HH:MM:SS:FF") or an
+/// offset ("12.5s", "500ms", "2m", "1h", "90f", "10000000t").
+fn parse_ttml_time(expr: &str, rates: &TtmlRates) -> Option<f32> {
+    let expr = expr.trim();
+    if expr.contains(':') {
+        let parts: Vec<&str> = expr.split(':').collect();
+        let (h, m, sec) = (parts.first()?, parts.get(1)?, parts.get(2)?);
+        let mut t = h.parse::<f32>().ok()? * 3600.0 + m.parse::<f32>().ok()? * 60.0;
+        t += sec.parse::<f32>().ok()?;
+        if let Some(frames) = parts.get(3) {
+            t += frames.parse::<f32>().ok()? / rates.frame_rate;
+        }
+        return Some(t);
+    }
+    let split = expr.find(|c: char| c.is_ascii_alphabetic())?;
+    let (value, metric) = expr.split_at(split);
+    let value: f32 = value.parse().ok()?;
+    Some(match metric {
+        "h" => value * 3600.0,
+        "m" => value * 60.0,
+        "s" => value,
+        "ms" => value / 1000.0,
+        "f" => value / rates.frame_rate,
+        "t" => value / rates.tick_rate,
+        _ => return None,
+    })
+}
+
+/// Text of a `<p>`: `<span>`s flattened, `<br/>` breaks lines, other
+/// whitespace collapsed like the default `xml:space`.
+fn ttml_text(node: roxmltree::Node, out: &mut String) {
+    for child in node.children() {
+        if child.is_text() {
+            let text = child.text().unwrap_or("");
+            let mut words = text.split_whitespace().peekable();
+            if text.starts_with(char::is_whitespace) && words.peek().is_some() {
+                out.push(' ');
+            }
+            while let Some(word) = words.next() {
+                out.push_str(word);
+                if words.peek().is_some() || text.ends_with(char::is_whitespace) {
+                    out.push(' ');
+                }
+            }
+        } else if child.tag_name().name() == "br" {
+            out.push('\n');
+        } else if child.is_element() {
+            ttml_text(child, out);
+        }
+    }
+}
+
+/// A `<p>` before cues sharing its timing are merged.
+struct TtmlLine {
+    s: f32,
+    e: f32,
+    /// Vertical origin of its region, lines higher up come first in a cue
+    y: f32,
+    t: String,
+}
+
+/// Walks `<body>`/`<div>` down to the `<p>`s, resolving times against the
+/// parent's like a `par` time container, and the inherited region.
+fn ttml_walk(
+    node: roxmltree::Node,
+    (begin, end): (f32, f32),
+    region: Option<&str>,
+    regions: &HashMap<&str, f32>,
+    rates: &TtmlRates,
+    out: &mut Vec<TtmlLine>,
+) {
+    for child in node.children().filter(|c| c.is_element()) {
+        let time = |name| ttml_attr(child, name).and_then(|v| parse_ttml_time(v, rates));
+        let b = begin + time("begin").unwrap_or(0.0);
+        let e = match (time("end"), time("dur")) {
+            (Some(e), _) => begin + e,
+            (None, Some(dur)) => b + dur,
+            (None, None) => end,
+        }
+        .min(end);
+        let region = ttml_attr(child, "region").or(region);
+
+        match child.tag_name().name() {
+            "p" => {
+                let mut text = String::new();
+                ttml_text(child, &mut text);
+                let t = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if !t.is_empty() && e.is_finite() && e > b {
+                    let y = region.and_then(|r| regions.get(r)).copied();
+                    out.push(TtmlLine {
+                        s: b,
+                        e,
+                        y: y.unwrap_or(f32::MAX),
+                        t,
+                    });
+                }
+            }
+            "body" | "div" => ttml_walk(child, (b, e), region, regions, rates, out),
+            _ => {}
+        }
+    }
+}
+
+/// Parses TTML/DFXP: every timed `<p>` in `<body>` becomes a cue. `<p>`s
+/// with the same timing (e.g. one per region) are joined into one cue, top
+/// region first by `tts:origin`. Styling is ignored; malformed XML yields no
+/// cues.
+pub fn parse_ttml_to_cues(ttml_text: &str) -> Vec<Cue> {
+    let Ok(doc) = roxmltree::Document::parse(ttml_text.trim_start_matches('\u{feff}')) else {
+        return Vec::new();
+    };
+    let tt = doc.root_element();
+    let rates = TtmlRates {
+        frame_rate: ttml_attr(tt, "frameRate")
+            .and_then(|r| r.parse().ok())
+            .unwrap_or(30.0),
+        tick_rate: ttml_attr(tt, "tickRate")
+            .and_then(|r| r.parse().ok())
+            .unwrap_or(1.0),
+    };
+
+    // Region id -> vertical origin ("10% 80%" -> 80), in any unit
+    let regions: HashMap<&str, f32> = tt
+        .descendants()
+        .filter(|n| n.tag_name().name() == "region")
+        .filter_map(|n| {
+            let y = ttml_attr(n, "origin")?.split_whitespace().nth(1)?;
+            let y = y.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+            Some((ttml_attr(n, "id")?, y.parse().ok()?))
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    ttml_walk(tt, (0.0, f32::INFINITY), None, &regions, &rates, &mut lines);
+    lines.sort_by(|a, b| {
+        (a.s, a.e, a.y)
+            .partial_cmp(&(b.s, b.e, b.y))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut cues: Vec<Cue> = Vec::new();
+    for line in lines {
+        match cues.last_mut() {
+            Some(cue) if cue.s == line.s && cue.e == line.e => {
+                cue.t.push('\n');
+                cue.t.push_str(&line.t);
+            }
+            _ => cues.push(Cue {
+                s: line.s,
+                e: line.e,
+                t: line.t,
+                f: None,
+                w: None,
+                r: None,
+            }),
+        }
+    }
+    cues
+}
+
 pub fn srt_to_json_file<P: AsRef<Path>, Q: AsRef<Path>>(srt_path: P, json_path: Q) -> Result<()> {
-    let srt_path = srt_path.as_ref();
-    let json_path = json_path.as_ref();
+    srt_to_json_file_with(srt_path, json_path, false)
+}
 
-    let srt = fs::read_to_string(srt_path)
-        .with_context(|| format!("Failed reading SRT: {}", srt_path.display()))?;
+/// `srt_to_json_file`, indented when `pretty`.
+pub fn srt_to_json_file_with<P: AsRef<Path>, Q: AsRef<Path>>(
+    srt_path: P,
+    json_path: Q,
+    pretty: bool,
+) -> Result<()> {
+    let opts = SubsOpts {
+        format: Some(SubsFormat::Srt),
+        pretty,
+        ..SubsOpts::default()
+    };
+    subs_to_json_file_with(srt_path, json_path, &opts)
+}
+
+/// Reads and parses a subtitle file as `opts` says (`pretty` aside); no cues
+/// is an error.
+pub fn read_cues_file<P: AsRef<Path>>(subs_path: P, opts: &SubsOpts) -> Result<Vec<Cue>> {
+    let subs_path = subs_path.as_ref();
+    let format = opts
+        .format
+        .or_else(|| SubsFormat::from_path(subs_path))
+        .unwrap_or_default();
 
-    let cues = parse_srt_to_cues(&srt);
+    let bytes = fs::read(subs_path).map_err(BadAppleError::io("reading subtitles", subs_path))?;
+    let text = decode_subs(&bytes, opts.encoding);
+
+    let mut cues = format.parse(&text, opts.keep_karaoke);
+    if format == SubsFormat::Srt {
+        apply_tag_policy(&mut cues, opts.tags);
+    }
+    resolve_overlaps(&mut cues, opts.overlaps);
+    if opts.scale != 1.0 {
+        scale_cues(&mut cues, opts.scale);
+    }
+    if opts.offset != 0.0 {
+        shift_cues(&mut cues, opts.offset);
+    }
+    // After merging, which rewrites the text; splitting keeps the offsets
+    if opts.ruby {
+        for cue in &mut cues {
+            let (t, ruby) = extract_ruby(&cue.t);
+            if !ruby.is_empty() {
+                cue.t = t;
+                cue.r = Some(ruby);
+                if let Some(words) = &mut cue.w {
+                    for w in words {
+                        w.x = extract_ruby(&w.x).0;
+                    }
+                }
+            }
+        }
+    }
+    if opts.max_cue_secs.is_some() || opts.max_cue_chars.is_some() {
+        split_long_cues(&mut cues, opts.max_cue_secs, opts.max_cue_chars);
+    }
 
     if cues.is_empty() {
-        return Err(anyhow!("No cues parsed from {}", srt_path.display()));
+        return Err(BadAppleError::MissingInput {
+            what: "Cues",
+            path: subs_path.to_path_buf(),
+        });
     }
+    Ok(cues)
+}
+
+/// Converts a subtitle file to JSON cues, as `opts` says.
+pub fn subs_to_json_file_with<P: AsRef<Path>, Q: AsRef<Path>>(
+    subs_path: P,
+    json_path: Q,
+    opts: &SubsOpts,
+) -> Result<()> {
+    let json_path = json_path.as_ref();
+    let cues = read_cues_file(subs_path, opts)?;
 
     if let Some(parent) = json_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed creating dir: {}", parent.display()))?;
+        fs::create_dir_all(parent).map_err(BadAppleError::io("creating dir", parent))?;
     }
 
-    // Compact JSON
-    let json = serde_json::to_string(&cues).context("Failed serializing cues to JSON")?;
-    fs::write(json_path, json)
-        .with_context(|| format!("Failed writing JSON: {}", json_path.display()))?;
+    let json = to_json(&cues, opts.pretty).map_err(std::io::Error::from);
+    json.and_then(|json| fs::write(json_path, json))
+        .map_err(BadAppleError::io("writing JSON", json_path))?;
+
+    Ok(())
+}
+
+/// Reads a JSON cue file, as written by `subs_to_json_file_with`.
+pub fn read_cues_json<P: AsRef<Path>>(json_path: P) -> Result<Vec<Cue>> {
+    let json_path = json_path.as_ref();
+    let json =
+        fs::read_to_string(json_path).map_err(BadAppleError::io("reading JSON", json_path))?;
+    serde_json::from_str(&json).map_err(BadAppleError::parse(json_path.display().to_string()))
+}
+
+/// File name of a bundle (`write_subs_bundle`) next to the payload.
+pub const BUNDLE_FILE: &str = "subtitles.json";
 
+/// Writing direction of a track's script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDir {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl TextDir {
+    /// Direction of a language's script, by code (`ar`, `he-IL`, ...).
+    pub fn of_lang(code: &str) -> Self {
+        let base = code.split(['-', '_']).next().unwrap_or(code).to_ascii_lowercase();
+        match base.as_str() {
+            "ar" | "dv" | "fa" | "he" | "iw" | "ps" | "sd" | "ug" | "ur" | "yi" => TextDir::Rtl,
+            _ => TextDir::Ltr,
+        }
+    }
+}
+
+/// One language of a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubsTrack {
+    /// Name to show in a track picker
+    pub label: String,
+    pub dir: TextDir,
+    pub cues: Vec<Cue>,
+}
+
+/// Every subtitle track of a song, by language code.
+pub type SubsBundle = BTreeMap<String, SubsTrack>;
+
+/// Language code a subtitle file is named for: `transcript_jp.srt` -> `jp`,
+/// `Bad Apple.en.vtt` -> `en`; the whole file stem when there's no suffix.
+pub fn lang_from_path(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match stem.rsplit_once(['.', '_']) {
+        Some((_, lang)) if !lang.is_empty() => lang.to_string(),
+        _ => stem,
+    }
+}
+
+/// Display name of a language code, the code itself when it isn't known.
+pub fn lang_label(code: &str) -> String {
+    let label = match code.to_ascii_lowercase().as_str() {
+        "jp" | "ja" => "日本語",
+        "romaji" => "Romaji",
+        "en" => "English",
+        "es" => "Español",
+        "fr" => "Français",
+        "de" => "Deutsch",
+        "it" => "Italiano",
+        "pt" => "Português",
+        "ru" => "Русский",
+        "ko" => "한국어",
+        "zh" => "中文",
+        "ar" => "العربية",
+        "he" => "עברית",
+        _ => code,
+    };
+    label.to_string()
+}
+
+/// Reads the track of language `lang` from `path`: cue JSON as it is, any
+/// other subtitle file as `opts` says.
+pub fn read_subs_track<P: AsRef<Path>>(path: P, lang: &str, opts: &SubsOpts) -> Result<SubsTrack> {
+    let path = path.as_ref();
+    let cues = if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("json")) {
+        read_cues_json(path)?
+    } else {
+        read_cues_file(path, opts)?
+    };
+    Ok(SubsTrack {
+        label: lang_label(lang),
+        dir: TextDir::of_lang(lang),
+        cues,
+    })
+}
+
+/// Reads every `(lang, path)` track and writes them to `json_path` as one
+/// object keyed by language, so a client fetches a single file:
+///   { "en": { "label": "English", "dir": "ltr", "cues": [...] }, ... }
+pub fn write_subs_bundle<P: AsRef<Path>>(
+    tracks: &[(String, PathBuf)],
+    json_path: P,
+    opts: &SubsOpts,
+) -> Result<SubsBundle> {
+    let json_path = json_path.as_ref();
+    let mut bundle = SubsBundle::new();
+    for (lang, path) in tracks {
+        if bundle.contains_key(lang) {
+            return Err(BadAppleError::InvalidOpts(format!(
+                "two tracks for language {lang} ({})",
+                path.display()
+            )));
+        }
+        bundle.insert(lang.clone(), read_subs_track(path, lang, opts)?);
+    }
+
+    if let Some(parent) = json_path.parent() {
+        fs::create_dir_all(parent).map_err(BadAppleError::io("creating dir", parent))?;
+    }
+    let json = to_json(&bundle, opts.pretty).map_err(std::io::Error::from);
+    json.and_then(|json| fs::write(json_path, json))
+        .map_err(BadAppleError::io("writing JSON", json_path))?;
+    Ok(bundle)
+}
+
+/// "HH:MM:SS" plus milliseconds after `sep` (`,` for SRT, `.` for WebVTT).
+fn clock_ts(secs: f32, sep: char) -> String {
+    let ms = (secs.max(0.0) as f64 * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{sep}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// Cue text without blank lines, which would end an SRT or WebVTT cue early.
+fn cue_lines(t: &str) -> impl Iterator<Item = &str> {
+    t.lines().filter(|l| !l.trim().is_empty())
+}
+
+/// Cues as SRT, numbered from 1 in the order given. Furigana go back into the
+/// text as `漢字(かんじ)`.
+pub fn cues_to_srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    let mut n = 0;
+    for cue in cues {
+        let t = ruby_text(cue, str::to_string, ruby_source);
+        let text = cue_lines(&t).collect::<Vec<_>>().join("\n");
+        if text.is_empty() {
+            continue;
+        }
+        n += 1;
+        if n > 1 {
+            srt.push('\n');
+        }
+        srt.push_str(&format!(
+            "{n}\n{} --> {}\n{text}\n",
+            clock_ts(cue.s, ','),
+            clock_ts(cue.e.max(cue.s), ',')
+        ));
+    }
+    srt
+}
+
+/// Writes `cues` to `srt_path` as SRT (`cues_to_srt`).
+pub fn cues_to_srt_file<P: AsRef<Path>>(cues: &[Cue], srt_path: P) -> Result<()> {
+    let srt_path = srt_path.as_ref();
+    if let Some(parent) = srt_path.parent() {
+        fs::create_dir_all(parent).map_err(BadAppleError::io("creating dir", parent))?;
+    }
+    fs::write(srt_path, cues_to_srt(cues)).map_err(BadAppleError::io("writing SRT", srt_path))?;
+    Ok(())
+}
+
+/// Cues as a WebVTT document. Text is escaped (`&`, `<`, `>`), so markup
+/// kept in a cue shows up literally, and blank lines inside a cue, which
+/// would end it early, are dropped. Furigana become `<ruby>` spans.
+pub fn cues_to_vtt(cues: &[Cue]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    for cue in cues {
+        let t = ruby_text(cue, escape, |b, r| format!("<ruby>{b}<rt>{r}</rt></ruby>"));
+        let text = cue_lines(&t).collect::<Vec<_>>().join("\n");
+        if text.is_empty() {
+            continue;
+        }
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{text}\n",
+            clock_ts(cue.s, '.'),
+            clock_ts(cue.e.max(cue.s), '.')
+        ));
+    }
+    vtt
+}
+
+/// Writes `cues` to `vtt_path` as WebVTT (`cues_to_vtt`).
+pub fn cues_to_vtt_file<P: AsRef<Path>>(cues: &[Cue], vtt_path: P) -> Result<()> {
+    let vtt_path = vtt_path.as_ref();
+    if let Some(parent) = vtt_path.parent() {
+        fs::create_dir_all(parent).map_err(BadAppleError::io("creating dir", parent))?;
+    }
+    fs::write(vtt_path, cues_to_vtt(cues)).map_err(BadAppleError::io("writing WebVTT", vtt_path))?;
     Ok(())
 }
@@ -0,0 +1,210 @@
+// src/lib/term.rs
+// Renderers for playing payload frames in a terminal.
+//
+// Frames are resampled to a character grid (see `Renderer::fit_grid`), then
+// each cell encodes a block of pixels. For the text renderers "lit" cells are
+// the white (off) parts of the frame, drawn in the terminal's foreground
+// colour, so the silhouette shows as the dark background. Sixel draws real
+// black/white pixels, one character cell's worth of pixels per cell.
+
+use std::str::FromStr;
+
+use crate::raster::{resample, Fit};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Renderer {
+    /// Half blocks: 1×2 pixels per cell
+    #[default]
+    Blocks,
+    /// Braille dots: 2×4 pixels per cell
+    Braille,
+    /// DEC sixel graphics; `cell_px` is the terminal's character cell size in pixels
+    Sixel { cell_px: (usize, usize) },
+}
+
+/// Cell size assumed for sixel until the terminal reports its own.
+pub const DEFAULT_SIXEL_CELL_PX: (usize, usize) = (8, 16);
+
+/// Braille dot bit for pixel (dx, dy) within a 2×4 cell (U+2800 block layout).
+const BRAILLE_BITS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+impl FromStr for Renderer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blocks" => Ok(Renderer::Blocks),
+            "braille" => Ok(Renderer::Braille),
+            "sixel" => Ok(Renderer::Sixel {
+                cell_px: DEFAULT_SIXEL_CELL_PX,
+            }),
+            _ => Err(format!(
+                "unknown renderer {s:?} (expected blocks|braille|sixel)"
+            )),
+        }
+    }
+}
+
+impl Renderer {
+    /// Pixels covered by one character cell (columns, rows).
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            Renderer::Blocks => (1, 2),
+            Renderer::Braille => (2, 4),
+            Renderer::Sixel { cell_px } => cell_px,
+        }
+    }
+
+    /// Whether the output is plain text lines (as opposed to a sixel image).
+    pub fn is_text(self) -> bool {
+        !matches!(self, Renderer::Sixel { .. })
+    }
+
+    /// Uses the terminal's real cell size (from `width / columns`, `height / rows`), if known.
+    pub fn with_cell_px(self, cell_px: Option<(usize, usize)>) -> Self {
+        match (self, cell_px) {
+            (Renderer::Sixel { .. }, Some((w, h))) if w > 0 && h > 0 => {
+                Renderer::Sixel { cell_px: (w, h) }
+            }
+            _ => self,
+        }
+    }
+
+    /// Largest grid within `max_cols`×`max_rows` that keeps the frame's aspect ratio.
+    pub fn fit_grid(
+        self,
+        fw: usize,
+        fh: usize,
+        max_cols: usize,
+        max_rows: usize,
+    ) -> (usize, usize) {
+        let (cw, ch) = self.cell_size();
+        let (max_pw, max_ph) = (max_cols * cw, max_rows * ch);
+        if fw == 0 || fh == 0 || max_pw == 0 || max_ph == 0 {
+            return (0, 0);
+        }
+
+        let (pw, ph) = if fw * max_ph > max_pw * fh {
+            (max_pw, fh * max_pw / fw)
+        } else {
+            (fw * max_ph / fh, max_ph)
+        };
+        ((pw / cw).max(1), (ph / ch).max(1))
+    }
+
+    /// Renders a 1/0 `fw`×`fh` frame covering `cols`×`rows` character cells.
+    ///
+    /// Text lines are joined with `\r\n` so output also works in raw mode.
+    pub fn render(
+        self,
+        frame: &[u8],
+        fw: usize,
+        fh: usize,
+        cols: usize,
+        rows: usize,
+        invert: bool,
+    ) -> String {
+        let (cw, ch) = self.cell_size();
+        let (pw, ph) = (cols * cw, rows * ch);
+        let mut px = resample(frame, fw, fh, pw, ph, Fit::Stretch);
+
+        if !self.is_text() {
+            if invert {
+                px.iter_mut().for_each(|v| *v ^= 1);
+            }
+            return sixel_encode(&px, pw, ph);
+        }
+
+        if !invert {
+            // Light up the off (white) pixels
+            px.iter_mut().for_each(|v| *v ^= 1);
+        }
+
+        let mut out = String::with_capacity(rows * (cols * 3 + 2));
+        for row in 0..rows {
+            if row > 0 {
+                out.push_str("\r\n");
+            }
+            for col in 0..cols {
+                let (x, y) = (col * cw, row * ch);
+                let lit = |dx: usize, dy: usize| px[(y + dy) * pw + x + dx] != 0;
+                out.push(match self {
+                    Renderer::Blocks => match (lit(0, 0), lit(0, 1)) {
+                        (false, false) => ' ',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (true, true) => '█',
+                    },
+                    Renderer::Braille => {
+                        let mut bits = 0u32;
+                        for (dx, col_bits) in BRAILLE_BITS.iter().enumerate() {
+                            for (dy, &bit) in col_bits.iter().enumerate() {
+                                if lit(dx, dy) {
+                                    bits |= bit;
+                                }
+                            }
+                        }
+                        char::from_u32(0x2800 + bits).unwrap_or(' ')
+                    }
+                    Renderer::Sixel { .. } => unreachable!("sixel handled above"),
+                });
+            }
+        }
+        out
+    }
+}
+
+fn push_sixel_run(out: &mut String, ch: char, n: usize) {
+    match n {
+        0 => {}
+        1..=3 => (0..n).for_each(|_| out.push(ch)),
+        _ => {
+            out.push('!');
+            out.push_str(&n.to_string());
+            out.push(ch);
+        }
+    }
+}
+
+/// Encodes a 1/0 `w`×`h` buffer as a two-colour sixel image (1 = black, 0 = white).
+pub fn sixel_encode(px: &[u8], w: usize, h: usize) -> String {
+    let mut out = String::with_capacity(w * h / 3);
+    out.push_str("\x1bPq");
+    out.push_str(&format!("\"1;1;{w};{h}"));
+    // Registers: 0 = white, 1 = black (RGB percentages)
+    out.push_str("#0;2;100;100;100#1;2;0;0;0");
+
+    for band in (0..h).step_by(6) {
+        for color in 0..2u8 {
+            out.push('#');
+            out.push(char::from(b'0' + color));
+
+            let mut run_ch = '?';
+            let mut run = 0usize;
+            for x in 0..w {
+                let mut bits = 0u8;
+                for k in 0..6 {
+                    let y = band + k;
+                    if y < h && px[y * w + x] == color {
+                        bits |= 1 << k;
+                    }
+                }
+                let ch = char::from(63 + bits);
+                if ch == run_ch {
+                    run += 1;
+                } else {
+                    push_sixel_run(&mut out, run_ch, run);
+                    run_ch = ch;
+                    run = 1;
+                }
+            }
+            push_sixel_run(&mut out, run_ch, run);
+
+            // `$` returns to the band start for the next colour, `-` moves to the next band
+            out.push(if color == 0 { '$' } else { '-' });
+        }
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
@@ -0,0 +1,113 @@
+// src/lib/wasm.rs
+// wasm-bindgen player for the browser (feature "wasm"), built on decode.rs so
+// the web side runs the exact same decoder as the Rust tools.
+//
+//   const player = new Player(new Uint8Array(await (await fetch("rectFrames.bin")).arrayBuffer()));
+//   const rgba = new Uint8ClampedArray(player.width * player.height * 4);
+//   if (player.next_frame(rgba)) ctx.putImageData(new ImageData(rgba, player.width), 0, 0);
+
+use wasm_bindgen::prelude::*;
+
+use crate::decode::{decode, frame_to_rgba, rasterize_rects_into, Rect};
+use crate::rectframes::Payload;
+
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+#[wasm_bindgen]
+pub struct Player {
+    width: u32,
+    height: u32,
+    fps: u32,
+    frames: Vec<Vec<Rect>>,
+    cursor: usize,
+    pixels: Vec<u8>,
+}
+
+impl Player {
+    fn from_parts(width: u32, height: u32, fps: u32, frames: Vec<Vec<Rect>>) -> Self {
+        Self {
+            width,
+            height,
+            fps,
+            frames,
+            cursor: 0,
+            pixels: vec![0; width as usize * height as usize],
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Player {
+    /// Loads a binary ("BAPL") payload.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<Player, JsError> {
+        let (header, frames) = decode(bytes)?;
+        let frames = frames.collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_parts(
+            header.width,
+            header.height,
+            header.fps,
+            frames,
+        ))
+    }
+
+    /// Loads a rectFrames.json payload.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Player, JsError> {
+        let p = Payload::from_json(json).map_err(|e| JsError::new(&format!("{e:#}")))?;
+        Ok(Self::from_parts(p.width, p.height, p.fps, p.rect_frames))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn frames_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Index of the frame the next `next_frame` call renders.
+    #[wasm_bindgen(getter)]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn seek(&mut self, frame: usize) {
+        self.cursor = frame.min(self.frames.len());
+    }
+
+    /// Frame index for a playback time in seconds (e.g. `audio.currentTime`).
+    pub fn frame_at(&self, secs: f64) -> usize {
+        ((secs.max(0.0) * self.fps as f64) as usize).min(self.frames.len().saturating_sub(1))
+    }
+
+    /// Renders the frame at the cursor into `rgba` (width × height × 4 bytes,
+    /// black silhouette on white) and advances. Returns false once past the end.
+    pub fn next_frame(&mut self, rgba: &mut [u8]) -> Result<bool, JsError> {
+        let Some(rects) = self.frames.get(self.cursor) else {
+            return Ok(false);
+        };
+        if rgba.len() < self.pixels.len() * 4 {
+            return Err(JsError::new("RGBA buffer smaller than width × height × 4"));
+        }
+
+        let (w, h) = (self.width as usize, self.height as usize);
+        rasterize_rects_into(rects, w, h, &mut self.pixels);
+        frame_to_rgba(&self.pixels, BLACK, WHITE, rgba);
+        self.cursor += 1;
+        Ok(true)
+    }
+}